@@ -0,0 +1,78 @@
+/*
+* Copyright 2026 Jordan Castro <jordan@grupojvm.com>
+*
+* Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except in compliance with the License. You may obtain a copy of the License at
+*
+* http://www.apache.org/licenses/LICENSE-2.0
+*
+* Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+*
+*/
+// Read-only properties and live vars, with a NULL setter. Exits with 1 when a check fails.
+#include <stdio.h>
+#include <stdlib.h>
+
+#include "pixelscript.h"
+
+static int value = 0;
+
+static void free_nothing(void *ptr) {
+    (void)ptr;
+}
+
+static pxs_Var *get_score(pxs_Var *args, void *opaque) {
+    return pxs_newint(7);
+}
+
+static pxs_Var *get_count(pxs_Var *args, void *opaque) {
+    return pxs_newint(3);
+}
+
+static pxs_Var *get_x(pxs_Var *args, void *opaque) {
+    return pxs_newint(5);
+}
+
+static pxs_PixelType *counter_type = NULL;
+
+static pxs_Var *new_counter(pxs_Var *args, void *opaque) {
+    return pxs_newinstance(counter_type, &value, free_nothing);
+}
+
+static pxs_Var *new_point(pxs_Var *args, void *opaque) {
+    pxs_PixelObject *point = pxs_newobject(&value, free_nothing, "Point");
+    pxs_object_addprop(point, "x", get_x, NULL, NULL);
+    return pxs_newhost(point);
+}
+
+int main(void) {
+    pxs_initialize();
+
+    pxs_Module *module = pxs_newmod("c_test");
+    pxs_addlivevar(module, "score", get_score, NULL, NULL);
+    counter_type = pxs_newtype("Counter");
+    pxs_type_addprop(counter_type, "count", get_count, NULL, NULL);
+    pxs_addtype(module, counter_type);
+    pxs_addfunc(module, "new_counter", new_counter, NULL);
+    pxs_addfunc(module, "new_point", new_point, NULL);
+    pxs_addmod(module);
+
+    char *err = pxs_execlua(
+        "local t = require('c_test')\n"
+        "assert(t.score == 7, 'score')\n"
+        "assert(not pcall(function() t.score = 1 end), 'score is read-only')\n"
+        "local counter = t.new_counter()\n"
+        "assert(counter.count == 3, 'count')\n"
+        "assert(not pcall(function() counter.count = 1 end), 'count is read-only')\n"
+        "local point = t.new_point()\n"
+        "assert(point.x == 5, 'x')\n"
+        "assert(not pcall(function() point.x = 1 end), 'x is read-only')\n",
+        "<null_setter>");
+    int failed = err[0] != '\0';
+    if (failed) {
+        fprintf(stderr, "%s\n", err);
+    }
+    pxs_freestr(err);
+
+    pxs_finalize();
+    return failed;
+}
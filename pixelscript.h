@@ -7,38 +7,44 @@
 #include <stdbool.h>
 #include <stdint.h>
 #include <stdlib.h>
+typedef struct pxs_Var pxs_Var;
 
 /**
- * This represents the variable type that is being read or created.
+ * Runtime mask with every runtime.
  */
-typedef enum pxs_VarType {
-  pxs_Int64,
-  pxs_UInt64,
-  pxs_String,
-  pxs_Bool,
-  pxs_Float64,
+#define PXS_ALL_RUNTIMES UINT32_MAX
+
+/**
+ * Version of the API description, bumped when the format changes.
+ */
+#define PXS_API_VERSION 1
+
+/**
+ * Type of a parameter or return value in a signature.
+ */
+typedef enum pxs_ParamType {
   /**
-   * Lua (nil), Python (None), JS/easyjs (null/undefined)
+   * Anything.
    */
-  pxs_Null,
+  pxs_ParamAny,
+  pxs_ParamInt,
+  pxs_ParamFloat,
+  pxs_ParamBool,
+  pxs_ParamString,
   /**
-   * Lua (Tree), Python (Class), JS/easyjs (Prototype)
+   * A list. Tables and dicts without list items (`pxs_Object`) and array views match too.
    */
-  pxs_Object,
+  pxs_ParamList,
+  pxs_ParamFunction,
   /**
-   * Host object converted when created.
-   * Lua (Tree), Python (object), JS/easyjs (Prototype think '{}')
+   * A host object.
    */
-  pxs_HostObject,
+  pxs_ParamObject,
   /**
-   * Lua (Tree), Python (list), JS/easyjs (Array)
+   * Nothing, only for return values.
    */
-  pxs_List,
-  /**
-   * Lua (Value), Python (def or lambda), JS/easyjs (anon function)
-   */
-  pxs_Function,
-} pxs_VarType;
+  pxs_ParamNull,
+} pxs_ParamType;
 
 /**
  * Public enum for supported runtimes.
@@ -70,7 +76,160 @@ typedef enum pxs_Runtime {
   pxs_PHP,
 } pxs_Runtime;
 
-typedef struct Option_DeleterFn Option_DeleterFn;
+/**
+ * Operator kinds a PixelType can overload.
+ *
+ * Callback args per kind:
+ * - Binary (Add, Sub, Mul, Div, Eq, Lt, Le, Concat): `[runtime, object, other, reflected]`.
+ *   `reflected` is true when the object was the right hand operand (i.e. `2 * v`).
+ * - Unary (ToString, Len, Unm): `[runtime, object]`
+ * - Call: `[runtime, object, args...]`
+ * - Index: `[runtime, object, key]`
+ * - NewIndex: `[runtime, object, key, value]`
+ * - Next: `[runtime, object, state]`. `state` is null on the first call, then the state of the previous step.
+ *   Return a list `[state, value]` for the next item or null when done. `state` must not be null.
+ */
+typedef enum pxs_OperatorKind {
+  /**
+   * Lua (__add), Python (__add__, __radd__)
+   */
+  pxs_OpAdd,
+  /**
+   * Lua (__sub), Python (__sub__, __rsub__)
+   */
+  pxs_OpSub,
+  /**
+   * Lua (__mul), Python (__mul__, __rmul__)
+   */
+  pxs_OpMul,
+  /**
+   * Lua (__div), Python (__truediv__, __rtruediv__)
+   */
+  pxs_OpDiv,
+  /**
+   * Lua (__eq), Python (__eq__)
+   */
+  pxs_OpEq,
+  /**
+   * Lua (__lt), Python (__lt__)
+   */
+  pxs_OpLt,
+  /**
+   * Lua (__le), Python (__le__)
+   */
+  pxs_OpLe,
+  /**
+   * Lua (__tostring), Python (__str__, __repr__)
+   */
+  pxs_OpToString,
+  /**
+   * Lua (__len), Python (__len__)
+   */
+  pxs_OpLen,
+  /**
+   * Lua (__call), Python (__call__)
+   */
+  pxs_OpCall,
+  /**
+   * Lua (__index), Python (__getitem__)
+   *
+   * In Lua this is only called after methods and properties.
+   */
+  pxs_OpIndex,
+  /**
+   * Lua (__newindex), Python (__setitem__)
+   */
+  pxs_OpNewIndex,
+  /**
+   * Lua (__concat), Python has no concat operator.
+   */
+  pxs_OpConcat,
+  /**
+   * Lua (__unm), Python (__neg__)
+   */
+  pxs_OpUnm,
+  /**
+   * Lua (__pairs), Python (__iter__)
+   *
+   * In Lua `for state, value in pairs(obj)`, in Python `for value in obj`.
+   */
+  pxs_OpNext,
+} pxs_OperatorKind;
+
+/**
+ * Primitive types a field, or a element of a `pxs_VarArrayView`, can have.
+ */
+typedef enum pxs_FieldType {
+  /**
+   * `int32_t`
+   */
+  pxs_FieldI32,
+  /**
+   * `int64_t`
+   */
+  pxs_FieldI64,
+  /**
+   * `float`
+   */
+  pxs_FieldF32,
+  /**
+   * `double`
+   */
+  pxs_FieldF64,
+  /**
+   * `bool`
+   */
+  pxs_FieldBool,
+} pxs_FieldType;
+
+/**
+ * This represents the variable type that is being read or created.
+ */
+typedef enum pxs_VarType {
+  pxs_Int64,
+  pxs_UInt64,
+  pxs_String,
+  pxs_Bool,
+  pxs_Float64,
+  /**
+   * Lua (nil), Python (None), JS/easyjs (null/undefined)
+   */
+  pxs_Null,
+  /**
+   * Lua (Tree), Python (Class), JS/easyjs (Prototype)
+   */
+  pxs_Object,
+  /**
+   * Host object converted when created.
+   * Lua (Tree), Python (object), JS/easyjs (Prototype think '{}')
+   *
+   * Each var holds a reference. The object is freed once all vars and script references are gone.
+   */
+  pxs_HostObject,
+  /**
+   * Lua (Tree), Python (list), JS/easyjs (Array)
+   */
+  pxs_List,
+  /**
+   * Lua (Value), Python (def or lambda), JS/easyjs (anon function)
+   */
+  pxs_Function,
+  /**
+   * A error message. When returned from a callback it is raised in the script.
+   * Lua (error), Python (RuntimeError), JS/easyjs (Error)
+   */
+  pxs_Exception,
+  /**
+   * A typed view over a host buffer, nothing is copied.
+   * Lua (userdata, 1 based), Python (`_pxs_ArrayView`, 0 based)
+   */
+  pxs_ArrayView,
+  /**
+   * A instance of a value type, copied in and out of the scripts.
+   * Lua (plain table), Python (instance of the value class)
+   */
+  pxs_Value,
+} pxs_VarType;
 
 /**
  * A Module is a C representation of data that needs to be (imported,required, etc)
@@ -169,95 +328,61 @@ typedef struct pxs_Module pxs_Module;
 typedef struct pxs_PixelObject pxs_PixelObject;
 
 /**
- * Holds data for a pxs_Var of list.
- *
- * It holds multiple pxsVar within.
- *
- * When creating call:
+ * A PixelScript Type.
  *
- * `pixelscript_var_newlist()`.
+ * Holds the methods, properties and operators shared by every instance (`pxs_PixelObject`) of a host class.
+ * Callbacks are registered in the function lookup once per type, not per instance.
  *
- * To add items
- *
- * `pixelscript_var_list_add(list_ptr, item_ptr)`
- *
- * To get items
- *
- * `pixelscript_var_list_get(list_ptr, index)`
- *
- * A full example looks like:
+ * example:
  * ```c
- * // Create a new list (you never interact with pxs_VarList directly...)
- * pxs_Var* list = pixelscript_var_newlist();
- *
- * // Add a item
- * pxs_Var* number = pixelscript_var_newint(1);
- * pixelscript_var_list_add(list, number);
- *
- * // Get a item
- * pxs_Var* item_got = pixelscript_var_list_get(list, 0);
+ * pxs_PixelType* person = pxs_newtype("Person");
+ * pxs_type_setctor(person, new_person, NULL);
+ * pxs_type_addmethod(person, "set_name", person_set_name, NULL);
+ * pxs_type_addprop(person, "name", person_get_name, person_set_name, NULL);
+ * pxs_type_addstatic(person, "from_json", person_from_json, NULL);
+ * pxs_type_addconst(person, "MAX_AGE", pxs_newint(150));
+ * pxs_addtype(module, person);
+ *
+ * // Within the constructor callback
+ * return pxs_newinstance(person, p, free_person);
  * ```
+ *
+ * Types live until `pxs_finalize` or `pxs_clearstate`. Members can be added or replaced at any time,
+ * runtimes rebuild their class/metatable the next time the type is used.
  */
-typedef struct pxs_VarList pxs_VarList;
+typedef struct pxs_PixelType pxs_PixelType;
 
 /**
- * The Variables actual value union.
+ * Describes the parameters, return type and docs of a callback.
+ *
+ * The runtime is not part of the signature, parameters start at the first script argument.
+ *
+ * example:
+ * ```c
+ * pxs_Signature* sig = pxs_newsig("Print a line, `times` times.");
+ * pxs_sigaddparam(sig, "text", pxs_ParamString, NULL);
+ * pxs_sigaddparam(sig, "times", pxs_ParamInt, pxs_newint(1));
+ * pxs_addfunc_ex(module, "println", println, NULL, sig);
+ * ```
  */
-typedef union pxs_VarValue {
-  int64_t i64_val;
-  uint64_t u64_val;
-  char *string_val;
-  bool bool_val;
-  double f64_val;
-  const void *null_val;
-  void *object_val;
-  int32_t host_object_val;
-  struct pxs_VarList *list_val;
-  void *function_val;
-} pxs_VarValue;
+typedef struct pxs_Signature pxs_Signature;
 
 /**
- * A PixelScript Var(iable).
- *
- * This is the universal truth between all languages PixelScript supports.
+ * A PixelScript Value Type.
  *
- * Currently supports:
- * - int (i32, i64, u32, u64)
- * - float (f32, f64)
- * - string
- * - boolean
- * - Objects
- * - HostObjects (C structs acting as pseudo-classes) This in the Host can also be a Int or Uint.
- * - List
- * - Functions (First class functions)
+ * A small struct (Vec2, Color, Rect) copied into the scripts instead of living in the host as a object.
+ * Lua gets a plain table, Python a instance of a lightweight class. Scripts can create values locally with
+ * `Vec2(1, 2)`, the host is only involved when a value crosses back, where it is validated and packed.
  *
- * When working with objects you must use the C-api:
+ * example:
  * ```c
- * // Calls a method on a object.
- * pixelscript_object_call(var)
+ * const char* names[] = {"x", "y"};
+ * pxs_FieldType types[] = {pxs_FieldF64, pxs_FieldF64};
+ * pxs_ValueType* vec2 = pxs_newvaluetype("Vec2", names, types, 2);
+ * pxs_addvaluetype(module, vec2);
  * ```
- *
- * When using within a callback, if said callback was attached to a Class, the first *mut Var will be the class/object.
- *
- * When using ints or floats, if (i32, u32, u64, f32) there is no gurantee that the supported language uses
- * those types. Usually it defaults to i64 and f64.
- *
- * When creating a object, this is a bit tricky but essentially you have to first create a pointer via the pixel script runtime.
  */
-typedef struct pxs_Var {
-  /**
-   * A tag for the variable type.
-   */
-  enum pxs_VarType tag;
-  /**
-   * A value as a union.
-   */
-  union pxs_VarValue value;
-  /**
-   * Optional delete method. This is used for Pointers in Objects, and Functions.
-   */
-  struct Option_DeleterFn deleter;
-} pxs_Var;
+typedef struct pxs_ValueType pxs_ValueType;
 
 /**
  * Function reference used in C.
@@ -269,17 +394,23 @@ typedef struct pxs_Var {
  *
  * But if you use any Vars within the function, you will have to free them before the function returns.
  */
-typedef struct pxs_Var *(*pxs_Func)(struct pxs_Var *args, void *opaque);
+typedef pxs_Var *(*pxs_Func)(pxs_Var *args, void *opaque);
 
 typedef void *pxs_Opaque;
 
+/**
+ * A `pxs_Func` that can be NULL. Same as `Option<pxs_Func>`, written out so it is a plain function
+ * pointer in C.
+ */
+typedef pxs_Var *(*pxs_OptFunc)(pxs_Var *args, void *opaque);
+
 typedef void (*FreeMethod)(void *ptr);
 
 /**
  * Type Helper for a pxs_Var
  * Use this instead of writing out pxs_Var*
  */
-typedef struct pxs_Var *pxs_VarT;
+typedef pxs_Var *pxs_VarT;
 
 /**
  * Function Type for Loading a file.
@@ -291,6 +422,11 @@ typedef char *(*LoadFileFn)(const char *file_path);
  */
 typedef void (*WriteFileFn)(const char *file_path, const char *contents);
 
+/**
+ * Function Type for writing a file and reporting failures. Returns true when the file was written.
+ */
+typedef bool (*WriteFileExFn)(const char *file_path, const char *contents);
+
 /**
  * Type for DirHandle.
  *
@@ -312,6 +448,11 @@ typedef struct pxs_DirHandle {
  */
 typedef struct pxs_DirHandle (*ReadDirFn)(const char *dir_path);
 
+/**
+ * Function Type for resolving a unknown module. Returns a new module or null.
+ */
+typedef struct pxs_Module *(*ResolveModFn)(const char *name);
+
 #ifdef __cplusplus
 extern "C" {
 #endif // __cplusplus
@@ -364,6 +505,64 @@ struct pxs_Module *pxs_newmod(const char *name);
  */
 void pxs_addfunc(struct pxs_Module *module_ptr, const char *name, pxs_Func func, pxs_Opaque opaque);
 
+/**
+ * Add a callback to a module, only in some runtimes.
+ *
+ * `runtime_mask` is a mask of `1 << pxs_Runtime`, `(1 << pxs_Lua)` adds it to Lua only.
+ */
+void pxs_addfunc_for(struct pxs_Module *module_ptr,
+                     const char *name,
+                     pxs_Func func,
+                     pxs_Opaque opaque,
+                     uint32_t runtime_mask);
+
+/**
+ * Create a signature for `pxs_addfunc_ex`. `doc` can be NULL.
+ *
+ * Parameters are added with `pxs_sigaddparam`, in order. See `pxs_Signature`.
+ */
+struct pxs_Signature *pxs_newsig(const char *doc);
+
+/**
+ * Add a parameter to a signature.
+ *
+ * `default_value` is used when the script does not pass the argument, NULL makes it required.
+ * Ownership of the default is transfered.
+ *
+ * Defaults can be a int, float, bool, string or null. Returns false, and frees the default, for anything
+ * else. The parameter is not added then.
+ */
+bool pxs_sigaddparam(struct pxs_Signature *sig,
+                     const char *name,
+                     enum pxs_ParamType param_type,
+                     pxs_Var *default_value);
+
+/**
+ * Set if a signature takes more arguments after it's parameters.
+ */
+void pxs_sigsetvariadic(struct pxs_Signature *sig, bool variadic);
+
+/**
+ * Set the return type of a signature. Defaults to `pxs_ParamAny`.
+ */
+void pxs_sigsetreturn(struct pxs_Signature *sig, enum pxs_ParamType returns);
+
+/**
+ * Add a callback with a signature to a module.
+ *
+ * The signature is shown by `help(module.name)` and `module.name.__doc__` in Python and
+ * `pxs.doc(module.name)` in Lua. Transfers ownership of the signature, NULL is the same as `pxs_addfunc`.
+ *
+ * Arguments are checked before `func` is called, it can trust `args`: every parameter is there with it's
+ * type, missing optional ones hold their default. Ints are accepted for floats and whole floats for ints.
+ * Bad calls raise in the script, like `docs.greet() argument 2 'times': expected int, found str`.
+ */
+void pxs_addfunc_ex(struct pxs_Module *module_ptr,
+                    const char *name,
+                    pxs_Func func,
+                    pxs_Opaque opaque,
+                    struct pxs_Signature *sig);
+
 /**
  * Add a Varible to a module.
  *
@@ -371,14 +570,53 @@ void pxs_addfunc(struct pxs_Module *module_ptr, const char *name, pxs_Func func,
  *
  * Variable ownership is transfered.
  */
-void pxs_addvar(struct pxs_Module *module_ptr, const char *name, struct pxs_Var *variable);
+void pxs_addvar(struct pxs_Module *module_ptr, const char *name, pxs_Var *variable);
+
+/**
+ * Add a constant to a module.
+ *
+ * Like `pxs_addvar`, but scripts can not assign it even in a module that is not frozen.
+ *
+ * Variable ownership is transfered.
+ */
+void pxs_addconst(struct pxs_Module *module_ptr, const char *name, pxs_Var *variable);
+
+/**
+ * Set if scripts can assign attributes of a module.
+ *
+ * Modules are frozen by default: `main.MAX_PLAYERS = 1` raises. A module that is not frozen can be
+ * assigned, except it's constants. Live variables with a setter can always be assigned.
+ */
+void pxs_setmodfrozen(struct pxs_Module *module_ptr, bool frozen);
+
+/**
+ * Add a live variable to a module.
+ *
+ * Unlike `pxs_addvar` the value is not copied. Every read calls `getter` with `[runtime]` and every write
+ * calls `setter` with `[runtime, value]`. Pass NULL as setter for a read-only variable.
+ *
+ * ```lua
+ * local main = require("main")
+ * print(main.score)   -- calls the getter
+ * main.score = 10     -- calls the setter
+ * ```
+ */
+void pxs_addlivevar(struct pxs_Module *module_ptr,
+                    const char *name,
+                    pxs_Func getter,
+                    pxs_OptFunc setter,
+                    pxs_Opaque opaque);
 
 /**
  * Add a Module to a Module
  *
+ * The child is a attribute of the parent and can be loaded by it's full name, `require('pxs.math')` in Lua
+ * gives the same table as `pxs.math` and Python has `from pxs import math`.
+ *
  * This transfers ownership.
  */
-void pxs_add_submod(struct pxs_Module *parent_ptr, struct pxs_Module *child_ptr);
+void pxs_add_submod(struct pxs_Module *parent_ptr,
+                    struct pxs_Module *child_ptr);
 
 /**
  * Add the module finally to the runtime.
@@ -387,6 +625,47 @@ void pxs_add_submod(struct pxs_Module *parent_ptr, struct pxs_Module *child_ptr)
  */
 void pxs_addmod(struct pxs_Module *module_ptr);
 
+/**
+ * Add the module to some runtimes only.
+ *
+ * `runtime_mask` is a mask of `1 << pxs_Runtime`, `(1 << pxs_Lua) | (1 << pxs_Python)` for example.
+ * Internal modules follow their parent. Transfers ownership, like `pxs_addmod`.
+ */
+void pxs_addmod_for(struct pxs_Module *module_ptr, uint32_t runtime_mask);
+
+/**
+ * Remove a module from the runtime and release it's functions.
+ *
+ * Lua forgets the module in `package.preload` and `package.loaded`. In Python the module is emptied,
+ * pocketpy can not delete modules, so importing it gives a empty module. Internal modules are removed too.
+ *
+ * Returns false if no module of that name was added.
+ */
+bool pxs_removemod(const char *name);
+
+/**
+ * Replace a module of the same name, or add it if there is none.
+ *
+ * The old module is removed like `pxs_removemod`. Scripts that already imported it keep the old Lua table,
+ * Python reuses the module so existing imports see the new functions.
+ *
+ * Transfers ownership of the module, like `pxs_addmod`.
+ */
+void pxs_replacemod(struct pxs_Module *module_ptr);
+
+/**
+ * Add a module written in a runtime's language, from memory.
+ *
+ * Scripts `require`/`import` it like a file module, errors show the file it would have been loaded from:
+ * `helpers/math.lua` or `helpers/math.py` for `helpers.math`. Lua compiles it now and runs it on the first
+ * `require`, Python runs it now.
+ *
+ * Returns the error, a empty string on success. Free it with `pxs_freestr`.
+ */
+char *pxs_addsourcemod(enum pxs_Runtime runtime,
+                       const char *name,
+                       const char *source);
+
 /**
  * Optionally free a module if you changed your mind.
  */
@@ -405,12 +684,156 @@ struct pxs_PixelObject *pxs_newobject(pxs_Opaque ptr,
 
 /**
  * Add a callback to a object.
+ *
+ * Methods live on the object's type, so this only registers the callback the first time a
+ * instance of the type adds it. Later calls with the same name are ignored, use `pxs_type_addmethod`
+ * to replace a method. Prefer `pxs_newtype` + `pxs_type_addmethod`.
  */
 void pxs_object_addfunc(struct pxs_PixelObject *object_ptr,
                         const char *name,
                         pxs_Func callback,
                         pxs_Opaque opaque);
 
+/**
+ * Add a property to a object.
+ *
+ * The getter is called with `[runtime, object]` and returns the value.
+ * The setter is called with `[runtime, object, value]`. Pass NULL for a read-only property,
+ * assigning to it will raise a error in the script.
+ *
+ * Like `pxs_object_addfunc` this adds the property to the object's type once, later calls with the same
+ * name are ignored. `pxs_type_addprop` replaces it instead. Prefer `pxs_type_addprop`.
+ */
+void pxs_object_addprop(struct pxs_PixelObject *object_ptr,
+                        const char *name,
+                        pxs_Func getter,
+                        pxs_OptFunc setter,
+                        pxs_Opaque opaque);
+
+/**
+ * Add a operator overload to a object.
+ *
+ * See `pxs_OperatorKind` for the args each kind receives.
+ *
+ * Like `pxs_object_addfunc` this adds the operator to the object's type once, later calls with the same
+ * kind are ignored. `pxs_type_addop` replaces it instead. Prefer `pxs_type_addop`.
+ */
+void pxs_object_addop(struct pxs_PixelObject *object_ptr,
+                      enum pxs_OperatorKind kind,
+                      pxs_Func callback,
+                      pxs_Opaque opaque);
+
+/**
+ * Create a new type, or get the existing type with this name.
+ *
+ * A type holds the methods, properties and operators of a host class. They are registered once
+ * and shared by every instance created with `pxs_newinstance`.
+ *
+ * The type is owned by PixelScript, do not free it. It stays valid until `pxs_finalize` or `pxs_clearstate`.
+ */
+struct pxs_PixelType *pxs_newtype(const char *type_name);
+
+/**
+ * Add a method to a type.
+ *
+ * The callback is called with `[runtime, object, args...]`. Adding a method with the same name replaces it.
+ */
+void pxs_type_addmethod(struct pxs_PixelType *type_ptr,
+                        const char *name,
+                        pxs_Func callback,
+                        pxs_Opaque opaque);
+
+/**
+ * Add a property to a type.
+ *
+ * Same callbacks as `pxs_object_addprop`. Pass NULL as setter for a read-only property.
+ * Adding a property with the same name replaces it.
+ */
+void pxs_type_addprop(struct pxs_PixelType *type_ptr,
+                      const char *name,
+                      pxs_Func getter,
+                      pxs_OptFunc setter,
+                      pxs_Opaque opaque);
+
+/**
+ * Add a operator overload to a type.
+ *
+ * See `pxs_OperatorKind` for the args each kind receives. Adding a operator of the same kind replaces it.
+ */
+void pxs_type_addop(struct pxs_PixelType *type_ptr,
+                    enum pxs_OperatorKind kind,
+                    pxs_Func callback,
+                    pxs_Opaque opaque);
+
+/**
+ * Add a field to a type.
+ *
+ * The runtimes read and write the primitive at `ptr + offset` of the host object directly, there is no callback.
+ * Use it for hot data like positions and health.
+ *
+ * example:
+ * ```c
+ * typedef struct { float x; float y; int32_t health; } Player;
+ *
+ * pxs_object_addfield(player_type, "x", offsetof(Player, x), pxs_FieldF32, false);
+ * pxs_object_addfield(player_type, "health", offsetof(Player, health), pxs_FieldI32, true);
+ * ```
+ *
+ * Numbers are converted to the field type on write. Adding a field with the same name replaces it.
+ */
+void pxs_object_addfield(struct pxs_PixelType *type_ptr,
+                         const char *name,
+                         uintptr_t offset,
+                         enum pxs_FieldType field_type,
+                         bool readonly);
+
+/**
+ * Set the constructor of a type.
+ *
+ * The callback is called with `[runtime, args...]` when the type is called from a script, i.e.
+ * `Person("Jordan", 23)`, and should return `pxs_newinstance`.
+ */
+void pxs_type_setctor(struct pxs_PixelType *type_ptr, pxs_Func callback, pxs_Opaque opaque);
+
+/**
+ * Add a static method to a type.
+ *
+ * The callback is called with `[runtime, args...]`, like a module function. I.e. `Person.from_json(...)`.
+ */
+void pxs_type_addstatic(struct pxs_PixelType *type_ptr,
+                        const char *name,
+                        pxs_Func callback,
+                        pxs_Opaque opaque);
+
+/**
+ * Add a constant to a type. I.e. `Person.MAX_AGE`.
+ *
+ * This transfers ownership of the var.
+ */
+void pxs_type_addconst(struct pxs_PixelType *type_ptr, const char *name, pxs_Var *var);
+
+/**
+ * Create a new instance of a type.
+ *
+ * This should only be used within a PixelScript function callback. I.e. a constructor.
+ *
+ * `free_method` is called with `ptr` once the host and every script let go of the instance.
+ * Returns a HostObject var that can be returned directly from the callback.
+ */
+pxs_Var *pxs_newinstance(struct pxs_PixelType *type_ptr, pxs_Opaque ptr, FreeMethod free_method);
+
+/**
+ * Create a value type from a field schema. Replaces any value type of the same name.
+ *
+ * `names` and `types` hold `count` fields, in constructor order. See `pxs_ValueType`.
+ *
+ * The type lives until `pxs_finalize` or `pxs_clearstate`.
+ */
+struct pxs_ValueType *pxs_newvaluetype(const char *name,
+                                       const char *const *names,
+                                       const enum pxs_FieldType *types,
+                                       uintptr_t count);
+
 /**
  * Add a object to a Module.
  *
@@ -444,54 +867,126 @@ void pxs_addobject(struct pxs_Module *module_ptr,
                    pxs_Func object_constructor,
                    pxs_Opaque opaque);
 
+/**
+ * Add a type to a Module.
+ *
+ * Unlike `pxs_addobject` the type is exposed as a class. It is callable if it has a constructor, and has
+ * it's static methods and constants as fields.
+ *
+ * In Lua it is a callable table, with `Person.isinstance(value)` for type checks:
+ * ```lua
+ * local p = Person("Jordan", 23)
+ * local q = Person.from_json(data)
+ * print(Person.MAX_AGE, Person.isinstance(p))
+ * ```
+ *
+ * In Python it is a class:
+ * ```python
+ * p = Person("Jordan", 23)
+ * print(Person.MAX_AGE, isinstance(p, Person))
+ * ```
+ */
+void pxs_addtype(struct pxs_Module *module_ptr,
+                 struct pxs_PixelType *type_ptr);
+
+/**
+ * Add a value type to a Module.
+ *
+ * The type becomes a constructor taking the fields in order. Missing fields are zero.
+ *
+ * ```lua
+ * local v = Vec2(1, 2)  -- {x = 1, y = 2}
+ * v.x = v.x + 1
+ * ```
+ *
+ * ```python
+ * v = Vec2(1, 2)
+ * v.x += 1
+ * ```
+ */
+void pxs_addvaluetype(struct pxs_Module *module_ptr, struct pxs_ValueType *value_type_ptr);
+
+/**
+ * Add a enum to a Module.
+ *
+ * `names` and `values` hold `count` members. Members cross into the host as their value, but print their name.
+ *
+ * ```lua
+ * local d = Damage.Fire
+ * print(d, d.name, d.value)    -- Damage.Fire  Fire  1
+ * print(Damage[1] == d)        -- reverse lookup
+ * Damage.Fire = 3              -- error, enums are frozen
+ * ```
+ *
+ * ```python
+ * d = Damage.Fire
+ * print(d, d.name, d.value)    # Damage.Fire Fire 1
+ * print(Damage(1) == d, 1 in Damage)
+ * ```
+ */
+void pxs_addenum(struct pxs_Module *module_ptr,
+                 const char *name,
+                 const char *const *names,
+                 const int64_t *values,
+                 uintptr_t count);
+
 /**
  * Make a new Var string.
  */
-struct pxs_Var *pxs_newstring(const char *str);
+pxs_Var *pxs_newstring(const char *str);
 
 /**
  * Make a new Null var.
  */
-struct pxs_Var *pxs_newnull(void);
+pxs_Var *pxs_newnull(void);
+
+/**
+ * Make a new Exception var.
+ *
+ * When returned from a callback, the message is raised as a error in the calling script.
+ * A null `msg` makes a exception with a empty message.
+ */
+pxs_Var *pxs_newexception(const char *msg);
 
 /**
  * Make a new HostObject var.
  *
  * If not a valid pointer, will return null
  *
- * Transfers ownership
+ * Transfers ownership. The object is freed (via its `free_method`) once this var, any copies of it,
+ * and every script reference to it are gone.
  */
-struct pxs_Var *pxs_newhost(struct pxs_PixelObject *pixel_object);
+pxs_Var *pxs_newhost(struct pxs_PixelObject *pixel_object);
 
 /**
  * Create a new variable int. (i64)
  */
-struct pxs_Var *pxs_newint(int64_t val);
+pxs_Var *pxs_newint(int64_t val);
 
 /**
  * Create a new variable uint. (u64)
  */
-struct pxs_Var *pxs_newuint(uint64_t val);
+pxs_Var *pxs_newuint(uint64_t val);
 
 /**
  * Create a new variable bool.
  */
-struct pxs_Var *pxs_newbool(bool val);
+pxs_Var *pxs_newbool(bool val);
 
 /**
  * Create a new variable float. (f64)
  */
-struct pxs_Var *pxs_newfloat(double val);
+pxs_Var *pxs_newfloat(double val);
 
 /**
  * Call a function on a object, and use a Enum for runtime rather than a var.
  *
  * var is self.
  */
-struct pxs_Var *pxs_object_callrt(enum pxs_Runtime runtime,
-                                  struct pxs_Var *var,
-                                  const char *method,
-                                  struct pxs_Var *args);
+pxs_Var *pxs_object_callrt(enum pxs_Runtime runtime,
+                           pxs_Var *var,
+                           const char *method,
+                           pxs_Var *args);
 
 /**
  * Object call.
@@ -507,30 +1002,30 @@ struct pxs_Var *pxs_object_callrt(enum pxs_Runtime runtime,
  *     Var name = pxs_object_call()
  * ```
  */
-pxs_VarT pxs_objectcall(struct pxs_Var *runtime,
-                        struct pxs_Var *var,
+pxs_VarT pxs_objectcall(pxs_Var *runtime,
+                        pxs_Var *var,
                         const char *method,
-                        struct pxs_Var *args);
+                        pxs_Var *args);
 
 /**
  * Get a int (i64) from a var.
  */
-int64_t pxs_getint(struct pxs_Var *var);
+int64_t pxs_getint(pxs_Var *var);
 
 /**
  * Get a uint (u64)
  */
-uint64_t pxs_getuint(struct pxs_Var *var);
+uint64_t pxs_getuint(pxs_Var *var);
 
 /**
  * Get a float (f64)
  */
-double pxs_getfloat(struct pxs_Var *var);
+double pxs_getfloat(pxs_Var *var);
 
 /**
  * Get a Bool
  */
-bool pxs_getbool(struct pxs_Var *var);
+bool pxs_getbool(pxs_Var *var);
 
 /**
  * Get a String
@@ -539,24 +1034,34 @@ bool pxs_getbool(struct pxs_Var *var);
  *
  * You have to free this memory by calling `pxs_free_str`
  */
-char *pxs_getstring(struct pxs_Var *var);
+char *pxs_getstring(pxs_Var *var);
 
 /**
  * Get the pointer of the Host Object
  *
  * This is "potentially" dangerous.
  */
-pxs_Opaque pxs_gethost(struct pxs_Var *var);
+pxs_Opaque pxs_gethost(pxs_Var *var);
+
+/**
+ * Destroy a host object now, even if scripts still reference it.
+ *
+ * The object's `free_method` is called and any script still holding it gets a "object was destroyed" error on use.
+ * Returns false if the var is not a live host object.
+ *
+ * Does not take ownership of the var.
+ */
+bool pxs_destroyobject(pxs_Var *var);
 
 /**
- * Get the IDX of the PixelObject
+ * Get the handle of the PixelObject
  */
-int32_t pxs_getobject(struct pxs_Var *var);
+int64_t pxs_getobject(pxs_Var *var);
 
 /**
  * Check if a variable is of a type.
  */
-bool pxs_varis(struct pxs_Var *var, enum pxs_VarType var_type);
+bool pxs_varis(pxs_Var *var, enum pxs_VarType var_type);
 
 /**
  * Set a function for reading a file.
@@ -568,10 +1073,19 @@ void pxs_set_filereader(LoadFileFn func);
 /**
  * Set a function for writing a file.
  *
- * This is used to write files via pxs_json
+ * Used by `pxs_genstubs` and `pxs_genapi`. Paths use `/` and can be in directories that do not exist yet,
+ * the writer has to create them.
  */
 void pxs_set_filewriter(WriteFileFn func);
 
+/**
+ * Set a function for writing a file that returns true when the file was written.
+ *
+ * Like `pxs_set_filewriter`, but `pxs_genstubs` and `pxs_genapi` can report failed writes. Used instead of
+ * the `pxs_set_filewriter` writer when both are set.
+ */
+void pxs_set_filewriter_ex(WriteFileExFn func);
+
 /**
  * Set a function for reading a directory.
  *
@@ -579,12 +1093,49 @@ void pxs_set_filewriter(WriteFileFn func);
  */
 void pxs_set_dirreader(ReadDirFn func);
 
+/**
+ * Set a function for resolving modules on demand.
+ *
+ * Called with the full name (`"items.weapons"`) when `require`/`import` can not find a added module or
+ * a file. Return a new module to add it, like `pxs_addmod`, or null. The module should have the name it
+ * was asked for, it is added to every runtime so it is only resolved once.
+ */
+void pxs_setmodresolver(ResolveModFn func);
+
+/**
+ * Write editor stubs of every added module to `out_dir`, with the file writer.
+ *
+ * - `pxs_Python`: a `.pyi` per module, `name/__init__.pyi` for modules with internal modules.
+ * - `pxs_Lua`: a LuaLS `---@meta` file per module, `pxs.math.lua` for `pxs.math`.
+ * - `pxs_JavaScript`: a `.d.ts` per module, internal modules are namespaces.
+ *
+ * Functions added with `pxs_addfunc_ex` get their parameters, return type and doc. Python stubs of
+ * nested modules are in directories, see `pxs_set_filewriter`.
+ *
+ * Returns the number of files that could not be written, -1 if there is no file writer. Failed writes are only
+ * known with a writer set by `pxs_set_filewriter_ex`, otherwise this is 0.
+ */
+int32_t pxs_genstubs(enum pxs_Runtime runtime,
+                     const char *out_dir);
+
+/**
+ * Write a JSON description of every added module to `out_path`, with the file writer.
+ *
+ * The description has the modules, functions, variables, types and enums with their signatures and docs.
+ * `pxs-lsp` reads it to complete, document and check host symbols in Lua and Python files. Write it
+ * after adding the modules, and again when they change.
+ *
+ * Returns the number of modules described, -1 if there is no file writer or the file was not written, see
+ * `pxs_set_filewriter_ex`.
+ */
+int32_t pxs_genapi(const char *out_path);
+
 /**
  * Free a PixelScript var.
  *
  * You should only free results from `pxs_object_call`
  */
-void pxs_freevar(struct pxs_Var *var);
+void pxs_freevar(pxs_Var *var);
 
 /**
  * Tells PixelScript that we are in a new thread.
@@ -599,10 +1150,27 @@ void pxs_stopthread(void);
 /**
  * Clear the current threads state for all languages.
  *
+ * Added modules, types and objects are forgotten, add the modules again afterwards.
+ *
  * Optionally, if you want to run the garbage collector.
  */
 void pxs_clearstate(bool gc_collect);
 
+/**
+ * Instantiate a script class by name within a specified runtime. I.e. a script subclass of a host type.
+ *
+ * `class_name` can be a dotted path, i.e. `"mods.FireSword"` in Lua. Python looks it up in `__main__`.
+ *
+ * The returned object var keeps the instance alive until it is freed. Use `pxs_objectcall` on it to call
+ * methods, overridden methods run the script code and the rest fall through to the host callbacks.
+ * On failure a exception var holding the error is returned.
+ *
+ * Transfers ownership of args.
+ */
+pxs_Var *pxs_instantiate(pxs_Var *runtime,
+                         const char *class_name,
+                         pxs_Var *args);
+
 /**
  * Call a method within a specifed runtime.
  *
@@ -610,21 +1178,21 @@ void pxs_clearstate(bool gc_collect);
  *
  * Transfers ownership of args.
  */
-struct pxs_Var *pxs_call(struct pxs_Var *runtime, const char *method, struct pxs_Var *args);
+pxs_Var *pxs_call(pxs_Var *runtime, const char *method, pxs_Var *args);
 
 /**
  * Call a ToString method on this Var. If already a string, it won't call it.
  *
  * Host must free this memory with `pxs_free_var`
  */
-struct pxs_Var *pxs_tostring(struct pxs_Var *runtime, struct pxs_Var *var);
+pxs_Var *pxs_tostring(pxs_Var *runtime, pxs_Var *var);
 
 /**
  * Create a new pxs_VarList.
  *
  * This does not take any arguments. To add to a list, you must call `pxs_var_list_add(ptr, item)`
  */
-struct pxs_Var *pxs_newlist(void);
+pxs_Var *pxs_newlist(void);
 
 /**
  * Add a item to a pxs_VarList.
@@ -635,8 +1203,8 @@ struct pxs_Var *pxs_newlist(void);
  *
  * Will return the index added at.
  */
-int32_t pxs_listadd(struct pxs_Var *list,
-                    struct pxs_Var *item);
+int32_t pxs_listadd(pxs_Var *list,
+                    pxs_Var *item);
 
 /**
  * Get a item from a pxs_VarList.
@@ -645,8 +1213,8 @@ int32_t pxs_listadd(struct pxs_Var *list,
  *
  * This will NOT return a cloned variable, you must NOT free it.
  */
-struct pxs_Var *pxs_listget(struct pxs_Var *list,
-                            int32_t index);
+pxs_Var *pxs_listget(pxs_Var *list,
+                     int32_t index);
 
 /**
  * Set a item at a specific index in a pxs_VarList.
@@ -657,16 +1225,73 @@ struct pxs_Var *pxs_listget(struct pxs_Var *list,
  *
  * This will return a boolean for success = true, or failure = false.
  */
-bool pxs_listset(struct pxs_Var *list,
+bool pxs_listset(pxs_Var *list,
                  int32_t index,
-                 struct pxs_Var *item);
+                 pxs_Var *item);
 
 /**
  * Get length of a pxs_VarList.
  *
  * Expects a pointer to a pxs_VarList
  */
-int32_t pxs_listlen(struct pxs_Var *list);
+int32_t pxs_listlen(pxs_Var *list);
+
+/**
+ * Create a new array view over a host buffer.
+ *
+ * Scripts read and write the elements straight in `ptr`, nothing is copied. Lua indexes from 1, Python from 0.
+ * The buffer is NOT owned by the view, keep it alive while scripts can reach the view.
+ *
+ * example:
+ * ```c
+ * float positions[1024];
+ * pxs_Var* view = pxs_newarrayview(pxs_FieldF32, positions, 1024, false);
+ * ```
+ *
+ * ```lua
+ * for i = 1, #view do
+ *     view[i] = view[i] + 1.0
+ * end
+ * ```
+ *
+ * ```python
+ * for i in range(len(view)):
+ *     view[i] += 1.0
+ * ```
+ */
+pxs_Var *pxs_newarrayview(enum pxs_FieldType elem_type,
+                          void *ptr,
+                          uintptr_t len,
+                          bool readonly);
+
+/**
+ * Get the length of a array view. -1 if the var is not a array view.
+ */
+int64_t pxs_arrayviewlen(pxs_Var *view);
+
+/**
+ * Get the host buffer of a array view. NULL if the var is not a array view.
+ */
+void *pxs_arrayviewptr(pxs_Var *view);
+
+/**
+ * Create a new value of a value type. Every field is zero.
+ */
+pxs_Var *pxs_newvalue(struct pxs_ValueType *value_type_ptr);
+
+/**
+ * Get a field of a value. Returns a Int64, Float64 or Bool var, NULL if the field does not exist.
+ */
+pxs_Var *pxs_valueget(pxs_Var *value, const char *name);
+
+/**
+ * Set a field of a value. Numbers are converted to the field type.
+ *
+ * Does not take ownership of `field`. Returns false if the field does not exist or the type does not fit.
+ */
+bool pxs_valueset(pxs_Var *value,
+                  const char *name,
+                  pxs_Var *field);
 
 /**
  * Call a `pxs_Var`s function.
@@ -675,16 +1300,14 @@ int32_t pxs_listlen(struct pxs_Var *list);
  *
  * Transfers ownership of args.
  */
-struct pxs_Var *pxs_varcall(struct pxs_Var *runtime,
-                            struct pxs_Var *var_func,
-                            struct pxs_Var *args);
+pxs_Var *pxs_varcall(pxs_Var *runtime, pxs_Var *var_func, pxs_Var *args);
 
 /**
  * Copy the pxs_Var.
  *
  * Memory is handled by caller
  */
-struct pxs_Var *pxs_newcopy(struct pxs_Var *item);
+pxs_Var *pxs_newcopy(pxs_Var *item);
 
 /**
  * Call a objects getter.
@@ -0,0 +1,506 @@
+// Copyright 2026 Jordan Castro <jordan@grupojvm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+//
+use pixelscript::shared::pxs_Runtime;
+
+/// Languages of mod files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    Lua,
+    Python,
+}
+
+impl Language {
+    /// The language of a opened document, by it's language id or extension.
+    pub fn of_document(language_id: &str, uri: &str) -> Option<Self> {
+        match language_id {
+            "lua" => Some(Language::Lua),
+            "python" => Some(Language::Python),
+            _ if uri.ends_with(".lua") => Some(Language::Lua),
+            _ if uri.ends_with(".py") => Some(Language::Python),
+            _ => None,
+        }
+    }
+
+    pub fn runtime(&self) -> pxs_Runtime {
+        match self {
+            Language::Lua => pxs_Runtime::pxs_Lua,
+            Language::Python => pxs_Runtime::pxs_Python,
+        }
+    }
+
+    /// Name of the language in markdown code blocks.
+    pub fn id(&self) -> &'static str {
+        match self {
+            Language::Lua => "lua",
+            Language::Python => "python",
+        }
+    }
+
+    /// Can `c` separate the parts of a chain? `a.b`, and `a:b` in Lua.
+    fn is_separator(&self, c: u8) -> bool {
+        c == b'.' || (c == b':' && *self == Language::Lua)
+    }
+}
+
+/// A local name bound to a host module or symbol by a `require`/`import`.
+#[derive(Debug, Clone)]
+pub struct Alias {
+    pub name: String,
+    /// The dotted path it is bound to, `pxs.math`.
+    pub path: String,
+    /// Byte offset of the imported name, for `from x import name`.
+    pub offset: Option<usize>,
+}
+
+/// A `a.b.c` expression, with the byte offset of every part.
+#[derive(Debug, Clone)]
+pub struct Chain {
+    pub parts: Vec<(String, usize)>,
+}
+
+impl Chain {
+    pub fn names(&self) -> Vec<&str> {
+        self.parts.iter().map(|(name, _)| name.as_str()).collect()
+    }
+}
+
+/// A opened Lua or Python file.
+pub struct Document {
+    pub language: Language,
+    pub text: String,
+    /// The text with comments and the contents of strings blanked. Same byte offsets as `text`.
+    pub code: Vec<u8>,
+    /// Byte offset where each line starts.
+    line_starts: Vec<usize>,
+    pub aliases: Vec<Alias>,
+}
+
+fn is_ident(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || c == b'_' || c >= 0x80
+}
+
+impl Document {
+    pub fn new(language: Language, text: String) -> Self {
+        let code = match language {
+            Language::Lua => blank_lua(text.as_bytes()),
+            Language::Python => blank_python(text.as_bytes()),
+        };
+        let mut line_starts = vec![0];
+        line_starts.extend(
+            text.bytes()
+                .enumerate()
+                .filter(|(_, c)| *c == b'\n')
+                .map(|(i, _)| i + 1),
+        );
+
+        let mut document = Document {
+            language,
+            text,
+            code,
+            line_starts,
+            aliases: vec![],
+        };
+        document.aliases = match language {
+            Language::Lua => document.lua_aliases(),
+            Language::Python => document.python_aliases(),
+        };
+        document
+    }
+
+    /// Byte offset of a LSP position. `character` counts UTF-16 units.
+    pub fn offset(&self, line: usize, character: usize) -> usize {
+        let Some(start) = self.line_starts.get(line).copied() else {
+            return self.text.len();
+        };
+        let end = self
+            .line_starts
+            .get(line + 1)
+            .map(|e| e - 1)
+            .unwrap_or(self.text.len());
+        let mut units = 0;
+        for (i, c) in self.text[start..end].char_indices() {
+            if units >= character {
+                return start + i;
+            }
+            units += c.len_utf16();
+        }
+        end
+    }
+
+    /// LSP position of a byte offset.
+    pub fn position(&self, offset: usize) -> (usize, usize) {
+        let line = self.line_starts.partition_point(|start| *start <= offset) - 1;
+        let start = self.line_starts[line];
+        let character = self.text[start..offset]
+            .chars()
+            .map(|c| c.len_utf16())
+            .sum();
+        (line, character)
+    }
+
+    /// The bound alias of a name, the last one wins.
+    pub fn alias(&self, name: &str) -> Option<&Alias> {
+        self.aliases.iter().rev().find(|a| a.name == name)
+    }
+
+    /// Identifier ending at `end`.
+    fn ident_before(&self, end: usize) -> (String, usize) {
+        let mut start = end;
+        while start > 0 && is_ident(self.code[start - 1]) {
+            start -= 1;
+        }
+        (self.text[start..end].to_string(), start)
+    }
+
+    /// The chain ending at `end`, the last part can be empty (`pxs.`). None when it does not start
+    /// with a name, like `get().name`.
+    pub fn chain_before(&self, end: usize) -> Option<Chain> {
+        let mut parts = vec![self.ident_before(end)];
+        loop {
+            let start = parts.last().unwrap().1;
+            if start == 0 || !self.language.is_separator(self.code[start - 1]) {
+                break;
+            }
+            let (name, name_start) = self.ident_before(start - 1);
+            if name.is_empty() {
+                // `"a"..pxs.math` in Lua is a chain after a concat.
+                if self.language == Language::Lua && start >= 2 && self.code[start - 2] == b'.' {
+                    break;
+                }
+                return None;
+            }
+            parts.push((name, name_start));
+        }
+        parts.reverse();
+        let head = &parts[0].0;
+        if head.as_bytes().first().is_some_and(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        Some(Chain { parts })
+    }
+
+    /// The chain of the identifier at `offset`, up to the end of that identifier.
+    pub fn chain_at(&self, offset: usize) -> Option<Chain> {
+        let mut end = offset.min(self.code.len());
+        while end < self.code.len() && is_ident(self.code[end]) {
+            end += 1;
+        }
+        self.chain_before(end)
+            .filter(|chain| !chain.parts.last().unwrap().0.is_empty())
+    }
+
+    /// The call `offset` is in: the byte offset of it's `(` and the index of the argument.
+    pub fn call_at(&self, offset: usize) -> Option<(usize, usize)> {
+        let mut depth = 0;
+        let mut commas = 0;
+        for i in (0..offset.min(self.code.len())).rev() {
+            match self.code[i] {
+                b')' | b']' | b'}' => depth += 1,
+                b'(' if depth == 0 => return Some((i, commas)),
+                b'[' | b'{' if depth == 0 => return None,
+                b'(' | b'[' | b'{' => depth -= 1,
+                b',' if depth == 0 => commas += 1,
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// Every chain in the document.
+    pub fn chains(&self) -> Vec<Chain> {
+        let mut chains = vec![];
+        let mut i = 0;
+        while i < self.code.len() {
+            let starts_name = is_ident(self.code[i]) && (i == 0 || !is_ident(self.code[i - 1]));
+            let after_separator = i > 0 && self.language.is_separator(self.code[i - 1]);
+            let after_concat =
+                i > 1 && self.language == Language::Lua && &self.code[i - 2..i] == b"..";
+            if !starts_name || (after_separator && !after_concat) {
+                i += 1;
+                continue;
+            }
+
+            // Walk forward over `name.name.name`.
+            let mut end = i;
+            loop {
+                while end < self.code.len() && is_ident(self.code[end]) {
+                    end += 1;
+                }
+                let has_next = end + 1 < self.code.len()
+                    && self.language.is_separator(self.code[end])
+                    && is_ident(self.code[end + 1])
+                    && !self.code[end + 1].is_ascii_digit();
+                if !has_next {
+                    break;
+                }
+                end += 1;
+            }
+            if let Some(chain) = self.chain_before(end) {
+                chains.push(chain);
+            }
+            i = end.max(i + 1);
+        }
+        chains
+    }
+
+    /// Is the chain ending at `end` assigned to? `a.b = 1`, not `a.b == 1`.
+    pub fn is_assigned(&self, end: usize) -> bool {
+        let rest = &self.code[end.min(self.code.len())..];
+        let rest: Vec<u8> = rest
+            .iter()
+            .copied()
+            .skip_while(|c| *c == b' ' || *c == b'\t')
+            .take(2)
+            .collect();
+        rest.first() == Some(&b'=') && rest.get(1) != Some(&b'=')
+    }
+
+    /// The text of the line before `offset`.
+    pub fn line_before(&self, offset: usize) -> &str {
+        let line = self.line_starts.partition_point(|start| *start <= offset) - 1;
+        &self.text[self.line_starts[line]..offset]
+    }
+
+    /// `local name = require("path")` and `name = require "path"`.
+    fn lua_aliases(&self) -> Vec<Alias> {
+        let mut aliases = vec![];
+        let word = b"require";
+        let mut i = 0;
+        while i + word.len() <= self.code.len() {
+            let found = &self.code[i..i + word.len()] == word
+                && (i == 0 || !is_ident(self.code[i - 1]))
+                && self.code.get(i + word.len()).is_none_or(|c| !is_ident(*c));
+            if !found {
+                i += 1;
+                continue;
+            }
+
+            // Backwards: `name =`.
+            let mut j = i;
+            while j > 0 && matches!(self.code[j - 1], b' ' | b'\t') {
+                j -= 1;
+            }
+            let assigned = j > 0
+                && self.code[j - 1] == b'='
+                && (j < 2 || !b"=~<>".contains(&self.code[j - 2]));
+            let name = if assigned {
+                j -= 1;
+                while j > 0 && matches!(self.code[j - 1], b' ' | b'\t') {
+                    j -= 1;
+                }
+                self.ident_before(j).0
+            } else {
+                String::new()
+            };
+
+            // Forwards: `("path")` or `"path"`.
+            let mut k = i + word.len();
+            while k < self.code.len() && matches!(self.code[k], b' ' | b'\t' | b'(') {
+                k += 1;
+            }
+            if !name.is_empty()
+                && let Some(quote) = self
+                    .code
+                    .get(k)
+                    .copied()
+                    .filter(|q| *q == b'"' || *q == b'\'')
+                && let Some(len) = self.text[k + 1..].find(quote as char)
+            {
+                aliases.push(Alias {
+                    name,
+                    path: self.text[k + 1..k + 1 + len].to_string(),
+                    offset: None,
+                });
+            }
+            i += word.len();
+        }
+        aliases
+    }
+
+    /// `import a`, `import a.b as c` and `from a import b as c, d`.
+    fn python_aliases(&self) -> Vec<Alias> {
+        let mut aliases = vec![];
+        let code = String::from_utf8_lossy(&self.code).into_owned();
+        let mut offset = 0;
+        let mut lines = code.split_inclusive('\n');
+        while let Some(line) = lines.next() {
+            let line_offset = offset;
+            offset += line.len();
+            let statement = line.trim_start();
+            let indent = line.len() - statement.len();
+
+            if let Some(names) = statement.strip_prefix("import ") {
+                for name in names.split(',') {
+                    let mut words = name.split_whitespace();
+                    let (Some(path), alias) = (words.next(), words.nth(1)) else {
+                        continue;
+                    };
+                    // `import a.b` binds `a`.
+                    let (alias, path) = match alias {
+                        Some(alias) => (alias, path),
+                        None => (
+                            path.split('.').next().unwrap(),
+                            path.split('.').next().unwrap(),
+                        ),
+                    };
+                    aliases.push(Alias {
+                        name: alias.to_string(),
+                        path: path.to_string(),
+                        offset: None,
+                    });
+                }
+            } else if let Some(rest) = statement.strip_prefix("from ") {
+                let Some((module, names)) = rest.split_once(" import ") else {
+                    continue;
+                };
+                let module = module.trim();
+                if module.starts_with('.') {
+                    continue;
+                }
+                let names_offset = line_offset
+                    + indent
+                    + "from ".len()
+                    + rest.find(" import ").unwrap()
+                    + " import ".len();
+
+                // `from a import (b,\n c)` continues until the `)`.
+                let mut names = names.to_string();
+                if names.trim_start().starts_with('(') {
+                    while !names.contains(')') {
+                        let Some(next) = lines.next() else {
+                            break;
+                        };
+                        offset += next.len();
+                        names.push_str(next);
+                    }
+                }
+
+                let mut name_offset = names_offset;
+                for name in names.split(',') {
+                    let start = name_offset;
+                    name_offset += name.len() + 1;
+                    let trimmed =
+                        name.trim_matches(|c: char| c.is_whitespace() || c == '(' || c == ')');
+                    let mut words = trimmed.split_whitespace();
+                    let (Some(member), alias) = (words.next(), words.nth(1)) else {
+                        continue;
+                    };
+                    if member == "*" {
+                        continue;
+                    }
+                    aliases.push(Alias {
+                        name: alias.unwrap_or(member).to_string(),
+                        path: format!("{module}.{member}"),
+                        offset: Some(start + name.find(member).unwrap()),
+                    });
+                }
+            }
+        }
+        aliases
+    }
+}
+
+/// Blank `from..to` except newlines.
+fn blank(code: &mut [u8], from: usize, to: usize) {
+    let to = to.min(code.len());
+    for c in code[from.min(to)..to].iter_mut() {
+        if *c != b'\n' {
+            *c = b' ';
+        }
+    }
+}
+
+/// The end of a quoted string starting at `start`, after the closing quote.
+fn quoted_end(text: &[u8], start: usize) -> usize {
+    let quote = text[start];
+    let mut i = start + 1;
+    while i < text.len() && text[i] != quote && text[i] != b'\n' {
+        if text[i] == b'\\' {
+            i += 1;
+        }
+        i += 1;
+    }
+    (i + 1).min(text.len())
+}
+
+/// The level of a Lua long bracket at `start`: `[[` is 0, `[==[` is 2.
+fn long_bracket(text: &[u8], start: usize) -> Option<usize> {
+    if text.get(start) != Some(&b'[') {
+        return None;
+    }
+    let level = text[start + 1..].iter().take_while(|c| **c == b'=').count();
+    (text.get(start + 1 + level) == Some(&b'[')).then_some(level)
+}
+
+/// The end of a Lua long bracket of `level` opened before `from`.
+fn long_bracket_end(text: &[u8], from: usize, level: usize) -> usize {
+    let close = format!("]{}]", "=".repeat(level));
+    find(text, from, close.as_bytes())
+        .map(|i| i + close.len())
+        .unwrap_or(text.len())
+}
+
+fn find(text: &[u8], from: usize, needle: &[u8]) -> Option<usize> {
+    text.get(from..)?
+        .windows(needle.len())
+        .position(|w| w == needle)
+        .map(|i| i + from)
+}
+
+fn blank_lua(text: &[u8]) -> Vec<u8> {
+    let mut code = text.to_vec();
+    let mut i = 0;
+    while i < text.len() {
+        if text[i..].starts_with(b"--") {
+            let end = match long_bracket(text, i + 2) {
+                Some(level) => long_bracket_end(text, i + 2, level),
+                None => find(text, i, b"\n").unwrap_or(text.len()),
+            };
+            blank(&mut code, i, end);
+            i = end;
+        } else if let Some(level) = long_bracket(text, i) {
+            let open = level + 2;
+            let end = long_bracket_end(text, i + open, level);
+            blank(&mut code, i + open, end.saturating_sub(open));
+            i = end;
+        } else if text[i] == b'"' || text[i] == b'\'' {
+            let end = quoted_end(text, i);
+            blank(&mut code, i + 1, end - 1);
+            i = end;
+        } else {
+            i += 1;
+        }
+    }
+    code
+}
+
+fn blank_python(text: &[u8]) -> Vec<u8> {
+    let mut code = text.to_vec();
+    let mut i = 0;
+    while i < text.len() {
+        if text[i] == b'#' {
+            let end = find(text, i, b"\n").unwrap_or(text.len());
+            blank(&mut code, i, end);
+            i = end;
+        } else if text[i..].starts_with(b"\"\"\"") || text[i..].starts_with(b"'''") {
+            let end = find(text, i + 3, &text[i..i + 3])
+                .map(|e| e + 3)
+                .unwrap_or(text.len());
+            blank(&mut code, i + 3, end.saturating_sub(3));
+            i = end;
+        } else if text[i] == b'"' || text[i] == b'\'' {
+            let end = quoted_end(text, i);
+            blank(&mut code, i + 1, end - 1);
+            i = end;
+        } else {
+            i += 1;
+        }
+    }
+    code
+}
@@ -0,0 +1,430 @@
+// Copyright 2026 Jordan Castro <jordan@grupojvm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+//
+use pixelscript::shared::{
+    api::{ApiKind, ApiSymbol},
+    json::Json,
+    signature::default_repr,
+};
+
+use crate::document::{Chain, Document, Language};
+
+/// LSP `DiagnosticSeverity.Warning`.
+const SEVERITY_WARNING: f64 = 2.0;
+
+/// The host modules described by the library.
+#[derive(Default)]
+pub struct Api {
+    pub modules: Vec<ApiSymbol>,
+}
+
+impl Api {
+    /// Find a symbol by it's dotted path, `pxs.math.add`.
+    pub fn find(&self, path: &str, language: Language) -> Option<&ApiSymbol> {
+        // Module names can have dots, the longest one wins.
+        let mut roots: Vec<&ApiSymbol> = self
+            .modules
+            .iter()
+            .filter(|m| m.is_for(language.runtime()))
+            .filter(|m| path == m.name || path.starts_with(&format!("{}.", m.name)))
+            .collect();
+        roots.sort_by_key(|m| std::cmp::Reverse(m.name.len()));
+        let root = roots.first()?;
+
+        let mut symbol = *root;
+        let rest = &path[root.name.len()..];
+        for name in rest.split('.').filter(|n| !n.is_empty()) {
+            symbol = symbol
+                .member(name)
+                .filter(|m| m.is_for(language.runtime()))?;
+        }
+        Some(symbol)
+    }
+
+    /// Resolve the names of a chain through the aliases of a document. Returns the symbol and it's path.
+    pub fn resolve(&self, document: &Document, names: &[&str]) -> Option<(&ApiSymbol, String)> {
+        let (head, rest) = names.split_first()?;
+        let alias = document.alias(head)?;
+        let mut path = alias.path.clone();
+        for name in rest {
+            path.push('.');
+            path.push_str(name);
+        }
+        self.find(&path, document.language)
+            .map(|symbol| (symbol, path))
+    }
+}
+
+/// Can the members of this symbol be listed?
+fn has_members(symbol: &ApiSymbol) -> bool {
+    matches!(
+        symbol.kind,
+        ApiKind::Module | ApiKind::Class | ApiKind::Enum
+    )
+}
+
+/// LSP `CompletionItemKind`.
+fn completion_kind(kind: ApiKind) -> f64 {
+    match kind {
+        ApiKind::Module => 9.0,
+        ApiKind::Function => 3.0,
+        ApiKind::Method => 2.0,
+        ApiKind::Variable => 6.0,
+        ApiKind::Constant => 21.0,
+        ApiKind::Class => 7.0,
+        ApiKind::Enum => 13.0,
+        ApiKind::EnumMember => 20.0,
+        ApiKind::Field => 5.0,
+    }
+}
+
+/// The one line description of a symbol. `path` is it's full dotted path.
+pub fn detail(symbol: &ApiSymbol, path: &str, language: Language) -> String {
+    let runtime = language.runtime();
+    let name = &symbol.name;
+    match symbol.kind {
+        ApiKind::Module => format!("module {path}"),
+        ApiKind::Enum => format!("enum {path}"),
+        ApiKind::Function | ApiKind::Method | ApiKind::Class => match symbol.signature.as_ref() {
+            Some(signature) => signature.signature_line(name, runtime),
+            None if symbol.kind == ApiKind::Class => format!("class {name}"),
+            None if language == Language::Lua => format!("{name}(...) -> any"),
+            None => format!("{name}(*args) -> Any"),
+        },
+        ApiKind::Variable | ApiKind::Field => {
+            format!("{name}: {}", symbol.value_type.type_name(runtime))
+        }
+        ApiKind::Constant | ApiKind::EnumMember => {
+            let mut line = format!("{name}: {}", symbol.value_type.type_name(runtime));
+            if let Some(value) = symbol.value.as_ref() {
+                line.push_str(&format!(" = {}", default_repr(value, runtime)));
+            }
+            line
+        }
+    }
+}
+
+fn doc(symbol: &ApiSymbol) -> &str {
+    symbol
+        .signature
+        .as_ref()
+        .map(|s| s.doc.as_str())
+        .unwrap_or_default()
+}
+
+fn completion_item(symbol: &ApiSymbol, path: &str, language: Language) -> Json {
+    let mut pairs = vec![
+        ("label", Json::string(&symbol.name)),
+        ("kind", Json::Number(completion_kind(symbol.kind))),
+        ("detail", Json::string(&detail(symbol, path, language))),
+    ];
+    if !doc(symbol).is_empty() {
+        pairs.push(("documentation", Json::string(doc(symbol))));
+    }
+    Json::object(pairs)
+}
+
+/// The members of `symbol` as completion items.
+fn member_items(symbol: &ApiSymbol, path: &str, language: Language) -> Vec<Json> {
+    symbol
+        .members
+        .iter()
+        .filter(|m| m.is_for(language.runtime()))
+        .map(|m| completion_item(m, &format!("{path}.{}", m.name), language))
+        .collect()
+}
+
+/// Module names after `require("` or `import `, and members after `from x import `.
+fn import_items(api: &Api, document: &Document, offset: usize) -> Option<Vec<Json>> {
+    let line = document.line_before(offset);
+    let language = document.language;
+    let module_items = |prefix: &str| {
+        let mut items = vec![];
+        let mut stack: Vec<(&ApiSymbol, String)> =
+            api.modules.iter().map(|m| (m, m.name.clone())).collect();
+        while let Some((module, path)) = stack.pop() {
+            if module.kind != ApiKind::Module || !module.is_for(language.runtime()) {
+                continue;
+            }
+            if path.starts_with(prefix) {
+                let mut item = completion_item(module, &path, language);
+                if let Json::Object(pairs) = &mut item {
+                    pairs[0].1 = Json::string(&path);
+                }
+                items.push(item);
+            }
+            stack.extend(
+                module
+                    .members
+                    .iter()
+                    .map(|m| (m, format!("{path}.{}", m.name))),
+            );
+        }
+        items
+    };
+
+    match language {
+        Language::Lua => {
+            let start = line.rfind("require")?;
+            let rest = line[start + "require".len()..].trim_start_matches([' ', '(']);
+            let prefix = rest.strip_prefix(['"', '\''])?;
+            (!prefix.contains(['"', '\'', ')'])).then(|| module_items(prefix))
+        }
+        Language::Python => {
+            let statement = line.trim_start();
+            if let Some(rest) = statement.strip_prefix("from ") {
+                match rest.split_once(" import ") {
+                    Some((module, _)) => {
+                        let module = module.trim();
+                        let symbol = api.find(module, language).filter(|s| has_members(s))?;
+                        Some(member_items(symbol, module, language))
+                    }
+                    None if !rest.contains(' ') => Some(module_items(rest)),
+                    None => None,
+                }
+            } else if let Some(rest) = statement.strip_prefix("import ") {
+                let prefix = rest.rsplit(',').next().unwrap_or_default().trim_start();
+                (!prefix.contains(' ')).then(|| module_items(prefix))
+            } else {
+                None
+            }
+        }
+    }
+}
+
+/// Completion items at `offset`.
+pub fn completion(api: &Api, document: &Document, offset: usize) -> Vec<Json> {
+    if let Some(items) = import_items(api, document, offset) {
+        return items;
+    }
+    let Some(chain) = document.chain_before(offset) else {
+        return vec![];
+    };
+    let names = chain.names();
+    if names.len() == 1 {
+        // The names bound to host modules and symbols.
+        return document
+            .aliases
+            .iter()
+            .filter(|a| a.name.starts_with(names[0]))
+            .filter_map(|a| {
+                let symbol = api.find(&a.path, document.language)?;
+                let mut item = completion_item(symbol, &a.path, document.language);
+                if let Json::Object(pairs) = &mut item {
+                    pairs[0].1 = Json::string(&a.name);
+                }
+                Some(item)
+            })
+            .collect();
+    }
+
+    match api.resolve(document, &names[..names.len() - 1]) {
+        Some((symbol, path)) if has_members(symbol) => {
+            member_items(symbol, &path, document.language)
+        }
+        _ => vec![],
+    }
+}
+
+/// Hover markdown of the symbol at `offset`.
+pub fn hover(api: &Api, document: &Document, offset: usize) -> Option<String> {
+    let chain = document.chain_at(offset)?;
+    // Only the chain up to the hovered part.
+    let part = chain
+        .parts
+        .iter()
+        .rposition(|(_, start)| *start <= offset)?;
+    let names = &chain.names()[..=part];
+
+    let (symbol, path) = match api.resolve(document, names) {
+        Some(resolved) => resolved,
+        // `from a import b` names are bound in place.
+        None => {
+            let alias = document
+                .aliases
+                .iter()
+                .find(|a| a.offset == Some(chain.parts[part].1))?;
+            (
+                api.find(&alias.path, document.language)?,
+                alias.path.clone(),
+            )
+        }
+    };
+
+    let language = document.language;
+    let mut markdown = format!(
+        "```{}\n{}\n```",
+        language.id(),
+        detail(symbol, &path, language)
+    );
+    if !doc(symbol).is_empty() {
+        markdown.push_str(&format!("\n\n{}", doc(symbol)));
+    }
+    Some(markdown)
+}
+
+/// Signature help of the call at `offset`.
+pub fn signature_help(api: &Api, document: &Document, offset: usize) -> Option<Json> {
+    let (open, argument) = document.call_at(offset)?;
+    let chain = document.chain_before(open)?;
+    let (symbol, _) = api.resolve(document, &chain.names())?;
+    let signature = symbol.signature.as_ref()?;
+    let runtime = document.language.runtime();
+
+    let label = signature.signature_line(&symbol.name, runtime);
+    let mut params: Vec<String> = signature
+        .params
+        .iter()
+        .map(|p| {
+            let mut param = format!("{}: {}", p.name, p.param_type.type_name(runtime));
+            if let Some(default) = p.default.as_ref() {
+                param.push_str(&format!(" = {}", default_repr(default, runtime)));
+            }
+            param
+        })
+        .collect();
+    if signature.variadic {
+        params.push(match document.language {
+            Language::Lua => "...".to_string(),
+            Language::Python => "*args".to_string(),
+        });
+    }
+
+    // Parameter labels are offsets in the label, in UTF-16 units.
+    let mut search = label.find('(').unwrap_or_default();
+    let parameters = params
+        .iter()
+        .map(|param| {
+            let start = label[search..]
+                .find(param.as_str())
+                .map(|i| i + search)
+                .unwrap_or(search);
+            search = start + param.len();
+            let utf16 = |end: usize| label[..end].encode_utf16().count() as f64;
+            Json::object(vec![(
+                "label",
+                Json::Array(vec![
+                    Json::Number(utf16(start)),
+                    Json::Number(utf16(search)),
+                ]),
+            )])
+        })
+        .collect();
+
+    let active = argument.min(params.len().saturating_sub(1));
+    let mut info = vec![
+        ("label", Json::string(&label)),
+        ("parameters", Json::Array(parameters)),
+    ];
+    if !signature.doc.is_empty() {
+        info.push(("documentation", Json::string(&signature.doc)));
+    }
+    Some(Json::object(vec![
+        ("signatures", Json::Array(vec![Json::object(info)])),
+        ("activeSignature", Json::Number(0.0)),
+        ("activeParameter", Json::Number(active as f64)),
+    ]))
+}
+
+fn diagnostic(document: &Document, start: usize, name: &str, message: String) -> Json {
+    let range = |offset: usize| {
+        let (line, character) = document.position(offset);
+        Json::object(vec![
+            ("line", Json::Number(line as f64)),
+            ("character", Json::Number(character as f64)),
+        ])
+    };
+    Json::object(vec![
+        (
+            "range",
+            Json::object(vec![
+                ("start", range(start)),
+                ("end", range(start + name.len())),
+            ]),
+        ),
+        ("severity", Json::Number(SEVERITY_WARNING)),
+        ("source", Json::string("pxs-lsp")),
+        ("message", Json::string(&message)),
+    ])
+}
+
+/// Why `name` is not a member of `parent`, None if it is.
+fn unknown_member(
+    api: &Api,
+    parent: &ApiSymbol,
+    parent_path: &str,
+    name: &str,
+    language: Language,
+) -> Option<String> {
+    if !has_members(parent) {
+        return None;
+    }
+    let kind = parent.kind.name();
+    match parent.member(name) {
+        Some(member) if member.is_for(language.runtime()) => None,
+        Some(_) => Some(format!(
+            "'{name}' of {kind} '{parent_path}' is not added to {}",
+            language.id()
+        )),
+        // A nested module can be resolved on demand.
+        None if api
+            .find(&format!("{parent_path}.{name}"), language)
+            .is_some() =>
+        {
+            None
+        }
+        None => Some(format!("Unknown symbol '{name}' in {kind} '{parent_path}'")),
+    }
+}
+
+/// Warnings for members of host modules, types and enums that do not exist.
+///
+/// Only names that go through a `require`/`import` of a described module are checked, everything else
+/// is left to the normal language server.
+pub fn diagnostics(api: &Api, document: &Document) -> Vec<Json> {
+    let language = document.language;
+    let mut out = vec![];
+
+    for alias in document.aliases.iter() {
+        let (Some(offset), Some((parent_path, name))) = (alias.offset, alias.path.rsplit_once('.'))
+        else {
+            continue;
+        };
+        if let Some(parent) = api.find(parent_path, language)
+            && let Some(message) = unknown_member(api, parent, parent_path, name, language)
+        {
+            out.push(diagnostic(document, offset, name, message));
+        }
+    }
+
+    for chain in document.chains() {
+        check_chain(api, document, &chain, &mut out);
+    }
+    out
+}
+
+fn check_chain(api: &Api, document: &Document, chain: &Chain, out: &mut Vec<Json>) {
+    let names = chain.names();
+    let Some((mut symbol, mut path)) = api.resolve(document, &names[..1]) else {
+        return;
+    };
+    for (name, start) in chain.parts.iter().skip(1) {
+        if let Some(message) = unknown_member(api, symbol, &path, name, document.language) {
+            // New keys of unfrozen modules.
+            if !document.is_assigned(start + name.len()) {
+                out.push(diagnostic(document, *start, name, message));
+            }
+            return;
+        }
+        path = format!("{path}.{name}");
+        match api.find(&path, document.language) {
+            Some(member) => symbol = member,
+            None => return,
+        }
+    }
+}
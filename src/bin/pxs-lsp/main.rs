@@ -0,0 +1,300 @@
+// Copyright 2026 Jordan Castro <jordan@grupojvm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+//
+//! A language server for the host API of a PixelScript game.
+//!
+//! It reads the description written by `pxs_genapi` and adds completion, hover docs, signature help and
+//! unknown symbol warnings for the host modules in Lua and Python mod files. Run it next to the normal
+//! Lua/Python language server, it only knows about host symbols.
+//!
+//! ```sh
+//! pxs-lsp path/to/api.json
+//! ```
+//!
+//! The path can also be the `api` key of the `initializationOptions`, relative to the workspace root.
+//! The description is read again when the file changes.
+
+mod document;
+mod features;
+mod rpc;
+
+use std::{
+    collections::HashMap,
+    io::{self, BufReader},
+    path::PathBuf,
+    time::SystemTime,
+};
+
+use anyhow::Result;
+use pixelscript::shared::{api::parse_api, json::Json};
+
+use crate::{
+    document::{Document, Language},
+    features::Api,
+};
+
+struct Server {
+    api_path: Option<PathBuf>,
+    /// Modified time of the description when it was read.
+    api_modified: Option<SystemTime>,
+    api: Api,
+    /// Opened documents by uri.
+    documents: HashMap<String, Document>,
+    shutdown: bool,
+    /// Outgoing messages.
+    outbox: Vec<Json>,
+}
+
+/// Get a nested key, `get(params, &["textDocument", "uri"])`.
+fn get<'a>(json: &'a Json, keys: &[&str]) -> Option<&'a Json> {
+    keys.iter().try_fold(json, |json, key| json.get(key))
+}
+
+/// The path of a `file://` uri.
+fn uri_path(uri: &str) -> Option<PathBuf> {
+    uri.strip_prefix("file://").map(PathBuf::from)
+}
+
+impl Server {
+    /// Read the description again if it changed. Returns true when it was read.
+    fn load_api(&mut self) -> bool {
+        let Some(path) = self.api_path.as_ref() else {
+            return false;
+        };
+        let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        if modified.is_none() || modified == self.api_modified {
+            return false;
+        }
+        self.api_modified = modified;
+
+        match std::fs::read_to_string(path)
+            .map_err(anyhow::Error::from)
+            .and_then(|text| parse_api(&text))
+        {
+            Ok(modules) => {
+                self.api = Api { modules };
+                true
+            }
+            Err(err) => {
+                eprintln!("pxs-lsp: could not read {}: {err}", path.display());
+                false
+            }
+        }
+    }
+
+    fn publish_diagnostics(&mut self, uri: &str) {
+        let diagnostics = match self.documents.get(uri) {
+            Some(document) => features::diagnostics(&self.api, document),
+            None => vec![],
+        };
+        self.outbox.push(rpc::notification(
+            "textDocument/publishDiagnostics",
+            Json::object(vec![
+                ("uri", Json::string(uri)),
+                ("diagnostics", Json::Array(diagnostics)),
+            ]),
+        ));
+    }
+
+    /// The document and byte offset of a `TextDocumentPositionParams`.
+    fn position(&self, params: &Json) -> Option<(&Document, usize)> {
+        let uri = get(params, &["textDocument", "uri"])?.as_str()?;
+        let document = self.documents.get(uri)?;
+        let line = get(params, &["position", "line"])?.as_i64()? as usize;
+        let character = get(params, &["position", "character"])?.as_i64()? as usize;
+        Some((document, document.offset(line, character)))
+    }
+
+    fn initialize(&mut self, params: &Json) -> Json {
+        if self.api_path.is_none()
+            && let Some(api) =
+                get(params, &["initializationOptions", "api"]).and_then(|a| a.as_str())
+        {
+            let root = params
+                .get("rootUri")
+                .and_then(|r| r.as_str())
+                .and_then(uri_path);
+            self.api_path = Some(match root {
+                Some(root) => root.join(api),
+                None => PathBuf::from(api),
+            });
+        }
+        if self.api_path.is_none() {
+            eprintln!(
+                "pxs-lsp: no API description, pass it's path or set initializationOptions.api"
+            );
+        }
+        self.load_api();
+
+        Json::object(vec![
+            (
+                "capabilities",
+                Json::object(vec![
+                    // Full document sync.
+                    ("textDocumentSync", Json::Number(1.0)),
+                    (
+                        "completionProvider",
+                        Json::object(vec![(
+                            "triggerCharacters",
+                            Json::Array(vec![Json::string("."), Json::string(":")]),
+                        )]),
+                    ),
+                    ("hoverProvider", Json::Bool(true)),
+                    (
+                        "signatureHelpProvider",
+                        Json::object(vec![(
+                            "triggerCharacters",
+                            Json::Array(vec![Json::string("("), Json::string(",")]),
+                        )]),
+                    ),
+                ]),
+            ),
+            (
+                "serverInfo",
+                Json::object(vec![
+                    ("name", Json::string("pxs-lsp")),
+                    ("version", Json::string(env!("CARGO_PKG_VERSION"))),
+                ]),
+            ),
+        ])
+    }
+
+    /// Handle a request, None for a unknown method.
+    fn request(&mut self, method: &str, params: &Json) -> Option<Json> {
+        let result = match method {
+            "initialize" => self.initialize(params),
+            "shutdown" => {
+                self.shutdown = true;
+                Json::Null
+            }
+            "textDocument/completion" => match self.position(params) {
+                Some((document, offset)) => {
+                    Json::Array(features::completion(&self.api, document, offset))
+                }
+                None => Json::Null,
+            },
+            "textDocument/hover" => self
+                .position(params)
+                .and_then(|(document, offset)| features::hover(&self.api, document, offset))
+                .map(|markdown| {
+                    Json::object(vec![(
+                        "contents",
+                        Json::object(vec![
+                            ("kind", Json::string("markdown")),
+                            ("value", Json::String(markdown)),
+                        ]),
+                    )])
+                })
+                .unwrap_or(Json::Null),
+            "textDocument/signatureHelp" => self
+                .position(params)
+                .and_then(|(document, offset)| {
+                    features::signature_help(&self.api, document, offset)
+                })
+                .unwrap_or(Json::Null),
+            _ => return None,
+        };
+        Some(result)
+    }
+
+    fn notification(&mut self, method: &str, params: &Json) {
+        let uri = get(params, &["textDocument", "uri"])
+            .and_then(|u| u.as_str())
+            .unwrap_or_default()
+            .to_string();
+        match method {
+            "textDocument/didOpen" => {
+                let language_id =
+                    get(params, &["textDocument", "languageId"]).and_then(|l| l.as_str());
+                let text = get(params, &["textDocument", "text"]).and_then(|t| t.as_str());
+                if let (Some(language), Some(text)) = (
+                    Language::of_document(language_id.unwrap_or_default(), &uri),
+                    text,
+                ) {
+                    self.documents
+                        .insert(uri.clone(), Document::new(language, text.to_string()));
+                    self.publish_diagnostics(&uri);
+                }
+            }
+            "textDocument/didChange" => {
+                // Full sync, the last change is the whole text.
+                let text = params
+                    .get("contentChanges")
+                    .and_then(|c| c.items().last())
+                    .and_then(|c| c.get("text"));
+                if let (Some(document), Some(text)) =
+                    (self.documents.get_mut(&uri), text.and_then(|t| t.as_str()))
+                {
+                    *document = Document::new(document.language, text.to_string());
+                    self.publish_diagnostics(&uri);
+                }
+            }
+            "textDocument/didClose" => {
+                // Clear the diagnostics of the closed document.
+                self.documents.remove(&uri);
+                self.publish_diagnostics(&uri);
+            }
+            _ => {}
+        }
+    }
+
+    fn handle(&mut self, message: &Json) {
+        if self.load_api() {
+            let uris: Vec<String> = self.documents.keys().cloned().collect();
+            for uri in uris {
+                self.publish_diagnostics(&uri);
+            }
+        }
+
+        let method = message
+            .get("method")
+            .and_then(|m| m.as_str())
+            .unwrap_or_default();
+        let params = message.get("params").unwrap_or(&Json::Null);
+        match message.get("id") {
+            // Responses to our requests are ignored, we do not send any.
+            Some(_) if method.is_empty() => {}
+            Some(id) => {
+                let response = match self.request(method, params) {
+                    Some(result) => rpc::response(id.clone(), result),
+                    None => rpc::error_response(
+                        id.clone(),
+                        rpc::METHOD_NOT_FOUND,
+                        &format!("Unknown method {method}"),
+                    ),
+                };
+                self.outbox.push(response);
+            }
+            None => self.notification(method, params),
+        }
+    }
+}
+
+fn main() -> Result<()> {
+    let mut server = Server {
+        api_path: std::env::args().nth(1).map(PathBuf::from),
+        api_modified: None,
+        api: Api::default(),
+        documents: HashMap::new(),
+        shutdown: false,
+        outbox: vec![],
+    };
+
+    let mut stdin = BufReader::new(io::stdin().lock());
+    let mut stdout = io::stdout().lock();
+    while let Some(message) = rpc::read_message(&mut stdin)? {
+        if message.get("method").and_then(|m| m.as_str()) == Some("exit") {
+            std::process::exit(if server.shutdown { 0 } else { 1 });
+        }
+        server.handle(&message);
+        for message in server.outbox.drain(..) {
+            rpc::write_message(&mut stdout, &message)?;
+        }
+    }
+    Ok(())
+}
@@ -7,7 +7,7 @@
 // Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
 //
 // use rand::{Rng, SeedableRng, distr::Alphanumeric, rngs::SmallRng};
-use shared::{func::pxs_Func, var::pxs_Var};
+use shared::{func::{pxs_Func, pxs_OptFunc}, var::pxs_Var};
 use std::{
     ffi::{CString, c_char, c_void},
     ptr,
@@ -20,13 +20,23 @@ use crate::lua::LuaScripting;
 use crate::python::PythonScripting;
 
 use crate::shared::{
-    LoadFileFn, PixelScript, PtrMagic, ReadDirFn, WriteFileFn,
-    func::{clear_function_lookup, lookup_add_function},
+    LoadFileFn, PXS_ALL_RUNTIMES, PixelScript, PtrMagic, ReadDirFn, ResolveModFn, WriteFileExFn, WriteFileFn, call_module_resolver, has_file_writer, write_file,
+    api::api_json,
+    func::{clear_function_lookup, lookup_add_function, lookup_add_function_sig, lookup_remove_function},
     get_pixel_state,
-    module::pxs_Module,
-    object::{FreeMethod, clear_object_lookup, lookup_add_object, pxs_PixelObject},
+    module::{EnumMember, clear_module_lookup, lookup_add_module, lookup_remove_module, pxs_Module},
+    object::{
+        FreeMethod, clear_object_lookup, destroy_object, lookup_add_object, pxs_FieldType, pxs_OperatorKind,
+        pxs_PixelObject,
+    },
     pxs_Runtime,
-    var::{ObjectMethods, pxs_VarT, pxs_VarType},
+    signature::{pxs_ParamType, pxs_Signature, valid_default},
+    stubs::generate_stubs,
+    types::{
+        ValueField, add_value_type, clear_type_lookup, get_or_create_type, get_value_type, pxs_PixelType,
+        pxs_ValueType,
+    },
+    var::{ObjectMethods, pxs_VarArrayView, pxs_VarT, pxs_VarType},
 };
 
 pub mod shared;
@@ -143,8 +153,12 @@ pub extern "C" fn pxs_finalize() {
 
     // Drop function lookup
     clear_function_lookup();
+    // Drop module lookup
+    clear_module_lookup();
     // Drop object lookup
     clear_object_lookup();
+    // Drop type lookup
+    clear_type_lookup();
 
     with_feature!("lua", {
         LuaScripting::stop();
@@ -236,6 +250,20 @@ pub extern "C" fn pxs_addfunc(
     name: *const c_char,
     func: pxs_Func,
     opaque: pxs_Opaque,
+) {
+    pxs_addfunc_for(module_ptr, name, func, opaque, PXS_ALL_RUNTIMES);
+}
+
+/// Add a callback to a module, only in some runtimes.
+///
+/// `runtime_mask` is a mask of `1 << pxs_Runtime`, `(1 << pxs_Lua)` adds it to Lua only.
+#[unsafe(no_mangle)]
+pub extern "C" fn pxs_addfunc_for(
+    module_ptr: *mut pxs_Module,
+    name: *const c_char,
+    func: pxs_Func,
+    opaque: pxs_Opaque,
+    runtime_mask: u32,
 ) {
     assert_initiated!();
     if module_ptr.is_null() {
@@ -257,7 +285,114 @@ pub extern "C" fn pxs_addfunc(
     let idx = lookup_add_function(&full_name, func, opaque);
 
     // Now add callback
-    module.add_callback(name_str, &full_name, idx);
+    module.add_callback(name_str, &full_name, idx, runtime_mask, None);
+}
+
+/// Create a signature for `pxs_addfunc_ex`. `doc` can be NULL.
+///
+/// Parameters are added with `pxs_sigaddparam`, in order. See `pxs_Signature`.
+#[unsafe(no_mangle)]
+pub extern "C" fn pxs_newsig(doc: *const c_char) -> *mut pxs_Signature {
+    assert_initiated!();
+    let doc = if doc.is_null() {
+        String::new()
+    } else {
+        borrow_string!(doc).to_string()
+    };
+
+    pxs_Signature::new(doc).into_raw()
+}
+
+/// Add a parameter to a signature.
+///
+/// `default_value` is used when the script does not pass the argument, NULL makes it required.
+/// Ownership of the default is transfered.
+///
+/// Defaults can be a int, float, bool, string or null. Returns false, and frees the default, for anything
+/// else. The parameter is not added then.
+#[unsafe(no_mangle)]
+pub extern "C" fn pxs_sigaddparam(
+    sig: *mut pxs_Signature,
+    name: *const c_char,
+    param_type: pxs_ParamType,
+    default_value: *mut pxs_Var,
+) -> bool {
+    assert_initiated!();
+    if sig.is_null() || name.is_null() {
+        return false;
+    }
+
+    let sig = unsafe { pxs_Signature::from_borrow(sig) };
+    let default = if default_value.is_null() {
+        None
+    } else {
+        Some(pxs_Var::from_raw(default_value))
+    };
+    if default.as_ref().is_some_and(|default| !valid_default(default)) {
+        return false;
+    }
+    sig.add_param(borrow_string!(name), param_type, default);
+    true
+}
+
+/// Set if a signature takes more arguments after it's parameters.
+#[unsafe(no_mangle)]
+pub extern "C" fn pxs_sigsetvariadic(sig: *mut pxs_Signature, variadic: bool) {
+    assert_initiated!();
+    if sig.is_null() {
+        return;
+    }
+
+    unsafe { pxs_Signature::from_borrow(sig) }.variadic = variadic;
+}
+
+/// Set the return type of a signature. Defaults to `pxs_ParamAny`.
+#[unsafe(no_mangle)]
+pub extern "C" fn pxs_sigsetreturn(sig: *mut pxs_Signature, returns: pxs_ParamType) {
+    assert_initiated!();
+    if sig.is_null() {
+        return;
+    }
+
+    unsafe { pxs_Signature::from_borrow(sig) }.returns = returns;
+}
+
+/// Add a callback with a signature to a module.
+///
+/// The signature is shown by `help(module.name)` and `module.name.__doc__` in Python and
+/// `pxs.doc(module.name)` in Lua. Transfers ownership of the signature, NULL is the same as `pxs_addfunc`.
+///
+/// Arguments are checked before `func` is called, it can trust `args`: every parameter is there with it's
+/// type, missing optional ones hold their default. Ints are accepted for floats and whole floats for ints.
+/// Bad calls raise in the script, like `docs.greet() argument 2 'times': expected int, found str`.
+#[unsafe(no_mangle)]
+pub extern "C" fn pxs_addfunc_ex(
+    module_ptr: *mut pxs_Module,
+    name: *const c_char,
+    func: pxs_Func,
+    opaque: pxs_Opaque,
+    sig: *mut pxs_Signature,
+) {
+    assert_initiated!();
+    if module_ptr.is_null() || name.is_null() {
+        return;
+    }
+
+    let module = unsafe { pxs_Module::from_borrow(module_ptr) };
+    let name_str = borrow_string!(name);
+    let full_name = format!("_{}{}", module.name, name_str);
+
+    if sig.is_null() {
+        let idx = lookup_add_function(&full_name, func, opaque);
+        module.add_callback(name_str, &full_name, idx, PXS_ALL_RUNTIMES, None);
+        return;
+    }
+
+    let mut signature = pxs_Signature::from_raw(sig);
+    signature.name = format!("{}.{}", module.name, name_str);
+    let signature = Arc::new(signature);
+    let idx = lookup_add_function_sig(&full_name, func, opaque, Arc::clone(&signature));
+    module.add_callback(name_str, &full_name, idx, PXS_ALL_RUNTIMES, Some(signature));
 }
 
 /// Add a Varible to a module.
@@ -287,8 +422,74 @@ pub extern "C" fn pxs_addvar(
     module.add_variable(name_str, variable);
 }
 
+/// Add a constant to a module.
+///
+/// Like `pxs_addvar`, but scripts can not assign it even in a module that is not frozen.
+///
+/// Variable ownership is transfered.
+#[unsafe(no_mangle)]
+pub extern "C" fn pxs_addconst(module_ptr: *mut pxs_Module, name: *const c_char, variable: *mut pxs_Var) {
+    assert_initiated!();
+    if module_ptr.is_null() || name.is_null() {
+        return;
+    }
+
+    let module = unsafe { pxs_Module::from_borrow(module_ptr) };
+    module.add_constant(borrow_string!(name), variable);
+}
+
+/// Set if scripts can assign attributes of a module.
+///
+/// Modules are frozen by default: `main.MAX_PLAYERS = 1` raises. A module that is not frozen can be
+/// assigned, except it's constants. Live variables with a setter can always be assigned.
+#[unsafe(no_mangle)]
+pub extern "C" fn pxs_setmodfrozen(module_ptr: *mut pxs_Module, frozen: bool) {
+    assert_initiated!();
+    if module_ptr.is_null() {
+        return;
+    }
+
+    let module = unsafe { pxs_Module::from_borrow(module_ptr) };
+    module.frozen = frozen;
+}
+
+/// Add a live variable to a module.
+///
+/// Unlike `pxs_addvar` the value is not copied. Every read calls `getter` with `[runtime]` and every write
+/// calls `setter` with `[runtime, value]`. Pass NULL as setter for a read-only variable.
+///
+/// ```lua
+/// local main = require("main")
+/// print(main.score)   -- calls the getter
+/// main.score = 10     -- calls the setter
+/// ```
+#[unsafe(no_mangle)]
+pub extern "C" fn pxs_addlivevar(
+    module_ptr: *mut pxs_Module,
+    name: *const c_char,
+    getter: pxs_Func,
+    setter: pxs_OptFunc,
+    opaque: pxs_Opaque,
+) {
+    assert_initiated!();
+    if module_ptr.is_null() || name.is_null() {
+        return;
+    }
+
+    let module = unsafe { pxs_Module::from_borrow(module_ptr) };
+    let name_str = borrow_string!(name);
+
+    let getter = lookup_add_function(&format!("_{}{}__get", module.name, name_str), getter, opaque);
+    let setter = setter.map(|setter| lookup_add_function(&format!("_{}{}__set", module.name, name_str), setter, opaque));
+
+    module.add_live_var(name_str, getter, setter);
+}
+
 /// Add a Module to a Module
 ///
+/// The child is a attribute of the parent and can be loaded by it's full name, `require('pxs.math')` in Lua
+/// gives the same table as `pxs.math` and Python has `from pxs import math`.
+///
 /// This transfers ownership.
 #[unsafe(no_mangle)]
 pub extern "C" fn pxs_add_submod(parent_ptr: *mut pxs_Module, child_ptr: *mut pxs_Module) {
@@ -301,7 +502,7 @@ pub extern "C" fn pxs_add_submod(parent_ptr: *mut pxs_Module, child_ptr: *mut px
     // Own child
     let child = pxs_Module::from_raw(child_ptr);
 
-    parent.add_module(child.clone());
+    parent.add_module(child);
 
     // Child is now owned by parent
 }
@@ -317,75 +518,518 @@ pub extern "C" fn pxs_addmod(module_ptr: *mut pxs_Module) {
     }
 
     let module = Arc::new(pxs_Module::from_raw(module_ptr));
+    add_module(module);
+
+    // Module gets dropped here, and that is good!
+}
+
+/// Add the module to some runtimes only.
+///
+/// `runtime_mask` is a mask of `1 << pxs_Runtime`, `(1 << pxs_Lua) | (1 << pxs_Python)` for example.
+/// Internal modules follow their parent. Transfers ownership, like `pxs_addmod`.
+#[unsafe(no_mangle)]
+pub extern "C" fn pxs_addmod_for(module_ptr: *mut pxs_Module, runtime_mask: u32) {
+    assert_initiated!();
+    if module_ptr.is_null() {
+        return;
+    }
+
+    let mut module = pxs_Module::from_raw(module_ptr);
+    module.runtimes = runtime_mask;
+    add_module(Arc::new(module));
+}
+
+/// Remove a module from the runtime and release it's functions.
+///
+/// Lua forgets the module in `package.preload` and `package.loaded`. In Python the module is emptied,
+/// pocketpy can not delete modules, so importing it gives a empty module. Internal modules are removed too.
+///
+/// Returns false if no module of that name was added.
+#[unsafe(no_mangle)]
+pub extern "C" fn pxs_removemod(name: *const c_char) -> bool {
+    assert_initiated!();
+    if name.is_null() {
+        return false;
+    }
+
+    match lookup_remove_module(borrow_string!(name)) {
+        Some(module) => {
+            remove_module(module);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Replace a module of the same name, or add it if there is none.
+///
+/// The old module is removed like `pxs_removemod`. Scripts that already imported it keep the old Lua table,
+/// Python reuses the module so existing imports see the new functions.
+///
+/// Transfers ownership of the module, like `pxs_addmod`.
+#[unsafe(no_mangle)]
+pub extern "C" fn pxs_replacemod(module_ptr: *mut pxs_Module) {
+    assert_initiated!();
+    if module_ptr.is_null() {
+        return;
+    }
+
+    let module = Arc::new(pxs_Module::from_raw(module_ptr));
+    if let Some(old) = lookup_remove_module(&module.name) {
+        remove_module(old);
+    }
+    add_module(module);
+}
 
+/// Add a module to every runtime and save it so it can be removed.
+fn add_module(module: Arc<pxs_Module>) {
     // LUA
     with_feature!("lua", {
-        LuaScripting::add_module(Arc::clone(&module));
+        if module.is_for(pxs_Runtime::pxs_Lua) {
+            LuaScripting::add_module(Arc::clone(&module));
+        }
     });
     with_feature!("python", {
-        PythonScripting::add_module(Arc::clone(&module));
+        if module.is_for(pxs_Runtime::pxs_Python) {
+            PythonScripting::add_module(Arc::clone(&module));
+        }
     });
 
-    // Module gets dropped here, and that is good!
+    // Adding a module under a taken name replaces the saved one, it's functions stay registered.
+    lookup_add_module(module);
+}
+
+/// Add the module the host resolver builds for `name`. Returns false if there is no resolver or it gave null.
+pub(crate) fn resolve_module(name: &str) -> bool {
+    match call_module_resolver(name) {
+        Some(module) => {
+            add_module(Arc::new(module));
+            true
+        }
+        None => false,
+    }
+}
+
+/// Remove a module from every runtime and release it's functions.
+fn remove_module(module: Arc<pxs_Module>) {
+    with_feature!("lua", {
+        if module.is_for(pxs_Runtime::pxs_Lua) {
+            LuaScripting::remove_module(Arc::clone(&module));
+        }
+    });
+    with_feature!("python", {
+        if module.is_for(pxs_Runtime::pxs_Python) {
+            PythonScripting::remove_module(Arc::clone(&module));
+        }
+    });
+
+    for idx in module.function_idxs() {
+        lookup_remove_function(idx);
+    }
+}
+
+/// Add a module written in a runtime's language, from memory.
+///
+/// Scripts `require`/`import` it like a file module, errors show the file it would have been loaded from:
+/// `helpers/math.lua` or `helpers/math.py` for `helpers.math`. Lua compiles it now and runs it on the first
+/// `require`, Python runs it now.
+///
+/// Returns the error, a empty string on success. Free it with `pxs_freestr`.
+#[unsafe(no_mangle)]
+pub extern "C" fn pxs_addsourcemod(runtime: pxs_Runtime, name: *const c_char, source: *const c_char) -> *mut c_char {
+    assert_initiated!();
+    if name.is_null() || source.is_null() {
+        return create_raw_string!("Name or source is null");
+    }
+    let name_borrow = borrow_string!(name);
+    let source_borrow = borrow_string!(source);
+
+    let result = match runtime {
+        pxs_Runtime::pxs_Lua => {
+            with_feature!(
+                "lua",
+                { LuaScripting::add_source_module(name_borrow, source_borrow) },
+                { String::from("Lua is not enabled") }
+            )
+        }
+        pxs_Runtime::pxs_Python => {
+            with_feature!(
+                "python",
+                { PythonScripting::add_source_module(name_borrow, source_borrow) },
+                { String::from("Python is not enabled") }
+            )
+        }
+        _ => format!("{:?} does not support source modules", runtime),
+    };
+
+    create_raw_string!(result)
+}
+
+/// Optionally free a module if you changed your mind.
+#[unsafe(no_mangle)]
+pub extern "C" fn pxs_freemod(module_ptr: *mut pxs_Module) {
+    assert_initiated!();
+
+    if module_ptr.is_null() {
+        return;
+    }
+
+    let _ = pxs_Module::from_raw(module_ptr);
+}
+
+/// Create a new object.
+///
+/// This should only be used within a PixelScript function callback. I.e. a constructor.
+///
+/// This must be wrapped in a `pxs_var_object` before use within a callback. If setting to a variable, this is done automatically for you.
+#[unsafe(no_mangle)]
+pub extern "C" fn pxs_newobject(
+    ptr: pxs_Opaque,
+    free_method: FreeMethod,
+    type_name: *const c_char,
+) -> *mut pxs_PixelObject {
+    assert_initiated!();
+    if ptr.is_null() || type_name.is_null() {
+        return ptr::null_mut();
+    }
+
+    // Borrow type_name
+    let type_name = borrow_string!(type_name);
+
+    pxs_PixelObject::new(ptr, free_method, type_name).into_raw()
+}
+
+/// Add a callback to a object.
+///
+/// Methods live on the object's type, so this only registers the callback the first time a
+/// instance of the type adds it. Later calls with the same name are ignored, use `pxs_type_addmethod`
+/// to replace a method. Prefer `pxs_newtype` + `pxs_type_addmethod`.
+#[unsafe(no_mangle)]
+pub extern "C" fn pxs_object_addfunc(
+    object_ptr: *mut pxs_PixelObject,
+    name: *const c_char,
+    callback: pxs_Func,
+    opaque: pxs_Opaque,
+) {
+    assert_initiated!();
+
+    if object_ptr.is_null() || name.is_null() {
+        return;
+    }
+
+    // Borrow ptr
+    let object_borrow = unsafe { pxs_PixelObject::from_borrow(object_ptr) };
+    let name_borrow = borrow_string!(name);
+
+    // Already registered by a previous instance
+    if object_borrow.pixel_type.has_method(name_borrow) {
+        return;
+    }
+
+    object_borrow
+        .pixel_type
+        .add_method(name_borrow, callback, opaque);
+}
+
+/// Add a property to a object.
+///
+/// The getter is called with `[runtime, object]` and returns the value.
+/// The setter is called with `[runtime, object, value]`. Pass NULL for a read-only property,
+/// assigning to it will raise a error in the script.
+///
+/// Like `pxs_object_addfunc` this adds the property to the object's type once, later calls with the same
+/// name are ignored. `pxs_type_addprop` replaces it instead. Prefer `pxs_type_addprop`.
+#[unsafe(no_mangle)]
+pub extern "C" fn pxs_object_addprop(
+    object_ptr: *mut pxs_PixelObject,
+    name: *const c_char,
+    getter: pxs_Func,
+    setter: pxs_OptFunc,
+    opaque: pxs_Opaque,
+) {
+    assert_initiated!();
+
+    if object_ptr.is_null() || name.is_null() {
+        return;
+    }
+
+    // Borrow ptr
+    let object_borrow = unsafe { pxs_PixelObject::from_borrow(object_ptr) };
+    let name_borrow = borrow_string!(name);
+
+    // Already registered by a previous instance
+    if object_borrow
+        .pixel_type
+        .members()
+        .properties
+        .iter()
+        .any(|p| p.name == name_borrow)
+    {
+        return;
+    }
+
+    object_borrow
+        .pixel_type
+        .add_property(name_borrow, getter, setter, opaque);
+}
+
+/// Add a operator overload to a object.
+///
+/// See `pxs_OperatorKind` for the args each kind receives.
+///
+/// Like `pxs_object_addfunc` this adds the operator to the object's type once, later calls with the same
+/// kind are ignored. `pxs_type_addop` replaces it instead. Prefer `pxs_type_addop`.
+#[unsafe(no_mangle)]
+pub extern "C" fn pxs_object_addop(
+    object_ptr: *mut pxs_PixelObject,
+    kind: pxs_OperatorKind,
+    callback: pxs_Func,
+    opaque: pxs_Opaque,
+) {
+    assert_initiated!();
+
+    if object_ptr.is_null() {
+        return;
+    }
+
+    // Borrow ptr
+    let object_borrow = unsafe { pxs_PixelObject::from_borrow(object_ptr) };
+
+    // Already registered by a previous instance
+    if object_borrow.pixel_type.members().get_operator(kind).is_some() {
+        return;
+    }
+
+    object_borrow.pixel_type.add_operator(kind, callback, opaque);
+}
+
+/// Create a new type, or get the existing type with this name.
+///
+/// A type holds the methods, properties and operators of a host class. They are registered once
+/// and shared by every instance created with `pxs_newinstance`.
+///
+/// The type is owned by PixelScript, do not free it. It stays valid until `pxs_finalize` or `pxs_clearstate`.
+#[unsafe(no_mangle)]
+pub extern "C" fn pxs_newtype(type_name: *const c_char) -> *mut pxs_PixelType {
+    assert_initiated!();
+    if type_name.is_null() {
+        return ptr::null_mut();
+    }
+
+    let type_name = borrow_string!(type_name);
+
+    Arc::as_ptr(&get_or_create_type(type_name)) as *mut pxs_PixelType
+}
+
+/// Add a method to a type.
+///
+/// The callback is called with `[runtime, object, args...]`. Adding a method with the same name replaces it.
+#[unsafe(no_mangle)]
+pub extern "C" fn pxs_type_addmethod(
+    type_ptr: *mut pxs_PixelType,
+    name: *const c_char,
+    callback: pxs_Func,
+    opaque: pxs_Opaque,
+) {
+    assert_initiated!();
+    if type_ptr.is_null() || name.is_null() {
+        return;
+    }
+
+    let pixel_type = unsafe { &*type_ptr };
+    let name_borrow = borrow_string!(name);
+
+    pixel_type.add_method(name_borrow, callback, opaque);
+}
+
+/// Add a property to a type.
+///
+/// Same callbacks as `pxs_object_addprop`. Pass NULL as setter for a read-only property.
+/// Adding a property with the same name replaces it.
+#[unsafe(no_mangle)]
+pub extern "C" fn pxs_type_addprop(
+    type_ptr: *mut pxs_PixelType,
+    name: *const c_char,
+    getter: pxs_Func,
+    setter: pxs_OptFunc,
+    opaque: pxs_Opaque,
+) {
+    assert_initiated!();
+    if type_ptr.is_null() || name.is_null() {
+        return;
+    }
+
+    let pixel_type = unsafe { &*type_ptr };
+    let name_borrow = borrow_string!(name);
+
+    pixel_type.add_property(name_borrow, getter, setter, opaque);
+}
+
+/// Add a operator overload to a type.
+///
+/// See `pxs_OperatorKind` for the args each kind receives. Adding a operator of the same kind replaces it.
+#[unsafe(no_mangle)]
+pub extern "C" fn pxs_type_addop(
+    type_ptr: *mut pxs_PixelType,
+    kind: pxs_OperatorKind,
+    callback: pxs_Func,
+    opaque: pxs_Opaque,
+) {
+    assert_initiated!();
+    if type_ptr.is_null() {
+        return;
+    }
+
+    let pixel_type = unsafe { &*type_ptr };
+
+    pixel_type.add_operator(kind, callback, opaque);
+}
+
+/// Add a field to a type.
+///
+/// The runtimes read and write the primitive at `ptr + offset` of the host object directly, there is no callback.
+/// Use it for hot data like positions and health.
+///
+/// example:
+/// ```c
+/// typedef struct { float x; float y; int32_t health; } Player;
+///
+/// pxs_object_addfield(player_type, "x", offsetof(Player, x), pxs_FieldF32, false);
+/// pxs_object_addfield(player_type, "health", offsetof(Player, health), pxs_FieldI32, true);
+/// ```
+///
+/// Numbers are converted to the field type on write. Adding a field with the same name replaces it.
+#[unsafe(no_mangle)]
+pub extern "C" fn pxs_object_addfield(
+    type_ptr: *mut pxs_PixelType,
+    name: *const c_char,
+    offset: usize,
+    field_type: pxs_FieldType,
+    readonly: bool,
+) {
+    assert_initiated!();
+    if type_ptr.is_null() || name.is_null() {
+        return;
+    }
+
+    let pixel_type = unsafe { &*type_ptr };
+    let name_borrow = borrow_string!(name);
+
+    pixel_type.add_field(name_borrow, offset, field_type, readonly);
+}
+
+/// Set the constructor of a type.
+///
+/// The callback is called with `[runtime, args...]` when the type is called from a script, i.e.
+/// `Person("Jordan", 23)`, and should return `pxs_newinstance`.
+#[unsafe(no_mangle)]
+pub extern "C" fn pxs_type_setctor(type_ptr: *mut pxs_PixelType, callback: pxs_Func, opaque: pxs_Opaque) {
+    assert_initiated!();
+    if type_ptr.is_null() {
+        return;
+    }
+
+    let pixel_type = unsafe { &*type_ptr };
+
+    pixel_type.set_constructor(callback, opaque);
+}
+
+/// Add a static method to a type.
+///
+/// The callback is called with `[runtime, args...]`, like a module function. I.e. `Person.from_json(...)`.
+#[unsafe(no_mangle)]
+pub extern "C" fn pxs_type_addstatic(
+    type_ptr: *mut pxs_PixelType,
+    name: *const c_char,
+    callback: pxs_Func,
+    opaque: pxs_Opaque,
+) {
+    assert_initiated!();
+    if type_ptr.is_null() || name.is_null() {
+        return;
+    }
+
+    let pixel_type = unsafe { &*type_ptr };
+    let name_borrow = borrow_string!(name);
+
+    pixel_type.add_static(name_borrow, callback, opaque);
 }
 
-/// Optionally free a module if you changed your mind.
+/// Add a constant to a type. I.e. `Person.MAX_AGE`.
+///
+/// This transfers ownership of the var.
 #[unsafe(no_mangle)]
-pub extern "C" fn pxs_freemod(module_ptr: *mut pxs_Module) {
+pub extern "C" fn pxs_type_addconst(type_ptr: *mut pxs_PixelType, name: *const c_char, var: *mut pxs_Var) {
     assert_initiated!();
-
-    if module_ptr.is_null() {
+    if type_ptr.is_null() || name.is_null() || var.is_null() {
         return;
     }
 
-    let _ = pxs_Module::from_raw(module_ptr);
+    let pixel_type = unsafe { &*type_ptr };
+    let name_borrow = borrow_string!(name);
+
+    pixel_type.add_constant(name_borrow, pxs_Var::from_raw(var));
 }
 
-/// Create a new object.
+/// Create a new instance of a type.
 ///
 /// This should only be used within a PixelScript function callback. I.e. a constructor.
 ///
-/// This must be wrapped in a `pxs_var_object` before use within a callback. If setting to a variable, this is done automatically for you.
+/// `free_method` is called with `ptr` once the host and every script let go of the instance.
+/// Returns a HostObject var that can be returned directly from the callback.
 #[unsafe(no_mangle)]
-pub extern "C" fn pxs_newobject(
+pub extern "C" fn pxs_newinstance(
+    type_ptr: *mut pxs_PixelType,
     ptr: pxs_Opaque,
     free_method: FreeMethod,
-    type_name: *const c_char,
-) -> *mut pxs_PixelObject {
+) -> *mut pxs_Var {
     assert_initiated!();
-    if ptr.is_null() || type_name.is_null() {
+    if type_ptr.is_null() || ptr.is_null() {
         return ptr::null_mut();
     }
 
-    // Borrow type_name
-    let type_name = borrow_string!(type_name);
+    let pixel_type = get_or_create_type(unsafe { &(*type_ptr).name });
+    let object = pxs_PixelObject::with_type(ptr, free_method, pixel_type);
 
-    pxs_PixelObject::new(ptr, free_method, type_name).into_raw()
+    let idx = lookup_add_object(Arc::new(object));
+
+    pxs_Var::new_host_object(idx).into_raw()
 }
 
-/// Add a callback to a object.
+/// Create a value type from a field schema. Replaces any value type of the same name.
+///
+/// `names` and `types` hold `count` fields, in constructor order. See `pxs_ValueType`.
+///
+/// The type lives until `pxs_finalize` or `pxs_clearstate`.
 #[unsafe(no_mangle)]
-pub extern "C" fn pxs_object_addfunc(
-    object_ptr: *mut pxs_PixelObject,
+pub extern "C" fn pxs_newvaluetype(
     name: *const c_char,
-    callback: pxs_Func,
-    opaque: pxs_Opaque,
-) {
+    names: *const *const c_char,
+    types: *const pxs_FieldType,
+    count: usize,
+) -> *mut pxs_ValueType {
     assert_initiated!();
-
-    if object_ptr.is_null() || name.is_null() {
-        return;
+    if name.is_null() || (count > 0 && (names.is_null() || types.is_null())) {
+        return ptr::null_mut();
     }
 
-    // Borrow ptr
-    let object_borrow = unsafe { pxs_PixelObject::from_borrow(object_ptr) };
-    let name_borrow = borrow_string!(name);
-
-    // Add to function lookup
-    let full_name = format!("_{}{}", object_borrow.type_name, name_borrow);
-    let idx = lookup_add_function(full_name.as_str(), callback, opaque);
+    let mut fields = Vec::with_capacity(count);
+    for i in 0..count {
+        let field_name = unsafe { *names.add(i) };
+        if field_name.is_null() {
+            return ptr::null_mut();
+        }
+        fields.push(ValueField {
+            name: borrow_string!(field_name).to_string(),
+            field_type: unsafe { *types.add(i) },
+        });
+    }
 
-    object_borrow.add_callback(name_borrow, full_name.as_str(), idx);
+    let value_type = add_value_type(pxs_ValueType {
+        name: borrow_string!(name).to_string(),
+        fields,
+    });
+    Arc::as_ptr(&value_type) as *mut pxs_ValueType
 }
 
 /// Add a object to a Module.
@@ -425,6 +1069,109 @@ pub extern "C" fn pxs_addobject(
     pxs_addfunc(module_ptr, name, object_constructor, opaque);
 }
 
+/// Add a type to a Module.
+///
+/// Unlike `pxs_addobject` the type is exposed as a class. It is callable if it has a constructor, and has
+/// it's static methods and constants as fields.
+///
+/// In Lua it is a callable table, with `Person.isinstance(value)` for type checks:
+/// ```lua
+/// local p = Person("Jordan", 23)
+/// local q = Person.from_json(data)
+/// print(Person.MAX_AGE, Person.isinstance(p))
+/// ```
+///
+/// In Python it is a class:
+/// ```python
+/// p = Person("Jordan", 23)
+/// print(Person.MAX_AGE, isinstance(p, Person))
+/// ```
+#[unsafe(no_mangle)]
+pub extern "C" fn pxs_addtype(module_ptr: *mut pxs_Module, type_ptr: *mut pxs_PixelType) {
+    assert_initiated!();
+    if module_ptr.is_null() || type_ptr.is_null() {
+        return;
+    }
+
+    let module = unsafe { pxs_Module::from_borrow(module_ptr) };
+    let pixel_type = get_or_create_type(unsafe { &(*type_ptr).name });
+
+    module.add_type(pixel_type);
+}
+
+/// Add a value type to a Module.
+///
+/// The type becomes a constructor taking the fields in order. Missing fields are zero.
+///
+/// ```lua
+/// local v = Vec2(1, 2)  -- {x = 1, y = 2}
+/// v.x = v.x + 1
+/// ```
+///
+/// ```python
+/// v = Vec2(1, 2)
+/// v.x += 1
+/// ```
+#[unsafe(no_mangle)]
+pub extern "C" fn pxs_addvaluetype(module_ptr: *mut pxs_Module, value_type_ptr: *mut pxs_ValueType) {
+    assert_initiated!();
+    if module_ptr.is_null() || value_type_ptr.is_null() {
+        return;
+    }
+
+    let module = unsafe { pxs_Module::from_borrow(module_ptr) };
+    let Some(value_type) = get_value_type(unsafe { &(*value_type_ptr).name }) else {
+        return;
+    };
+
+    module.add_value_type(value_type);
+}
+
+/// Add a enum to a Module.
+///
+/// `names` and `values` hold `count` members. Members cross into the host as their value, but print their name.
+///
+/// ```lua
+/// local d = Damage.Fire
+/// print(d, d.name, d.value)    -- Damage.Fire  Fire  1
+/// print(Damage[1] == d)        -- reverse lookup
+/// Damage.Fire = 3              -- error, enums are frozen
+/// ```
+///
+/// ```python
+/// d = Damage.Fire
+/// print(d, d.name, d.value)    # Damage.Fire Fire 1
+/// print(Damage(1) == d, 1 in Damage)
+/// ```
+#[unsafe(no_mangle)]
+pub extern "C" fn pxs_addenum(
+    module_ptr: *mut pxs_Module,
+    name: *const c_char,
+    names: *const *const c_char,
+    values: *const i64,
+    count: usize,
+) {
+    assert_initiated!();
+    if module_ptr.is_null() || name.is_null() || (count > 0 && (names.is_null() || values.is_null())) {
+        return;
+    }
+
+    let mut members = Vec::with_capacity(count);
+    for i in 0..count {
+        let member_name = unsafe { *names.add(i) };
+        if member_name.is_null() {
+            return;
+        }
+        members.push(EnumMember {
+            name: borrow_string!(member_name).to_string(),
+            value: unsafe { *values.add(i) },
+        });
+    }
+
+    let module = unsafe { pxs_Module::from_borrow(module_ptr) };
+    module.add_enum(borrow_string!(name), members);
+}
+
 /// Make a new Var string.
 #[unsafe(no_mangle)]
 pub extern "C" fn pxs_newstring(str: *const c_char) -> *mut pxs_Var {
@@ -439,11 +1186,25 @@ pub extern "C" fn pxs_newnull() -> *mut pxs_Var {
     pxs_Var::new_null().into_raw()
 }
 
+/// Make a new Exception var.
+///
+/// When returned from a callback, the message is raised as a error in the calling script.
+/// A null `msg` makes a exception with a empty message.
+#[unsafe(no_mangle)]
+pub extern "C" fn pxs_newexception(msg: *const c_char) -> *mut pxs_Var {
+    if msg.is_null() {
+        return pxs_Var::new_exception(String::new()).into_raw();
+    }
+    let val = borrow_string!(msg);
+    pxs_Var::new_exception(val.to_string()).into_raw()
+}
+
 /// Make a new HostObject var.
 ///
 /// If not a valid pointer, will return null
 ///
-/// Transfers ownership
+/// Transfers ownership. The object is freed (via its `free_method`) once this var, any copies of it,
+/// and every script reference to it are gone.
 #[unsafe(no_mangle)]
 pub extern "C" fn pxs_newhost(pixel_object: *mut pxs_PixelObject) -> *mut pxs_Var {
     assert_initiated!();
@@ -551,8 +1312,8 @@ pub extern "C" fn pxs_objectcall(
     }
     let runtime = runtime.unwrap();
 
-    // Ensure type
-    if !var_borrow.is_object() {
+    // Ensure type. Host objects dispatch through their script wrapper, so script overrides are called.
+    if !var_borrow.is_object() && !var_borrow.is_host_object() {
         return ptr::null_mut();
     }
 
@@ -686,9 +1447,31 @@ pub extern "C" fn pxs_gethost(var: *mut pxs_Var) -> pxs_Opaque {
     unsafe { pxs_Var::from_borrow(var).get_host_ptr() }
 }
 
-/// Get the IDX of the PixelObject
+/// Destroy a host object now, even if scripts still reference it.
+///
+/// The object's `free_method` is called and any script still holding it gets a "object was destroyed" error on use.
+/// Returns false if the var is not a live host object.
+///
+/// Does not take ownership of the var.
+#[unsafe(no_mangle)]
+pub extern "C" fn pxs_destroyobject(var: *mut pxs_Var) -> bool {
+    assert_initiated!();
+
+    if var.is_null() {
+        return false;
+    }
+
+    let var = unsafe { pxs_Var::from_borrow(var) };
+    if !var.is_host_object() {
+        return false;
+    }
+
+    destroy_object(var.get_object_ptr())
+}
+
+/// Get the handle of the PixelObject
 #[unsafe(no_mangle)]
-pub extern "C" fn pxs_getobject(var: *mut pxs_Var) -> i32 {
+pub extern "C" fn pxs_getobject(var: *mut pxs_Var) -> i64 {
     if var.is_null() {
         return -1;
     }
@@ -721,7 +1504,8 @@ pub extern "C" fn pxs_set_filereader(func: LoadFileFn) {
 
 /// Set a function for writing a file.
 ///
-/// This is used to write files via pxs_json
+/// Used by `pxs_genstubs` and `pxs_genapi`. Paths use `/` and can be in directories that do not exist yet,
+/// the writer has to create them.
 #[unsafe(no_mangle)]
 pub extern "C" fn pxs_set_filewriter(func: WriteFileFn) {
     assert_initiated!();
@@ -730,6 +1514,18 @@ pub extern "C" fn pxs_set_filewriter(func: WriteFileFn) {
     *write_file = Some(func);
 }
 
+/// Set a function for writing a file that returns true when the file was written.
+///
+/// Like `pxs_set_filewriter`, but `pxs_genstubs` and `pxs_genapi` can report failed writes. Used instead of
+/// the `pxs_set_filewriter` writer when both are set.
+#[unsafe(no_mangle)]
+pub extern "C" fn pxs_set_filewriter_ex(func: WriteFileExFn) {
+    assert_initiated!();
+    let state = get_pixel_state();
+    let mut write_file_ex = state.write_file_ex.borrow_mut();
+    *write_file_ex = Some(func);
+}
+
 /// Set a function for reading a directory.
 ///
 /// This is used to read a dir.
@@ -741,6 +1537,75 @@ pub extern "C" fn pxs_set_dirreader(func: ReadDirFn) {
     *read_dir = Some(func);
 }
 
+/// Set a function for resolving modules on demand.
+///
+/// Called with the full name (`"items.weapons"`) when `require`/`import` can not find a added module or
+/// a file. Return a new module to add it, like `pxs_addmod`, or null. The module should have the name it
+/// was asked for, it is added to every runtime so it is only resolved once.
+#[unsafe(no_mangle)]
+pub extern "C" fn pxs_setmodresolver(func: ResolveModFn) {
+    assert_initiated!();
+    let state = get_pixel_state();
+    let mut resolve_module = state.resolve_module.borrow_mut();
+    *resolve_module = Some(func);
+}
+
+/// Write editor stubs of every added module to `out_dir`, with the file writer.
+///
+/// - `pxs_Python`: a `.pyi` per module, `name/__init__.pyi` for modules with internal modules.
+/// - `pxs_Lua`: a LuaLS `---@meta` file per module, `pxs.math.lua` for `pxs.math`.
+/// - `pxs_JavaScript`: a `.d.ts` per module, internal modules are namespaces.
+///
+/// Functions added with `pxs_addfunc_ex` get their parameters, return type and doc. Python stubs of
+/// nested modules are in directories, see `pxs_set_filewriter`.
+///
+/// Returns the number of files that could not be written, -1 if there is no file writer. Failed writes are only
+/// known with a writer set by `pxs_set_filewriter_ex`, otherwise this is 0.
+#[unsafe(no_mangle)]
+pub extern "C" fn pxs_genstubs(runtime: pxs_Runtime, out_dir: *const c_char) -> i32 {
+    assert_initiated!();
+    if out_dir.is_null() || !has_file_writer() {
+        return -1;
+    }
+    let out_dir = borrow_string!(out_dir).trim_end_matches('/');
+
+    let mut errors = 0;
+    for file in generate_stubs(runtime) {
+        let path = if out_dir.is_empty() {
+            file.path
+        } else {
+            format!("{}/{}", out_dir, file.path)
+        };
+        if !write_file(&path, &file.contents) {
+            errors += 1;
+        }
+    }
+    errors
+}
+
+/// Write a JSON description of every added module to `out_path`, with the file writer.
+///
+/// The description has the modules, functions, variables, types and enums with their signatures and docs.
+/// `pxs-lsp` reads it to complete, document and check host symbols in Lua and Python files. Write it
+/// after adding the modules, and again when they change.
+///
+/// Returns the number of modules described, -1 if there is no file writer or the file was not written, see
+/// `pxs_set_filewriter_ex`.
+#[unsafe(no_mangle)]
+pub extern "C" fn pxs_genapi(out_path: *const c_char) -> i32 {
+    assert_initiated!();
+    if out_path.is_null() || !has_file_writer() {
+        return -1;
+    }
+    let out_path = borrow_string!(out_path);
+
+    let api = api_json();
+    if !write_file(out_path, &api.to_string()) {
+        return -1;
+    }
+    api.get("modules").map(|m| m.items().len()).unwrap_or_default() as i32
+}
+
 /// Free a PixelScript var.
 ///
 /// You should only free results from `pxs_object_call`
@@ -781,14 +1646,20 @@ pub extern "C" fn pxs_stopthread() {
 
 /// Clear the current threads state for all languages.
 /// 
+/// Added modules, types and objects are forgotten, add the modules again afterwards.
+///
 /// Optionally, if you want to run the garbage collector.
 #[unsafe(no_mangle)]
 pub extern "C" fn pxs_clearstate(gc_collect: bool) {
     assert_initiated!();
     // Drop function lookup
     clear_function_lookup();
+    // Drop module lookup, their functions are gone
+    clear_module_lookup();
     // Drop object lookup
     clear_object_lookup();
+    // Drop type lookup
+    clear_type_lookup();
 
     with_feature!("lua", {
         LuaScripting::clear_state(gc_collect);
@@ -798,6 +1669,61 @@ pub extern "C" fn pxs_clearstate(gc_collect: bool) {
     }); 
 }
 
+/// Instantiate a script class by name within a specified runtime. I.e. a script subclass of a host type.
+///
+/// `class_name` can be a dotted path, i.e. `"mods.FireSword"` in Lua. Python looks it up in `__main__`.
+///
+/// The returned object var keeps the instance alive until it is freed. Use `pxs_objectcall` on it to call
+/// methods, overridden methods run the script code and the rest fall through to the host callbacks.
+/// On failure a exception var holding the error is returned.
+///
+/// Transfers ownership of args.
+#[unsafe(no_mangle)]
+pub extern "C" fn pxs_instantiate(
+    runtime: *mut pxs_Var,
+    class_name: *const c_char,
+    args: *mut pxs_Var,
+) -> *mut pxs_Var {
+    assert_initiated!();
+
+    if runtime.is_null() || class_name.is_null() || args.is_null() {
+        return ptr::null_mut();
+    }
+
+    let runtime_borrow = unsafe { pxs_Runtime::from_var_ptr(runtime) };
+    let class_name = borrow_string!(class_name);
+    // Own args
+    let args = own_var!(args);
+    if !args.is_list() {
+        return ptr::null_mut();
+    }
+    let list = args.get_list().unwrap();
+
+    let Some(rt) = runtime_borrow else {
+        return ptr::null_mut();
+    };
+    let res = match rt {
+        pxs_Runtime::pxs_Lua => {
+            with_feature!("lua", { LuaScripting::instantiate(class_name, list) }, {
+                return std::ptr::null_mut();
+            })
+        }
+        pxs_Runtime::pxs_Python => {
+            with_feature!(
+                "python",
+                { PythonScripting::instantiate(class_name, list) },
+                { return std::ptr::null_mut(); }
+            )
+        }
+        _ => return ptr::null_mut(),
+    };
+
+    match res {
+        Ok(var) => var.into_raw(),
+        Err(e) => pxs_Var::new_exception(e.to_string()).into_raw(),
+    }
+}
+
 /// Call a method within a specifed runtime.
 ///
 /// Runtime is a `pxs_Var`.
@@ -887,6 +1813,9 @@ pub extern "C" fn pxs_tostring(runtime: *mut pxs_Var, var: *mut pxs_Var) -> *mut
         pxs_VarType::pxs_Float64 => {
             return pxs_Var::new_string(b_var.get_f64().unwrap().to_string()).into_raw();
         }
+        pxs_VarType::pxs_Exception => {
+            return pxs_Var::new_string(b_var.get_exception().unwrap()).into_raw();
+        }
         _ => {
             // Do nothing
         }
@@ -1053,6 +1982,133 @@ pub extern "C" fn pxs_listlen(list: *mut pxs_Var) -> i32 {
     list.vars.len() as i32
 }
 
+/// Create a new array view over a host buffer.
+///
+/// Scripts read and write the elements straight in `ptr`, nothing is copied. Lua indexes from 1, Python from 0.
+/// The buffer is NOT owned by the view, keep it alive while scripts can reach the view.
+///
+/// example:
+/// ```c
+/// float positions[1024];
+/// pxs_Var* view = pxs_newarrayview(pxs_FieldF32, positions, 1024, false);
+/// ```
+///
+/// ```lua
+/// for i = 1, #view do
+///     view[i] = view[i] + 1.0
+/// end
+/// ```
+///
+/// ```python
+/// for i in range(len(view)):
+///     view[i] += 1.0
+/// ```
+#[unsafe(no_mangle)]
+pub extern "C" fn pxs_newarrayview(
+    elem_type: pxs_FieldType,
+    ptr: *mut c_void,
+    len: usize,
+    readonly: bool,
+) -> *mut pxs_Var {
+    assert_initiated!();
+
+    if ptr.is_null() && len > 0 {
+        return pxs_Var::new_null().into_raw();
+    }
+
+    pxs_Var::new_array_view(pxs_VarArrayView {
+        elem_type,
+        ptr,
+        len,
+        readonly,
+    })
+    .into_raw()
+}
+
+/// Get the length of a array view. -1 if the var is not a array view.
+#[unsafe(no_mangle)]
+pub extern "C" fn pxs_arrayviewlen(view: *mut pxs_Var) -> i64 {
+    assert_initiated!();
+
+    if view.is_null() {
+        return -1;
+    }
+
+    let borrow = unsafe { pxs_Var::from_borrow(view) };
+    match borrow.get_array_view() {
+        Some(view) => view.len as i64,
+        None => -1,
+    }
+}
+
+/// Get the host buffer of a array view. NULL if the var is not a array view.
+#[unsafe(no_mangle)]
+pub extern "C" fn pxs_arrayviewptr(view: *mut pxs_Var) -> *mut c_void {
+    assert_initiated!();
+
+    if view.is_null() {
+        return ptr::null_mut();
+    }
+
+    let borrow = unsafe { pxs_Var::from_borrow(view) };
+    match borrow.get_array_view() {
+        Some(view) => view.ptr,
+        None => ptr::null_mut(),
+    }
+}
+
+/// Create a new value of a value type. Every field is zero.
+#[unsafe(no_mangle)]
+pub extern "C" fn pxs_newvalue(value_type_ptr: *mut pxs_ValueType) -> *mut pxs_Var {
+    assert_initiated!();
+    if value_type_ptr.is_null() {
+        return pxs_Var::new_null().into_raw();
+    }
+
+    let Some(value_type) = get_value_type(unsafe { &(*value_type_ptr).name }) else {
+        return pxs_Var::new_null().into_raw();
+    };
+    match value_type.pack(vec![]) {
+        Ok(value) => pxs_Var::new_value(value).into_raw(),
+        Err(e) => pxs_Var::new_exception(e).into_raw(),
+    }
+}
+
+/// Get a field of a value. Returns a Int64, Float64 or Bool var, NULL if the field does not exist.
+#[unsafe(no_mangle)]
+pub extern "C" fn pxs_valueget(value: *mut pxs_Var, name: *const c_char) -> *mut pxs_Var {
+    assert_initiated!();
+    if value.is_null() || name.is_null() {
+        return ptr::null_mut();
+    }
+
+    let borrow = unsafe { pxs_Var::from_borrow(value) };
+    let name = borrow_string!(name);
+    match borrow.get_value().and_then(|value| value.get(name)) {
+        Some(field) => pxs_Var::new_field(field).into_raw(),
+        None => ptr::null_mut(),
+    }
+}
+
+/// Set a field of a value. Numbers are converted to the field type.
+///
+/// Does not take ownership of `field`. Returns false if the field does not exist or the type does not fit.
+#[unsafe(no_mangle)]
+pub extern "C" fn pxs_valueset(value: *mut pxs_Var, name: *const c_char, field: *mut pxs_Var) -> bool {
+    assert_initiated!();
+    if value.is_null() || name.is_null() || field.is_null() {
+        return false;
+    }
+
+    let borrow = unsafe { pxs_Var::from_borrow(value) };
+    let field = unsafe { pxs_Var::from_borrow(field) };
+    let name = borrow_string!(name);
+    let (Some(value), Some(field)) = (borrow.get_value_mut(), field.get_field()) else {
+        return false;
+    };
+    value.set(name, field).is_ok()
+}
+
 /// Call a `pxs_Var`s function.
 ///
 /// Expects runtime var, var function, and args that is a List.
@@ -0,0 +1,62 @@
+// Copyright 2026 Jordan Castro <jordan@grupojvm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+//
+use mlua::prelude::*;
+
+use crate::{
+    lua::var::{field_from_lua, field_into_lua},
+    shared::var::pxs_VarArrayView,
+};
+
+/// A array view in Lua. Indexes from 1 like a Lua sequence, so `ipairs` and `#` work.
+pub(super) struct LuaArrayView(pub pxs_VarArrayView);
+
+/// Convert a Lua index into a view index for a write.
+fn view_index(view: &pxs_VarArrayView, index: i64) -> LuaResult<usize> {
+    if index < 1 || index as usize > view.len {
+        return Err(LuaError::RuntimeError(format!(
+            "index {} out of range for a array view of length {}",
+            index, view.len
+        )));
+    }
+    Ok(index as usize - 1)
+}
+
+impl LuaUserData for LuaArrayView {
+    fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
+        // Reads past the end give nil like a table, so `ipairs` stops there. Writes are checked.
+        methods.add_meta_method(LuaMetaMethod::Index, |_, this, index: i64| {
+            if index < 1 {
+                return Ok(LuaValue::Nil);
+            }
+            Ok(this.0.get(index as usize - 1).map_or(LuaValue::Nil, field_into_lua))
+        });
+        methods.add_meta_method(LuaMetaMethod::NewIndex, |_, this, (index, value): (i64, LuaValue)| {
+            let index = view_index(&this.0, index)?;
+            let value = field_from_lua(&value).ok_or_else(|| {
+                LuaError::RuntimeError(format!("array view can not hold a {}", value.type_name()))
+            })?;
+            this.0.set(index, value).map_err(LuaError::RuntimeError)
+        });
+        methods.add_meta_method(LuaMetaMethod::Len, |_, this, ()| Ok(this.0.len));
+        methods.add_meta_function(LuaMetaMethod::Pairs, |lua, view: LuaAnyUserData| {
+            // Same as ipairs, stops at the end of the view.
+            let iter = lua.create_function(|_, (view, index): (LuaAnyUserData, i64)| {
+                let this = view.borrow::<LuaArrayView>()?;
+                match this.0.get(index as usize) {
+                    Some(value) => Ok((LuaValue::Integer(index + 1), field_into_lua(value))),
+                    None => Ok((LuaValue::Nil, LuaValue::Nil)),
+                }
+            })?;
+            Ok((iter, view, 0))
+        });
+        methods.add_meta_method(LuaMetaMethod::ToString, |_, this, ()| {
+            Ok(format!("ArrayView({:?}, {})", this.0.elem_type, this.0.len))
+        });
+    }
+}
@@ -0,0 +1,113 @@
+// Copyright 2026 Jordan Castro <jordan@grupojvm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+//
+use mlua::prelude::*;
+
+use crate::shared::module::ModuleEnum;
+
+/// A enum member in Lua. Crosses into the host as it's value.
+pub(super) struct LuaEnumMember {
+    pub enum_name: String,
+    pub name: String,
+    pub value: i64,
+}
+
+impl LuaUserData for LuaEnumMember {
+    fn add_fields<F: LuaUserDataFields<Self>>(fields: &mut F) {
+        fields.add_field_method_get("name", |_, this| Ok(this.name.clone()));
+        fields.add_field_method_get("value", |_, this| Ok(this.value));
+    }
+
+    fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
+        methods.add_meta_method(LuaMetaMethod::ToString, |_, this, ()| {
+            Ok(format!("{}.{}", this.enum_name, this.name))
+        });
+        methods.add_meta_function(LuaMetaMethod::Eq, |_, (a, b): (LuaAnyUserData, LuaAnyUserData)| {
+            let (Ok(a), Ok(b)) = (a.borrow::<LuaEnumMember>(), b.borrow::<LuaEnumMember>()) else {
+                return Ok(false);
+            };
+            Ok(a.enum_name == b.enum_name && a.value == b.value)
+        });
+        methods.add_meta_function(LuaMetaMethod::Lt, |_, (a, b): (LuaUserDataRef<LuaEnumMember>, LuaUserDataRef<LuaEnumMember>)| {
+            Ok(a.value < b.value)
+        });
+        methods.add_meta_function(LuaMetaMethod::Le, |_, (a, b): (LuaUserDataRef<LuaEnumMember>, LuaUserDataRef<LuaEnumMember>)| {
+            Ok(a.value <= b.value)
+        });
+    }
+}
+
+/// Create the frozen table of a enum.
+///
+/// `Enum.Name` and `Enum[value]` both give the member, and indexing with a member checks membership.
+pub(super) fn create_enum(lua: &Lua, module_enum: &ModuleEnum) -> LuaResult<LuaTable> {
+    let mut members = vec![];
+    for member in module_enum.members.iter() {
+        let ud = lua.create_userdata(LuaEnumMember {
+            enum_name: module_enum.name.clone(),
+            name: member.name.clone(),
+            value: member.value,
+        })?;
+        members.push((member.name.clone(), member.value, ud));
+    }
+
+    let mt = lua.create_table()?;
+    let enum_name = module_enum.name.clone();
+    let index_members = members.clone();
+    mt.set(
+        "__index",
+        lua.create_function(move |_, (_, key): (LuaTable, LuaValue)| {
+            let found = match &key {
+                LuaValue::String(s) => {
+                    let s = s.to_str()?;
+                    index_members.iter().find(|(name, _, _)| *name == *s)
+                }
+                LuaValue::Integer(i) => index_members.iter().find(|(_, value, _)| value == i),
+                LuaValue::UserData(ud) => match ud.borrow::<LuaEnumMember>() {
+                    Ok(m) if m.enum_name == enum_name => index_members.iter().find(|(_, value, _)| *value == m.value),
+                    _ => None,
+                },
+                _ => None,
+            };
+            Ok(found.map(|(_, _, ud)| ud.clone()))
+        })?,
+    )?;
+    let enum_name = module_enum.name.clone();
+    mt.set(
+        "__newindex",
+        lua.create_function(move |_, _: LuaMultiValue| -> LuaResult<()> {
+            Err(LuaError::RuntimeError(format!("enum '{}' is read-only", enum_name)))
+        })?,
+    )?;
+    let count = members.len();
+    mt.set("__len", lua.create_function(move |_, _: LuaTable| Ok(count))?)?;
+    // Members in declaration order, by name.
+    mt.set(
+        "__pairs",
+        lua.create_function(move |lua, table: LuaTable| {
+            let members = members.clone();
+            let iter = lua.create_function(move |_, (_, key): (LuaValue, Option<String>)| {
+                let next = match key {
+                    None => 0,
+                    Some(key) => members.iter().position(|(name, _, _)| *name == key).map_or(members.len(), |i| i + 1),
+                };
+                match members.get(next) {
+                    Some((name, _, ud)) => Ok((Some(name.clone()), Some(ud.clone()))),
+                    None => Ok((None, None)),
+                }
+            })?;
+            Ok((iter, table, LuaValue::Nil))
+        })?,
+    )?;
+    let enum_name = module_enum.name.clone();
+    mt.set("__tostring", lua.create_function(move |_, _: LuaTable| Ok(format!("enum {}", enum_name)))?)?;
+
+    let table = lua.create_table()?;
+    table.set_metatable(Some(mt))?;
+    Ok(table)
+}
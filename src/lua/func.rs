@@ -41,6 +41,41 @@ pub(super) fn internal_add_callback(lua: &Lua, fn_idx: i32) -> LuaFunction {
     }).expect("Could not create lua function")
 }
 
+/// Registry key of the docs table.
+const DOCS_KEY: &str = "pxs_docs";
+
+/// Get the docs of functions, weak keys so the docs go with their function.
+fn docs_table(lua: &Lua) -> LuaResult<LuaTable> {
+    if let Ok(docs) = lua.named_registry_value::<LuaTable>(DOCS_KEY) {
+        return Ok(docs);
+    }
+
+    let docs = lua.create_table()?;
+    let mt = lua.create_table()?;
+    mt.set("__mode", "k")?;
+    docs.set_metatable(Some(mt))?;
+    lua.set_named_registry_value(DOCS_KEY, &docs)?;
+    Ok(docs)
+}
+
+/// Save the doc of a callback for `pxs.doc`.
+pub(super) fn set_doc(lua: &Lua, function: &LuaFunction, doc: String) -> LuaResult<()> {
+    docs_table(lua)?.set(function, doc)
+}
+
+/// Add the global `pxs` table with `pxs.doc(fn)`, the doc of a host callback or nil.
+pub(super) fn setup_doc_helper(lua: &Lua) -> LuaResult<()> {
+    let pxs = match lua.globals().get::<LuaValue>("pxs")? {
+        LuaValue::Table(table) => table,
+        _ => lua.create_table()?,
+    };
+    pxs.set(
+        "doc",
+        lua.create_function(|lua, function: LuaValue| docs_table(lua)?.get::<LuaValue>(function))?,
+    )?;
+    lua.globals().set("pxs", pxs)
+}
+
 // /// Add a callback to lua __main__ context.
 // pub(super) fn add_callback(name: &str, fn_idx: i32) {
 //     let state = get_lua_state();
@@ -6,37 +6,77 @@
 //
 // Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
 //
+pub mod array;
+pub mod enums;
 pub mod func;
 pub mod module;
 pub mod object;
+pub mod value;
 pub mod var;
 
 use anyhow::anyhow;
 use mlua::prelude::*;
 use parking_lot::{ReentrantMutex, ReentrantMutexGuard};
-use std::{cell::RefCell, collections::HashMap};
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    sync::atomic::{AtomicI64, Ordering},
+};
 
 use crate::{
     lua::var::{from_lua, into_lua},
-    shared::{PixelScript, read_file, var::{ObjectMethods, pxs_Var}},
+    resolve_module,
+    shared::{
+        PixelScript, pxs_Runtime, read_file,
+        object::{pxs_PixelObject, release_wrappers},
+        var::{ObjectMethods, pxs_Var},
+    },
 };
 
 thread_local! {
     static LUASTATE: ReentrantMutex<State> = ReentrantMutex::new(init_state());
+    /// Set while the engine is closing. Finalizers must not touch Lua then.
+    static LUA_CLOSING: Cell<bool> = const { Cell::new(false) };
 }
 
 /// This is the Lua state. Each language gets it's own private state
 struct State {
+    /// Wrapper context of this state. Each thread has it's own Lua state, so each gets it's own wrapper slot.
+    context: i64,
+    /// `{__mode = "v"}`, the metatable of the weak boxes in wrapper slots.
+    ///
+    /// Declared before `engine` so it is dropped while the engine is still alive.
+    weak_mt: LuaTable,
     /// The lua engine.
     engine: Lua,
     /// Cached Tables
     tables: RefCell<HashMap<String, LuaTable>>,
 }
 
+impl Drop for State {
+    fn drop(&mut self) {
+        LUA_CLOSING.with(|closing| closing.set(true));
+    }
+}
+
+/// Is the engine of this thread closing?
+pub(self) fn is_lua_closing() -> bool {
+    LUA_CLOSING.with(|closing| closing.get())
+}
+
+/// The next wrapper context.
+static NEXT_CONTEXT: AtomicI64 = AtomicI64::new(0);
+
 /// Initialize Lua state per thread.
 fn init_state() -> State {
+    let engine = Lua::new();
+    let weak_mt = engine.create_table().expect("Could not create weak metatable.");
+    weak_mt.set("__mode", "v").expect("Could not set __mode.");
+
     State {
-        engine: Lua::new(),
+        context: NEXT_CONTEXT.fetch_add(1, Ordering::Relaxed),
+        weak_mt,
+        engine,
         tables: RefCell::new(HashMap::new()),
     }
 }
@@ -62,6 +102,50 @@ pub(self) fn store_metatable(name: &str, table: LuaTable) {
     state.tables.borrow_mut().insert(name.to_string(), table);
 }
 
+/// Get the wrapper context of this thread's Lua state.
+fn get_context() -> i64 {
+    let state = get_lua_state();
+    state.context
+}
+
+/// Release logic of a Lua wrapper slot. The slot holds a weak box, so the table itself is left to the GC.
+fn release_object_table(ptr: *mut std::ffi::c_void) {
+    unsafe {
+        drop(Box::from_raw(ptr as *mut LuaTable));
+    }
+}
+
+/// Get the live table of a host object in this Lua state.
+pub(self) fn get_object_table(object: &pxs_PixelObject) -> Option<LuaTable> {
+    let weak_box = object.get_wrapper(pxs_Runtime::pxs_Lua, get_context())?;
+    let weak_box = unsafe { &*(weak_box as *const LuaTable) };
+    weak_box.raw_get(1).ok().flatten()
+}
+
+/// Save the table of a host object in it's Lua wrapper slot.
+///
+/// The slot holds a weak box (`{__mode = "v"}`) instead of the table, the table holds a reference on the object
+/// so a strong reference would keep both alive forever.
+pub(self) fn store_object_table(lua: &Lua, object: &pxs_PixelObject, table: LuaTable) {
+    let state = get_lua_state();
+    let weak_box = lua.create_table().expect("Could not create weak box.");
+    weak_box
+        .set_metatable(Some(state.weak_mt.clone()))
+        .expect("Could not set weak metatable.");
+    weak_box.raw_set(1, table).expect("Could not store object table.");
+
+    let ptr = Box::into_raw(Box::new(weak_box));
+    object.set_wrapper(pxs_Runtime::pxs_Lua, state.context, ptr as *mut std::ffi::c_void, release_object_table);
+}
+
+/// Is this table a host object, or a instance of a script subclass of one?
+fn is_host_table(table: &LuaTable) -> bool {
+    table.raw_get::<Option<i64>>("_id").ok().flatten().is_some()
+        && table
+            .metatable()
+            .is_some_and(|mt| matches!(mt.raw_get::<LuaValue>("__name"), Ok(LuaValue::String(_))))
+}
+
 /// Execute some orbituary lua code.
 /// Returns a String. Empty means no error happened and was successful!
 pub fn execute(code: &str, file_name: &str) -> String {
@@ -122,6 +206,22 @@ fn setup_module_loader(lua: &Lua) {
     searchers
         .set(len + 1, loader)
         .expect("Could not set loader in searchers Lua.");
+
+    // Last, ask the host resolver. It adds the module to `package.preload`.
+    let resolver = lua
+        .create_function(|lua, name: String| {
+            if !resolve_module(&name) {
+                return Ok(LuaNil);
+            }
+
+            let package: LuaTable = lua.globals().get("package")?;
+            let preload: LuaTable = package.get("preload")?;
+            preload.get::<LuaValue>(name)
+        })
+        .expect("Could not create resolver function Lua.");
+    searchers
+        .set(len + 2, resolver)
+        .expect("Could not set resolver in searchers Lua.");
 }
 
 pub struct LuaScripting;
@@ -131,6 +231,14 @@ impl PixelScript for LuaScripting {
         module::add_module(source, None);
     }
 
+    fn remove_module(source: std::sync::Arc<crate::shared::module::pxs_Module>) {
+        module::remove_module(&source, None);
+    }
+
+    fn add_source_module(name: &str, source: &str) -> String {
+        module::add_source_module(name, source)
+    }
+
     fn execute(code: &str, file_name: &str) -> String {
         execute(code, file_name)
     }
@@ -139,6 +247,7 @@ impl PixelScript for LuaScripting {
         // Initalize the state
         let state = get_lua_state();
         setup_module_loader(&state.engine);
+        func::setup_doc_helper(&state.engine).expect("Could not add pxs.doc Lua.");
     }
 
     fn stop() {
@@ -147,6 +256,7 @@ impl PixelScript for LuaScripting {
 
         // Ok clear the cached tables
         state.tables.borrow_mut().clear();
+        release_wrappers(pxs_Runtime::pxs_Lua, Some(state.context));
 
         // Ok now cler the GC.
         state.engine.gc_collect().unwrap();
@@ -164,6 +274,7 @@ impl PixelScript for LuaScripting {
         let state = get_lua_state();
 
         state.tables.borrow_mut();
+        release_wrappers(pxs_Runtime::pxs_Lua, Some(state.context));
 
         if call_gc {
             state.engine.gc_collect().unwrap();
@@ -190,16 +301,30 @@ impl ObjectMethods for LuaScripting {
         args: &mut crate::shared::var::pxs_VarList,
     ) -> Result<crate::shared::var::pxs_Var, anyhow::Error> {
         // Get the lua table.
-        let table = unsafe {
-            // Just grab it from the ptr itself
-            let table_ptr = var.value.object_val as *const LuaTable;
-            (&*table_ptr).clone()
+        let table = if var.is_host_object() {
+            // Host objects are tables too
+            let state = get_lua_state();
+            match into_lua(&state.engine, var)? {
+                LuaValue::Table(t) => t,
+                _ => return Err(anyhow!("Host object is not a Lua table.")),
+            }
+        } else {
+            unsafe {
+                // Just grab it from the ptr itself
+                let table_ptr = var.value.object_val as *const LuaTable;
+                (&*table_ptr).clone()
+            }
         };
 
         let lua_args = args_to_lua(&args.vars);
-        let res = table
-            .call_function(method, lua_args)
-            .expect("Could not call function on Lua Table.");
+        // Host objects and their script subclasses get `self`, like `obj:method(...)`.
+        let res = if is_host_table(&table) {
+            table.call_method(method, lua_args)?
+        } else {
+            table
+                .call_function(method, lua_args)
+                .expect("Could not call function on Lua Table.")
+        };
 
         let pixel_res = from_lua(res).expect("Could not convert LuaVar into PixelScript Var.");
 
@@ -274,4 +399,37 @@ impl ObjectMethods for LuaScripting {
         })
     }
 
+    fn instantiate(
+        class_name: &str,
+        args: &mut crate::shared::var::pxs_VarList,
+    ) -> Result<pxs_Var, anyhow::Error> {
+        let lua_args = args_to_lua(&args.vars);
+        let state = get_lua_state();
+
+        // Resolve a dotted path from the globals
+        let mut class = LuaValue::Table(state.engine.globals());
+        for part in class_name.split('.') {
+            class = match class {
+                LuaValue::Table(t) => t.get(part)?,
+                _ => LuaValue::Nil,
+            };
+        }
+        if class.is_nil() {
+            return Err(anyhow!("Lua class {} was not found.", class_name));
+        }
+
+        let instance: LuaValue = match class {
+            LuaValue::Table(t) => t.call(lua_args)?,
+            LuaValue::Function(f) => f.call(lua_args)?,
+            _ => return Err(anyhow!("Lua class {} is not callable.", class_name)),
+        };
+        let LuaValue::Table(instance) = instance else {
+            return Err(anyhow!("Lua class {} did not return a table.", class_name));
+        };
+
+        // Boxed so the host keeps a strong reference.
+        let obj = Box::into_raw(Box::new(instance));
+        Ok(pxs_Var::new_object(obj as *mut std::ffi::c_void, Some(Box::new(var::free_lua_mem))))
+    }
+
 }
@@ -6,16 +6,24 @@
 //
 // Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
 //
-use std::sync::Arc;
+use std::{collections::{HashMap, HashSet}, sync::Arc};
 
 use crate::{
-    lua::{func::internal_add_callback, get_lua_state, into_lua},
-    shared::{PtrMagic, module::pxs_Module, var::pxs_Var},
+    lua::{enums::create_enum, from_lua, func::{internal_add_callback, set_doc}, get_lua_state, into_lua, object::create_class, value::create_value_constructor},
+    shared::{
+        PtrMagic,
+        func::call_function,
+        module::{ModuleLiveVar, pxs_Module},
+        pxs_Runtime,
+        var::pxs_Var,
+    },
 };
 use mlua::prelude::*;
 
 /// Create the module table.
-fn create_module(context: &Lua, module: &pxs_Module) -> LuaTable {
+///
+/// `mod_name` is the full name, `pxs.math` for the `math` child of `pxs`.
+fn create_module(context: &Lua, module: &pxs_Module, mod_name: &str) -> LuaTable {
     let module_table = context.create_table().expect("Could not create LUA table.");
 
     // Add variables
@@ -31,17 +39,132 @@ fn create_module(context: &Lua, module: &pxs_Module) -> LuaTable {
     }
 
     // Add callbacks
-    for callback in module.callbacks.iter() {
+    for callback in module.callbacks.iter().filter(|c| c.is_for(pxs_Runtime::pxs_Lua)) {
         // Create lua function
         let lua_function = internal_add_callback(context, callback.idx);
+        if let Some(signature) = callback.signature.as_ref() {
+            set_doc(context, &lua_function, signature.describe(&callback.name, pxs_Runtime::pxs_Lua))
+                .expect("Could not set callback doc");
+        }
         module_table
             .set(callback.name.as_str(), lua_function)
             .expect("Could not set callback to module");
     }
 
+    // Add types
+    for pixel_type in module.types.iter() {
+        module_table
+            .set(pixel_type.name.as_str(), create_class(context, pixel_type))
+            .expect("Could not set type to module");
+    }
+
+    // Add value types
+    for value_type in module.value_types.iter() {
+        module_table
+            .set(value_type.name.as_str(), create_value_constructor(context, value_type))
+            .expect("Could not set value type to module");
+    }
+
+    // Add enums
+    for module_enum in module.enums.iter() {
+        let enum_table = create_enum(context, module_enum).expect("Could not create enum");
+        module_table
+            .set(module_enum.name.as_str(), enum_table)
+            .expect("Could not set enum to module");
+    }
+
+    // Add internal modules. Going through require keeps `pxs.math` and `require('pxs.math')` the same table.
+    if !module.modules.is_empty() {
+        let require: LuaFunction = context.globals().get("require").expect("Could not grab require");
+        for child in module.modules.iter() {
+            let child_table: LuaValue = require
+                .call(format!("{mod_name}.{}", child.name))
+                .expect("Could not require internal module");
+            module_table
+                .set(child.name.as_str(), child_table)
+                .expect("Could not set internal module to module");
+        }
+    }
+
+    // Frozen modules, constants and live variables need every write, so scripts get a empty proxy.
+    let needs_proxy = module.frozen || !module.live_vars.is_empty() || module.variables.iter().any(|v| v.constant);
+    if needs_proxy {
+        return create_proxy(context, module_table, module, mod_name).expect("Could not create module proxy");
+    }
+
     module_table
 }
 
+/// Create the proxy of a module table.
+///
+/// Reads go to the live variable getters or the table. Writes call live variable setters, raise for
+/// constants and frozen modules, and are stored in the table otherwise.
+fn create_proxy(lua: &Lua, module_table: LuaTable, module: &pxs_Module, mod_name: &str) -> LuaResult<LuaTable> {
+    let live_vars: Arc<HashMap<String, ModuleLiveVar>> =
+        Arc::new(module.live_vars.iter().map(|v| (v.name.clone(), v.clone())).collect());
+    let constants: HashSet<String> = module
+        .variables
+        .iter()
+        .filter(|v| v.constant)
+        .map(|v| v.name.clone())
+        .collect();
+    let frozen = module.frozen;
+    let mod_name = mod_name.to_string();
+
+    let mt = lua.create_table()?;
+    let getters = Arc::clone(&live_vars);
+    let storage = module_table.clone();
+    mt.set(
+        "__index",
+        lua.create_function(move |lua, (_, key): (LuaTable, LuaValue)| {
+            let Some(live_var) = key.as_string().and_then(|k| getters.get(&*k.to_str().ok()?)) else {
+                return storage.raw_get::<LuaValue>(key);
+            };
+            let res = unsafe { call_function(live_var.getter, vec![pxs_Var::new_i64(pxs_Runtime::pxs_Lua as i64)]) };
+            into_lua(lua, &res)
+        })?,
+    )?;
+    let storage = module_table.clone();
+    mt.set(
+        "__newindex",
+        lua.create_function(move |lua, (_, key, value): (LuaTable, LuaValue, LuaValue)| {
+            let name = key.as_string().and_then(|k| k.to_str().ok().map(|k| k.to_string()));
+            let live_var = name.as_ref().and_then(|name| live_vars.get(name));
+            let Some(live_var) = live_var else {
+                if let Some(name) = name.as_ref().filter(|name| constants.contains(*name)) {
+                    return Err(LuaError::RuntimeError(format!("constant '{}' is read-only", name)));
+                }
+                if frozen {
+                    return Err(LuaError::RuntimeError(format!("module '{}' is read-only", mod_name)));
+                }
+                return storage.raw_set(key, value);
+            };
+            let Some(setter) = live_var.setter else {
+                return Err(LuaError::RuntimeError(format!("variable '{}' is read-only", live_var.name)));
+            };
+            let argv = vec![
+                pxs_Var::new_i64(pxs_Runtime::pxs_Lua as i64),
+                from_lua(value).map_err(|e| LuaError::RuntimeError(e.to_string()))?,
+            ];
+            let res = unsafe { call_function(setter, argv) };
+            // Only exceptions matter
+            into_lua(lua, &res).map(|_| ())
+        })?,
+    )?;
+    // pairs() walks the stored values.
+    mt.set(
+        "__pairs",
+        lua.create_function(move |lua, _: LuaTable| {
+            let next: LuaFunction = lua.globals().get("next")?;
+            Ok((next, module_table.clone(), LuaValue::Nil))
+        })?,
+    )?;
+
+    let proxy = lua.create_table()?;
+    proxy.set_metatable(Some(mt))?;
+    Ok(proxy)
+}
+
 /// Add a module to Lua!
 pub fn add_module(module: Arc<pxs_Module>, parent: Option<&str>) {
     // First get lua state
@@ -65,15 +188,15 @@ pub fn add_module(module: Arc<pxs_Module>, parent: Option<&str>) {
 
     // Add internal modules.
     for child in module.modules.iter() {
-        let child_module = child.clone();
-        add_module(Arc::new(child_module), Some(mod_name.as_str()));
+        add_module(Arc::clone(child), Some(mod_name.as_str()));
     }
 
     // create the loader function for require()
+    let loader_name = mod_name.clone();
     let loader = state
         .engine
         .create_function(move |lua, _: ()| {
-            let module_table = create_module(lua, &module_for_lua);
+            let module_table = create_module(lua, &module_for_lua, &loader_name);
             // Return module
             Ok(module_table)
         })
@@ -85,3 +208,55 @@ pub fn add_module(module: Arc<pxs_Module>, parent: Option<&str>) {
         .expect("Could not set Lua module loader.");
 
 }
+
+/// Remove a module and it's internal modules from Lua.
+pub fn remove_module(module: &pxs_Module, parent: Option<&str>) {
+    let state = get_lua_state();
+
+    let mod_name = match parent {
+        Some(p) => format!("{p}.{}", module.name),
+        None => module.name.clone(),
+    };
+
+    for child in module.modules.iter() {
+        remove_module(child, Some(mod_name.as_str()));
+    }
+
+    let package: LuaTable = state
+        .engine
+        .globals()
+        .get("package")
+        .expect("Could not grab the Package table");
+    for table in ["preload", "loaded"] {
+        let table: LuaTable = package.get(table).expect("Could not grab the Package tables");
+        table
+            .set(mod_name.as_str(), LuaValue::Nil)
+            .expect("Could not remove Lua module.");
+    }
+}
+
+/// Add a module from Lua source. The chunk is the `package.preload` loader, like a file found by `require`.
+///
+/// The chunk is named like the file it would be loaded from, `helpers/math.lua` for `helpers.math`.
+pub fn add_source_module(name: &str, source: &str) -> String {
+    let state = get_lua_state();
+    let chunk_name = format!("{}.lua", name.replace(".", "/"));
+
+    let chunk = match state.engine.load(source).set_name(&chunk_name).into_function() {
+        Ok(chunk) => chunk,
+        Err(e) => return format!("Error in LUA: {}, for file: {}", e, chunk_name),
+    };
+
+    let package: LuaTable = state
+        .engine
+        .globals()
+        .get("package")
+        .expect("Could not grab the Package table");
+    let preload: LuaTable = package.get("preload").expect("Could not grab the Preload table");
+    let loaded: LuaTable = package.get("loaded").expect("Could not grab the Loaded table");
+    preload.set(name, chunk).expect("Could not set Lua module loader.");
+    // A module added again is loaded again.
+    loaded.set(name, LuaValue::Nil).expect("Could not remove Lua module.");
+
+    String::new()
+}
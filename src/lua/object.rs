@@ -6,15 +6,48 @@
 //
 // Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
 //
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use crate::{
-    lua::{from_lua, get_metatable, into_lua, store_metatable},
-    shared::{pxs_Runtime, func::call_function, object::pxs_PixelObject, var::pxs_Var},
+    lua::{
+        from_lua,
+        func::internal_add_callback,
+        get_metatable, into_lua, is_lua_closing, store_metatable,
+        var::{field_from_lua, field_into_lua},
+    },
+    shared::{
+        func::call_function,
+        object::{ObjectField, get_object, object_decref, pxs_OperatorKind, pxs_PixelObject},
+        pxs_Runtime,
+        types::{TypeMembers, pxs_PixelType},
+        var::pxs_Var,
+    },
 };
 use mlua::prelude::*;
 
-fn create_object_callback(lua: &Lua, fn_idx: i32) -> LuaFunction {
+/// Get the object of a object table. Errors if the object was destroyed or is not a `type_name`.
+///
+/// Scripts can pass any table with a `_id` to a metamethod, so the type is always checked.
+fn live_object(obj: &LuaTable, type_name: &str) -> LuaResult<(i64, Arc<pxs_PixelObject>)> {
+    let obj_id: i64 = obj.raw_get("_id")?;
+    let object = get_object(obj_id)
+        .ok_or_else(|| LuaError::RuntimeError("object was destroyed".to_string()))?;
+    if object.type_name != type_name {
+        return Err(LuaError::RuntimeError(format!(
+            "expected a {} object, found {}",
+            type_name, object.type_name
+        )));
+    }
+    Ok((obj_id, object))
+}
+
+/// Get the handle of a object table, see `live_object`.
+fn live_object_id(obj: &LuaTable, type_name: &str) -> LuaResult<i64> {
+    live_object(obj, type_name).map(|(obj_id, _)| obj_id)
+}
+
+fn create_object_callback(lua: &Lua, fn_idx: i32, type_name: &str) -> LuaFunction {
+    let type_name = type_name.to_string();
     lua.create_function(
         move |lua, (internal_obj, args): (LuaTable, LuaMultiValue)| {
             let mut argv = vec![];
@@ -23,12 +56,10 @@ fn create_object_callback(lua: &Lua, fn_idx: i32) -> LuaFunction {
             argv.push(pxs_Var::new_i64(pxs_Runtime::pxs_Lua as i64));
 
             // Get obj id
-            let obj_id: i64 = internal_obj
-                .get("_id")
-                .expect("Could not grab ID from Object.");
+            let obj_id = live_object_id(&internal_obj, &type_name)?;
 
-            // Add object id
-            argv.push(pxs_Var::new_i64(obj_id));
+            // Add object
+            argv.push(pxs_Var::new_host_object(obj_id));
 
             // Add args
             for arg in args {
@@ -47,34 +78,453 @@ fn create_object_callback(lua: &Lua, fn_idx: i32) -> LuaFunction {
     .expect("Could not create function on object")
 }
 
-pub(super) fn create_object(lua: &Lua, idx: i32, source: Arc<pxs_PixelObject>) -> LuaTable {
-    let table = lua.create_table().expect("Could not create table.");
-    table
-        .set("_id", LuaValue::Integer(idx as i64))
-        .expect("Could not set _id on Lua Table.");
-    // Check if the meta table exists already
+/// Look up a field by a Lua key.
+fn get_field<'a>(fields: &'a HashMap<String, ObjectField>, key: &LuaValue) -> Option<&'a ObjectField> {
+    let LuaValue::String(key) = key else {
+        return None;
+    };
+    fields.get(&*key.to_str().ok()?)
+}
+
+/// Read a field of a object table straight from host memory.
+fn read_field(obj: &LuaTable, type_name: &str, field: &ObjectField) -> LuaResult<LuaValue> {
+    let (_, object) = live_object(obj, type_name)?;
+    Ok(field_into_lua(unsafe { field.read(object.ptr) }))
+}
 
-    let metatable = get_metatable(&source.type_name);
+/// Write a field of a object table straight into host memory.
+fn write_field(obj: &LuaTable, type_name: &str, field: &ObjectField, value: LuaValue) -> LuaResult<()> {
+    if field.readonly {
+        return Err(LuaError::RuntimeError(format!("field '{}' is read-only", field.name)));
+    }
+    let (_, object) = live_object(obj, type_name)?;
+    let value = field_from_lua(&value).ok_or_else(|| {
+        LuaError::RuntimeError(format!(
+            "field '{}' can not be set to a {}",
+            field.name,
+            value.type_name()
+        ))
+    })?;
+    unsafe { field.write(object.ptr, value) }.map_err(LuaError::RuntimeError)
+}
 
-    // let mut state = get_state();
-    let metatable = if let Some(mt) = metatable {
-        mt.clone()
-    } else {
-        // Create new metatable
-        let mt = lua.create_table().expect("Could not create Metatable");
-        // Add methods
-        for method in source.callbacks.iter() {
-            let func = create_object_callback(lua, method.idx);
-            mt.set(method.name.clone(), func)
-                .expect("Could not set method");
+/// Create the `__pairs` metamethod of a Next operator.
+///
+/// Returns a iterator function for the generic for, which yields `state, value` until the host returns null.
+fn create_pairs_callback(lua: &Lua, fn_idx: i32, type_name: &str) -> LuaFunction {
+    let next = create_object_callback(lua, fn_idx, type_name);
+    let iter = lua
+        .create_function(move |_, (obj, state): (LuaTable, LuaValue)| {
+            match next.call::<LuaValue>((obj, state))? {
+                LuaValue::Table(step) => Ok((step.raw_get::<LuaValue>(1)?, step.raw_get::<LuaValue>(2)?)),
+                _ => Ok((LuaValue::Nil, LuaValue::Nil)),
+            }
+        })
+        .expect("Could not create iterator");
+
+    lua.create_function(move |_, obj: LuaTable| Ok((iter.clone(), obj, LuaValue::Nil)))
+        .expect("Could not create __pairs")
+}
+
+/// Is the value a object table of this type?
+fn is_object_of(value: &LuaValue, type_name: &str) -> bool {
+    let LuaValue::Table(t) = value else {
+        return false;
+    };
+    let Some(mt) = t.metatable() else {
+        return false;
+    };
+    matches!(mt.raw_get::<Option<String>>("__name"), Ok(Some(name)) if name == type_name)
+}
+
+/// Create a binary operator callback.
+///
+/// Lua calls the metamethod of either operand, so the object is found first and the order passed as `reflected`.
+fn create_binary_callback(lua: &Lua, fn_idx: i32, type_name: &str) -> LuaFunction {
+    let type_name = type_name.to_string();
+    lua.create_function(move |lua, (lhs, rhs): (LuaValue, LuaValue)| {
+        let (obj, other, reflected) = if is_object_of(&lhs, &type_name) {
+            (lhs, rhs, false)
+        } else {
+            (rhs, lhs, true)
+        };
+        let obj_id = match &obj {
+            LuaValue::Table(t) => live_object_id(t, &type_name)?,
+            _ => return Err(LuaError::RuntimeError("Operator without a object.".to_string())),
+        };
+
+        let argv = vec![
+            pxs_Var::new_i64(pxs_Runtime::pxs_Lua as i64),
+            pxs_Var::new_host_object(obj_id),
+            from_lua(other).expect("Could not convert Lua Value into Var."),
+            pxs_Var::new_bool(reflected),
+        ];
+
+        unsafe {
+            let res = call_function(fn_idx, argv);
+            into_lua(lua, &res)
         }
+    })
+    .expect("Could not create operator on object")
+}
+
+/// Create a unary operator callback.
+///
+/// Lua passes the operand twice for `__len` and `__unm`, only the object is passed on.
+fn create_unary_callback(lua: &Lua, fn_idx: i32, type_name: &str) -> LuaFunction {
+    let type_name = type_name.to_string();
+    lua.create_function(move |lua, (obj, _rest): (LuaTable, LuaMultiValue)| {
+        let obj_id = live_object_id(&obj, &type_name)?;
+        let argv = vec![
+            pxs_Var::new_i64(pxs_Runtime::pxs_Lua as i64),
+            pxs_Var::new_host_object(obj_id),
+        ];
 
+        unsafe {
+            let res = call_function(fn_idx, argv);
+            into_lua(lua, &res)
+        }
+    })
+    .expect("Could not create operator on object")
+}
+
+/// Map methods, properties and the index operators onto `__index`/`__newindex`.
+///
+/// Lookup order is methods, getters and then the Index operator. Assigning to a read-only property raises.
+fn add_index(lua: &Lua, mt: &LuaTable, type_name: &str, source: &TypeMembers) {
+    let getters = lua.create_table().expect("Could not create getters table.");
+    let setters = lua.create_table().expect("Could not create setters table.");
+    for prop in source.properties.iter() {
+        getters
+            .set(prop.name.clone(), create_object_callback(lua, prop.getter.idx, type_name))
+            .expect("Could not set getter");
+        let setter = match &prop.setter {
+            Some(setter) => LuaValue::Function(create_object_callback(lua, setter.idx, type_name)),
+            None => LuaValue::Boolean(false),
+        };
+        setters
+            .set(prop.name.clone(), setter)
+            .expect("Could not set setter");
+    }
+    let index_op = source
+        .get_operator(pxs_OperatorKind::pxs_OpIndex)
+        .map(|op| create_object_callback(lua, op.callback.idx, type_name));
+    let newindex_op = source
+        .get_operator(pxs_OperatorKind::pxs_OpNewIndex)
+        .map(|op| create_object_callback(lua, op.callback.idx, type_name));
+
+    let fields: Arc<HashMap<String, ObjectField>> = Arc::new(
+        source
+            .fields
+            .iter()
+            .map(|field| (field.name.clone(), field.clone()))
+            .collect(),
+    );
+
+    let methods = mt.clone();
+    let index_fields = Arc::clone(&fields);
+    let index_type = type_name.to_string();
+    let index = lua
+        .create_function(move |_, (obj, key): (LuaTable, LuaValue)| {
+            let method: LuaValue = methods.raw_get(key.clone())?;
+            if !method.is_nil() {
+                return Ok(method);
+            }
+            if let Some(field) = get_field(&index_fields, &key) {
+                return read_field(&obj, &index_type, field);
+            }
+            if let Some(getter) = getters.raw_get::<Option<LuaFunction>>(key.clone())? {
+                return getter.call(obj);
+            }
+            match &index_op {
+                Some(index_op) => index_op.call((obj, key)),
+                None => Ok(LuaValue::Nil),
+            }
+        })
+        .expect("Could not create __index");
+    mt.set("__index", index).expect("Could not set __index");
+
+    let newindex_type = type_name.to_string();
+    let newindex = lua
+        .create_function(move |_, (obj, key, value): (LuaTable, LuaValue, LuaValue)| {
+            if let Some(field) = get_field(&fields, &key) {
+                return write_field(&obj, &newindex_type, field, value);
+            }
+            match setters.raw_get::<LuaValue>(key.clone())? {
+                LuaValue::Function(setter) => setter.call::<()>((obj, value)),
+                LuaValue::Boolean(false) => Err(LuaError::RuntimeError(format!(
+                    "property '{}' is read-only",
+                    key.to_string()?
+                ))),
+                _ => match &newindex_op {
+                    Some(newindex_op) => newindex_op.call::<()>((obj, key, value)),
+                    None => obj.raw_set(key, value),
+                },
+            }
+        })
+        .expect("Could not create __newindex");
+    mt.set("__newindex", newindex)
+        .expect("Could not set __newindex");
+}
+
+/// Add the operator metamethods. Index and NewIndex are handled by `add_index`.
+fn add_operators(lua: &Lua, mt: &LuaTable, type_name: &str, source: &TypeMembers) {
+    for op in source.operators.iter() {
+        let metamethod = match op.kind {
+            pxs_OperatorKind::pxs_OpAdd => "__add",
+            pxs_OperatorKind::pxs_OpSub => "__sub",
+            pxs_OperatorKind::pxs_OpMul => "__mul",
+            pxs_OperatorKind::pxs_OpDiv => "__div",
+            pxs_OperatorKind::pxs_OpEq => "__eq",
+            pxs_OperatorKind::pxs_OpLt => "__lt",
+            pxs_OperatorKind::pxs_OpLe => "__le",
+            pxs_OperatorKind::pxs_OpConcat => "__concat",
+            pxs_OperatorKind::pxs_OpToString => "__tostring",
+            pxs_OperatorKind::pxs_OpLen => "__len",
+            pxs_OperatorKind::pxs_OpCall => "__call",
+            pxs_OperatorKind::pxs_OpUnm => "__unm",
+            pxs_OperatorKind::pxs_OpNext => "__pairs",
+            pxs_OperatorKind::pxs_OpIndex | pxs_OperatorKind::pxs_OpNewIndex => continue,
+        };
+        let func = match op.kind {
+            pxs_OperatorKind::pxs_OpCall => create_object_callback(lua, op.callback.idx, type_name),
+            pxs_OperatorKind::pxs_OpNext => create_pairs_callback(lua, op.callback.idx, type_name),
+            kind if kind.is_binary() => {
+                create_binary_callback(lua, op.callback.idx, type_name)
+            }
+            _ => create_unary_callback(lua, op.callback.idx, type_name),
+        };
+        mt.set(metamethod, func)
+            .expect("Could not set metamethod");
+    }
+}
+
+/// Is the value a host object of this type?
+fn is_instance(value: &LuaValue, type_name: &str) -> LuaResult<bool> {
+    let LuaValue::Table(table) = value else {
+        return Ok(false);
+    };
+    let Some(mt) = table.metatable() else {
+        return Ok(false);
+    };
+    let name: Option<String> = mt.raw_get("__name")?;
+    Ok(name.as_deref() == Some(type_name))
+}
+
+/// Look up a key on a instance of a script subclass.
+///
+/// Walks the subclass chain first, so script methods override host methods, then falls back to the host metatable.
+fn subclass_index(class: &LuaTable, base_index: &LuaValue, obj: LuaTable, key: LuaValue) -> LuaResult<LuaValue> {
+    let mut current = Some(class.clone());
+    while let Some(class) = current {
+        let value: LuaValue = class.raw_get(key.clone())?;
+        if !value.is_nil() {
+            return Ok(value);
+        }
+        current = class.raw_get("__pxs_parent")?;
+    }
+
+    match base_index {
+        LuaValue::Table(index) => index.raw_get(key),
+        LuaValue::Function(index) => index.call((obj, key)),
+        _ => Ok(LuaValue::Nil),
+    }
+}
+
+/// Create a script subclass of `parent`, which is the class table of a type or another subclass.
+///
+/// Instances are the host object tables with a copy of the host metatable, whose `__index` checks the
+/// subclass first. So from the host's side they are still host objects.
+fn extend_class(lua: &Lua, parent: LuaTable, base_mt: LuaTable) -> LuaResult<LuaTable> {
+    let class = lua.create_table()?;
+    let parent_is_subclass = parent.raw_get::<Option<LuaTable>>("__pxs_mt")?.is_some();
+    if parent_is_subclass {
+        class.raw_set("__pxs_parent", parent.clone())?;
+    }
+
+    // Instance metatable
+    let mt = lua.create_table()?;
+    for pair in base_mt.pairs::<LuaValue, LuaValue>() {
+        let (key, value) = pair?;
+        mt.raw_set(key, value)?;
+    }
+    let index_class = class.clone();
+    let index = lua.create_function(move |_, (obj, key): (LuaTable, LuaValue)| {
+        // Read per call, the host metatable is refilled when the type changes.
+        let base_index: LuaValue = base_mt.raw_get("__index")?;
+        subclass_index(&index_class, &base_index, obj, key)
+    })?;
+    mt.raw_set("__index", index)?;
+    mt.raw_set("__pxs_class", class.clone())?;
+    class.raw_set("__pxs_mt", mt.clone())?;
+
+    // Type check against this subclass and it's children
+    let check_class = class.clone();
+    let isinstance = lua.create_function(move |_, value: LuaValue| {
+        let LuaValue::Table(table) = value else {
+            return Ok(false);
+        };
+        let Some(value_mt) = table.metatable() else {
+            return Ok(false);
+        };
+        let mut current: Option<LuaTable> = value_mt.raw_get("__pxs_class")?;
+        while let Some(class) = current {
+            if class == check_class {
+                return Ok(true);
+            }
+            current = class.raw_get("__pxs_parent")?;
+        }
+        Ok(false)
+    })?;
+    class.raw_set("isinstance", isinstance)?;
+
+    // Calling the subclass constructs through the parent, then switches the metatable.
+    let class_mt = lua.create_table()?;
+    let ctor_parent = parent.clone();
+    let call = lua.create_function(move |_, (_class, args): (LuaTable, LuaMultiValue)| {
+        let obj: LuaTable = ctor_parent.call(args)?;
+        obj.set_metatable(Some(mt.clone()))?;
+        Ok(obj)
+    })?;
+    class_mt.raw_set("__call", call)?;
+    // Statics, constants and `extend` are inherited
+    class_mt.raw_set("__index", parent)?;
+    class.set_metatable(Some(class_mt))?;
+
+    Ok(class)
+}
+
+/// Create the class table of a type.
+///
+/// Holds the static methods and constants, `isinstance`, `extend` and calling it runs the constructor.
+pub(super) fn create_class(lua: &Lua, pixel_type: &Arc<pxs_PixelType>) -> LuaTable {
+    let class = lua.create_table().expect("Could not create class table.");
+    let members = pixel_type.members();
+
+    for constant in members.constants.iter() {
+        class
+            .set(
+                constant.name.clone(),
+                into_lua(lua, &constant.var).expect("Could not convert constant to Lua."),
+            )
+            .expect("Could not set constant");
+    }
+    for method in members.statics.iter() {
+        class
+            .set(method.name.clone(), internal_add_callback(lua, method.idx))
+            .expect("Could not set static method");
+    }
+
+    let type_name = pixel_type.name.clone();
+    let isinstance = lua
+        .create_function(move |_, value: LuaValue| is_instance(&value, &type_name))
+        .expect("Could not create isinstance");
+    class
+        .set("isinstance", isinstance)
+        .expect("Could not set isinstance");
+
+    // `Sword:extend()` creates a script subclass.
+    let base_type = Arc::clone(pixel_type);
+    let extend = lua
+        .create_function(move |lua, parent: LuaTable| {
+            extend_class(lua, parent, get_or_create_metatable(lua, &base_type))
+        })
+        .expect("Could not create extend");
+    class.set("extend", extend).expect("Could not set extend");
+
+    let mt = lua.create_table().expect("Could not create class metatable.");
+    let call = match &members.constructor {
+        Some(constructor) => {
+            let constructor = internal_add_callback(lua, constructor.idx);
+            lua.create_function(move |_, (_class, args): (LuaTable, LuaMultiValue)| {
+                constructor.call::<LuaValue>(args)
+            })
+        }
+        None => {
+            let type_name = pixel_type.name.clone();
+            lua.create_function(move |_, _: LuaMultiValue| -> LuaResult<LuaValue> {
+                Err(LuaError::RuntimeError(format!(
+                    "type '{}' has no constructor",
+                    type_name
+                )))
+            })
+        }
+    }
+    .expect("Could not create __call");
+    mt.set("__call", call).expect("Could not set __call");
+    mt.set("__name", pixel_type.name.clone())
+        .expect("Could not set __name");
+    class
+        .set_metatable(Some(mt))
+        .expect("Could not attach class metatable.");
+
+    class
+}
+
+/// Get the metatable of a type's instances, creating it on first use.
+///
+/// When members were added since it was built, the metatable is refilled in place so live instances see them.
+pub(super) fn get_or_create_metatable(lua: &Lua, pixel_type: &pxs_PixelType) -> LuaTable {
+    let members = pixel_type.members();
+    // Check if the meta table exists already
+    let mt = match get_metatable(&pixel_type.name) {
+        Some(mt) => {
+            let generation: Option<u64> = mt.raw_get("__pxs_generation").unwrap_or(None);
+            if generation == Some(members.generation) {
+                return mt;
+            }
+            mt.clear().expect("Could not clear Metatable");
+            mt
+        }
+        // Create new metatable
+        None => lua.create_table().expect("Could not create Metatable"),
+    };
+    mt.raw_set("__pxs_generation", members.generation)
+        .expect("Could not set __pxs_generation");
+    // Add methods
+    for method in members.methods.iter() {
+        let func = create_object_callback(lua, method.idx, &pixel_type.name);
+        mt.set(method.name.clone(), func)
+            .expect("Could not set method");
+    }
+
+    mt.set("__name", pixel_type.name.clone())
+        .expect("Could not set __name");
+    // Release the table's reference once collected.
+    let gc = lua
+        .create_function(|_, obj: LuaTable| {
+            // Lua can not be used while closing, the lookup is cleared by pxs_finalize.
+            if is_lua_closing() {
+                return Ok(());
+            }
+            let obj_id: i64 = obj.raw_get("_id")?;
+            object_decref(obj_id);
+            Ok(())
+        })
+        .expect("Could not create __gc");
+    mt.set("__gc", gc).expect("Could not set __gc");
+    add_operators(lua, &mt, &pixel_type.name, &members);
+
+    let has_index = members.get_operator(pxs_OperatorKind::pxs_OpIndex).is_some()
+        || members.get_operator(pxs_OperatorKind::pxs_OpNewIndex).is_some();
+    if members.properties.is_empty() && members.fields.is_empty() && !has_index {
         mt.set("__index", mt.clone())
             .expect("Could not set __index");
-        // save it
-        store_metatable(&source.type_name, mt.clone());
-        mt
-    };
+    } else {
+        add_index(lua, &mt, &pixel_type.name, &members);
+    }
+    // save it
+    store_metatable(&pixel_type.name, mt.clone());
+    mt
+}
+
+pub(super) fn create_object(lua: &Lua, idx: i64, source: Arc<pxs_PixelObject>) -> LuaTable {
+    let table = lua.create_table().expect("Could not create table.");
+    table
+        .set("_id", LuaValue::Integer(idx))
+        .expect("Could not set _id on Lua Table.");
+
+    let metatable = get_or_create_metatable(lua, &source.pixel_type);
 
     table
         .set_metatable(Some(metatable))
@@ -0,0 +1,113 @@
+// Copyright 2026 Jordan Castro <jordan@grupojvm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+//
+use std::sync::Arc;
+
+use mlua::prelude::*;
+
+use crate::{
+    lua::{
+        get_metatable, store_metatable,
+        var::{field_from_lua, field_into_lua},
+    },
+    shared::{
+        object::FieldValue,
+        types::{get_value_type, pxs_PixelValue, pxs_ValueType},
+    },
+};
+
+/// Get the metatable of a value type. It only tags the table, the fields are plain table fields.
+fn get_value_metatable(lua: &Lua, value_type: &Arc<pxs_ValueType>) -> LuaResult<LuaTable> {
+    let key = format!("{}__value", value_type.name);
+    if let Some(mt) = get_metatable(&key) {
+        return Ok(mt);
+    }
+
+    let mt = lua.create_table()?;
+    mt.set("__pxs_value", value_type.name.as_str())?;
+    let fields: Vec<String> = value_type.fields.iter().map(|f| f.name.clone()).collect();
+    let name = value_type.name.clone();
+    let tostring = lua.create_function(move |_, table: LuaTable| {
+        let mut parts = vec![];
+        for field in fields.iter() {
+            let value: LuaValue = table.raw_get(field.as_str())?;
+            parts.push(format!("{}={}", field, value.to_string()?));
+        }
+        Ok(format!("{}({})", name, parts.join(", ")))
+    })?;
+    mt.set("__tostring", tostring)?;
+
+    store_metatable(&key, mt.clone());
+    Ok(mt)
+}
+
+/// Convert a Lua value into a field of a value type. Nil is a missing field.
+fn value_field(value_type: &pxs_ValueType, i: usize, value: &LuaValue) -> LuaResult<Option<FieldValue>> {
+    if value.is_nil() {
+        return Ok(None);
+    }
+    field_from_lua(value).map(Some).ok_or_else(|| {
+        LuaError::RuntimeError(format!(
+            "{}.{} can not be a {}",
+            value_type.name,
+            value_type.fields[i].name,
+            value.type_name()
+        ))
+    })
+}
+
+/// Copy a value into a plain Lua table.
+pub(super) fn value_into_lua(lua: &Lua, value: &pxs_PixelValue) -> LuaResult<LuaTable> {
+    let table = lua.create_table()?;
+    for (field, v) in value.value_type.fields.iter().zip(value.fields.iter()) {
+        table.raw_set(field.name.as_str(), field_into_lua(*v))?;
+    }
+    table.set_metatable(Some(get_value_metatable(lua, &value.value_type)?))?;
+    Ok(table)
+}
+
+/// Validate and pack a value table. None if the table is not a value.
+pub(super) fn value_from_lua(table: &LuaTable) -> Option<LuaResult<pxs_PixelValue>> {
+    let name: String = table.metatable()?.raw_get("__pxs_value").ok()?;
+    let value_type = get_value_type(&name)?;
+
+    let pack = || {
+        let mut values = vec![];
+        for (i, field) in value_type.fields.iter().enumerate() {
+            let value: LuaValue = table.raw_get(field.name.as_str())?;
+            values.push(value_field(&value_type, i, &value)?);
+        }
+        value_type.pack(values).map_err(LuaError::RuntimeError)
+    };
+    Some(pack())
+}
+
+/// Create the constructor of a value type, `Vec2(1, 2)`. Runs in Lua only.
+pub(super) fn create_value_constructor(lua: &Lua, value_type: &Arc<pxs_ValueType>) -> LuaFunction {
+    let value_type = Arc::clone(value_type);
+    lua.create_function(move |lua, args: LuaMultiValue| {
+        let mut values = vec![];
+        for (i, arg) in args.iter().enumerate() {
+            if i >= value_type.fields.len() {
+                break;
+            }
+            values.push(value_field(&value_type, i, arg)?);
+        }
+        if args.len() > value_type.fields.len() {
+            return Err(LuaError::RuntimeError(format!(
+                "{} takes at most {} fields, got {}",
+                value_type.name,
+                value_type.fields.len(),
+                args.len()
+            )));
+        }
+        let value = value_type.pack(values).map_err(LuaError::RuntimeError)?;
+        value_into_lua(lua, &value)
+    })
+    .expect("Could not create value constructor")
+}
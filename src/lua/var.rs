@@ -13,15 +13,41 @@ use mlua::prelude::*;
 
 // Pure Rust goes here
 use crate::{
-    lua::object::create_object,
+    lua::{
+        array::LuaArrayView,
+        enums::LuaEnumMember,
+        get_object_table,
+        object::create_object,
+        store_object_table,
+        value::{value_from_lua, value_into_lua},
+    },
     shared::{
-        object::get_object,
+        object::{FieldValue, get_object, object_incref},
         var::{pxs_Var, pxs_VarType},
     },
 };
 
+/// Convert a field or array view value into Lua.
+pub(super) fn field_into_lua(value: FieldValue) -> LuaValue {
+    match value {
+        FieldValue::Int(v) => LuaValue::Integer(v),
+        FieldValue::Float(v) => LuaValue::Number(v),
+        FieldValue::Bool(v) => LuaValue::Boolean(v),
+    }
+}
+
+/// Convert a Lua value into a field or array view value. None if it is not a number or bool.
+pub(super) fn field_from_lua(value: &LuaValue) -> Option<FieldValue> {
+    match value {
+        LuaValue::Integer(v) => Some(FieldValue::Int(*v)),
+        LuaValue::Number(v) => Some(FieldValue::Float(*v)),
+        LuaValue::Boolean(v) => Some(FieldValue::Bool(*v)),
+        _ => None,
+    }
+}
+
 /// Lua Function for freeing memory
-fn free_lua_mem(ptr: *mut c_void) {
+pub(super) fn free_lua_mem(ptr: *mut c_void) {
     if ptr.is_null() {
         return;
     }
@@ -46,6 +72,20 @@ pub(super) fn from_lua(value: LuaValue) -> Result<pxs_Var, anyhow::Error> {
             ))
         }
         LuaValue::Table(t) => {
+            // Host object tables have a `_id` and a named metatable.
+            if let Some(mt) = t.metatable()
+                && matches!(mt.raw_get::<LuaValue>("__name"), Ok(LuaValue::String(_)))
+                && let Ok(Some(idx)) = t.raw_get::<Option<i64>>("_id")
+                && get_object(idx).is_some()
+            {
+                return Ok(pxs_Var::new_host_object(idx));
+            }
+
+            // Value type tables are validated and packed.
+            if let Some(value) = value_from_lua(&t) {
+                return Ok(pxs_Var::new_value(value?));
+            }
+
             // Check if table is actually a list.
             let t_length = t.raw_len();
 
@@ -66,6 +106,17 @@ pub(super) fn from_lua(value: LuaValue) -> Result<pxs_Var, anyhow::Error> {
                 Ok(list_var)
             }
         }
+        LuaValue::UserData(ud) => {
+            if let Ok(view) = ud.borrow::<LuaArrayView>() {
+                // Same host buffer
+                return Ok(pxs_Var::new_array_view(view.0));
+            }
+            // Enum members are their value for the host.
+            if let Ok(member) = ud.borrow::<LuaEnumMember>() {
+                return Ok(pxs_Var::new_i64(member.value));
+            }
+            Ok(pxs_Var::new_null())
+        }
         _ => Ok(pxs_Var::new_null()),
     }
 }
@@ -102,27 +153,22 @@ pub(super) fn into_lua(lua: &Lua, var: &pxs_Var) -> LuaResult<LuaValue> {
             }
         }
         pxs_VarType::pxs_HostObject => {
-            unsafe {
-                let idx = var.value.host_object_val;
-                // let object_lookup = get_object_lookup();
-                let pixel_object = get_object(idx).unwrap();
-                let lang_ptr_is_null = pixel_object.lang_ptr.lock().unwrap().is_null();
-                if lang_ptr_is_null {
-                    // Create the table for the first time and mutate the pixel object.
-                    let table = create_object(lua, idx, Arc::clone(&pixel_object));
-                    // Add table ptr
-                    let table_ptr = Box::into_raw(Box::new(table));
-                    pixel_object.update_lang_ptr(table_ptr as *mut c_void);
-                }
+            let idx = unsafe { var.value.host_object_val };
+            let pixel_object = get_object(idx).ok_or_else(|| {
+                mlua::Error::RuntimeError(format!("Host object {} does not exist.", idx))
+            })?;
 
-                // Get PTR again.
-                let lang_ptr = pixel_object.lang_ptr.lock().unwrap();
-                // Get as table.
-                let table_ptr = *lang_ptr as *const LuaTable;
-                // Return table
-                let table = (&*table_ptr).clone();
-                Ok(mlua::Value::Table(table))
+            // Reuse the table while the script still references it.
+            if let Some(table) = get_object_table(&pixel_object) {
+                return Ok(mlua::Value::Table(table));
             }
+
+            // Create the table, it holds a reference until collected.
+            let table = create_object(lua, idx, Arc::clone(&pixel_object));
+            object_incref(idx);
+            store_object_table(lua, &pixel_object, table.clone());
+
+            Ok(mlua::Value::Table(table))
         }
         pxs_VarType::pxs_List => {
             // Have to convert each item to a lua variable
@@ -154,5 +200,11 @@ pub(super) fn into_lua(lua: &Lua, var: &pxs_Var) -> LuaResult<LuaValue> {
                 Ok(mlua::Value::Function(lua_function))
             }
         }
+        pxs_VarType::pxs_Exception => Err(mlua::Error::RuntimeError(var.get_exception().unwrap())),
+        pxs_VarType::pxs_ArrayView => {
+            let view = LuaArrayView(*var.get_array_view().unwrap());
+            Ok(mlua::Value::UserData(lua.create_userdata(view)?))
+        }
+        pxs_VarType::pxs_Value => Ok(mlua::Value::Table(value_into_lua(lua, var.get_value().unwrap())?)),
     }
 }
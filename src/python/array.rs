@@ -0,0 +1,158 @@
+// Copyright 2026 Jordan Castro <jordan@grupojvm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+//
+use crate::{
+    python::{
+        exec_py,
+        func::{py_get_arg, raise},
+        get_array_view_type, pocketpy, set_array_view_type,
+    },
+    shared::{object::FieldValue, var::pxs_VarArrayView},
+};
+
+/// Get the view of a `_pxs_ArrayView`.
+unsafe fn get_view(obj: pocketpy::py_Ref) -> pxs_VarArrayView {
+    unsafe { *(pocketpy::py_touserdata(obj) as *const pxs_VarArrayView) }
+}
+
+/// Convert a Python index into a view index. Negative indexes count from the end.
+unsafe fn view_index(view: &pxs_VarArrayView, index: pocketpy::py_Ref) -> Result<usize, String> {
+    if unsafe { pocketpy::py_typeof(index) } as i32 != pocketpy::py_PredefinedType::tp_int as i32 {
+        return Err("array view indexes must be integers".to_string());
+    }
+    let index = unsafe { pocketpy::py_toint(index) };
+    let real = if index < 0 { index + view.len as i64 } else { index };
+    if real < 0 || real as usize >= view.len {
+        return Err(format!("index {} out of range for a array view of length {}", index, view.len));
+    }
+    Ok(real as usize)
+}
+
+/// Set a field or array view value.
+pub(super) unsafe fn field_to_pocketpyref(out: pocketpy::py_Ref, value: FieldValue) {
+    unsafe {
+        match value {
+            FieldValue::Int(v) => pocketpy::py_newint(out, v),
+            FieldValue::Float(v) => pocketpy::py_newfloat(out, v),
+            FieldValue::Bool(v) => pocketpy::py_newbool(out, v),
+        }
+    }
+}
+
+/// Get a field or array view value. None if it is not a number or bool.
+pub(super) unsafe fn pocketpyref_to_field(value: pocketpy::py_Ref) -> Option<FieldValue> {
+    unsafe {
+        let tp = pocketpy::py_typeof(value) as i32;
+        // bool is checked first, it is a int subclass
+        if tp == pocketpy::py_PredefinedType::tp_bool as i32 {
+            Some(FieldValue::Bool(pocketpy::py_tobool(value)))
+        } else if tp == pocketpy::py_PredefinedType::tp_int as i32 {
+            Some(FieldValue::Int(pocketpy::py_toint(value)))
+        } else if tp == pocketpy::py_PredefinedType::tp_float as i32 {
+            Some(FieldValue::Float(pocketpy::py_tofloat(value)))
+        } else {
+            None
+        }
+    }
+}
+
+unsafe extern "C" fn view_getitem(argc: i32, argv: pocketpy::py_StackRef) -> bool {
+    if argc != 2 {
+        return unsafe { raise("__getitem__ expects a index.") };
+    }
+    unsafe {
+        let view = get_view(py_get_arg(argv, 0));
+        match view_index(&view, py_get_arg(argv, 1)) {
+            Ok(index) => field_to_pocketpyref(pocketpy::py_retval(), view.get(index).unwrap()),
+            Err(e) => return raise(&e),
+        }
+    }
+    true
+}
+
+unsafe extern "C" fn view_setitem(argc: i32, argv: pocketpy::py_StackRef) -> bool {
+    if argc != 3 {
+        return unsafe { raise("__setitem__ expects a index and value.") };
+    }
+    unsafe {
+        let view = get_view(py_get_arg(argv, 0));
+        let index = match view_index(&view, py_get_arg(argv, 1)) {
+            Ok(index) => index,
+            Err(e) => return raise(&e),
+        };
+        let Some(value) = pocketpyref_to_field(py_get_arg(argv, 2)) else {
+            return raise("array view can only hold numbers and bools");
+        };
+        if let Err(e) = view.set(index, value) {
+            return raise(&e);
+        }
+        pocketpy::py_newnone(pocketpy::py_retval());
+    }
+    true
+}
+
+unsafe extern "C" fn view_len(_argc: i32, argv: pocketpy::py_StackRef) -> bool {
+    unsafe {
+        let view = get_view(py_get_arg(argv, 0));
+        pocketpy::py_newint(pocketpy::py_retval(), view.len as i64);
+    }
+    true
+}
+
+/// Register the `_pxs_ArrayView` type. Needs to be called in every PKPY VM.
+pub(super) unsafe fn setup_array_view() {
+    unsafe {
+        let builtins = pocketpy::py_getmodule(c"builtins".as_ptr());
+        let tp = pocketpy::py_newtype(
+            c"_pxs_ArrayView".as_ptr(),
+            pocketpy::py_PredefinedType::tp_object as pocketpy::py_Type,
+            builtins,
+            None,
+        );
+        pocketpy::py_bindmagic(tp, pocketpy::py_name(c"__getitem__".as_ptr()), Some(view_getitem));
+        pocketpy::py_bindmagic(tp, pocketpy::py_name(c"__setitem__".as_ptr()), Some(view_setitem));
+        pocketpy::py_bindmagic(tp, pocketpy::py_name(c"__len__".as_ptr()), Some(view_len));
+        set_array_view_type(tp);
+    }
+
+    // A generator gives the iterator it's __next__
+    let _ = exec_py(
+        r#"
+def __iter__(self):
+    for i in range(len(self)):
+        yield self[i]
+_pxs_ArrayView.__iter__ = __iter__
+del __iter__
+"#,
+        "<pxs_ArrayView>",
+        "builtins",
+    );
+}
+
+/// Create a `_pxs_ArrayView` in `out`.
+pub(super) unsafe fn new_array_view(out: pocketpy::py_Ref, view: pxs_VarArrayView) {
+    let Some(tp) = get_array_view_type() else {
+        unsafe { pocketpy::py_newnone(out) };
+        return;
+    };
+    unsafe {
+        let ud = pocketpy::py_newobject(out, tp, 0, std::mem::size_of::<pxs_VarArrayView>() as i32);
+        *(ud as *mut pxs_VarArrayView) = view;
+    }
+}
+
+/// Get the view of a ref, if it is a `_pxs_ArrayView`.
+pub(super) unsafe fn as_array_view(obj: pocketpy::py_Ref) -> Option<pxs_VarArrayView> {
+    let tp = get_array_view_type()?;
+    unsafe {
+        if pocketpy::py_typeof(obj) != tp {
+            return None;
+        }
+        Some(get_view(obj))
+    }
+}
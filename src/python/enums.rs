@@ -0,0 +1,127 @@
+// Copyright 2026 Jordan Castro <jordan@grupojvm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+//
+use crate::{
+    python::{add_new_defined_object, exec_py, pocketpy},
+    shared::module::ModuleEnum,
+};
+
+/// Create a enum in a module.
+///
+/// Members are instances of `_{name}` with read-only `name` and `value`. The module attribute is the only
+/// instance of `_{name}__enum`, which holds the members as read-only properties and does the lookups:
+/// `Enum(value)`, `Enum["Name"]`, `value in Enum` and iteration.
+pub(super) fn create_enum(module_enum: &ModuleEnum, module_name: &str) {
+    let name = &module_enum.name;
+    let members = module_enum
+        .members
+        .iter()
+        .map(|m| format!("_{name}('{}', {})", m.name, m.value))
+        .collect::<Vec<String>>()
+        .join(", ");
+    let mut properties = String::new();
+    for (i, member) in module_enum.members.iter().enumerate() {
+        properties.push_str(&format!(
+            r#"
+    @property
+    def {}(self):
+        return _{name}__enum._members[{i}]
+"#,
+            member.name
+        ));
+    }
+
+    let code = format!(
+        r#"
+class _{name}:
+    _pxs_enum = '{name}'
+
+    def __init__(self, name, value):
+        self._name = name
+        self._value = value
+
+    @property
+    def name(self):
+        return self._name
+
+    @property
+    def value(self):
+        return self._value
+
+    def __repr__(self):
+        return '{name}.' + self._name
+
+    def __str__(self):
+        return '{name}.' + self._name
+
+    def __hash__(self):
+        return hash(self._value)
+
+    def __eq__(self, other):
+        if type(other) is _{name}:
+            return self._value == other._value
+        return self._value == other
+
+    def __ne__(self, other):
+        return not self.__eq__(other)
+
+class _{name}__enum:
+    _members = [{members}]
+{properties}
+    def __call__(self, value):
+        for m in _{name}__enum._members:
+            if m._value == value:
+                return m
+        raise ValueError(str(value) + ' is not a valid {name}')
+
+    def __getitem__(self, name):
+        for m in _{name}__enum._members:
+            if m._name == name:
+                return m
+        raise KeyError(name)
+
+    def __contains__(self, value):
+        for m in _{name}__enum._members:
+            if m == value:
+                return True
+        return False
+
+    def __iter__(self):
+        for m in _{name}__enum._members:
+            yield m
+
+    def __len__(self):
+        return len(_{name}__enum._members)
+
+    def __repr__(self):
+        return '<enum {name}>'
+"#
+    );
+    exec_py(&code, &format!("<{name}>"), "builtins");
+    add_new_defined_object(name);
+    add_new_defined_object(&format!("{name}__enum"));
+
+    exec_py(&format!("{name} = _{name}__enum()"), &format!("<{module_name}>"), module_name);
+}
+
+/// Get the value of a enum member, if this is one.
+pub(super) unsafe fn enum_value(pref: pocketpy::py_Ref) -> Option<i64> {
+    unsafe {
+        let tp = pocketpy::py_typeof(pref);
+        let marker = pocketpy::py_tpfindname(tp, pocketpy::py_name(c"_pxs_enum".as_ptr()));
+        if marker.is_null() {
+            return None;
+        }
+
+        let value = pocketpy::py_getdict(pref, pocketpy::py_name(c"_value".as_ptr()));
+        if value.is_null() || pocketpy::py_typeof(value) as i32 != pocketpy::py_PredefinedType::tp_int as i32 {
+            return None;
+        }
+        Some(pocketpy::py_toint(value))
+    }
+}
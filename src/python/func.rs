@@ -9,7 +9,7 @@
 use crate::{
     borrow_string, create_raw_string, free_raw_string,
     python::{get_fn_idx_from_name, pocketpy, var::pocketpyref_to_var, var_to_pocketpyref},
-    shared::{func::call_function, pxs_Runtime, var::pxs_Var},
+    shared::{func::call_function, object::get_object, pxs_Runtime, var::pxs_Var},
 };
 
 /// Use instead of the py_arg macro.
@@ -34,19 +34,48 @@ pub(super) unsafe fn py_assign(left: pocketpy::py_Ref, right: pocketpy::py_Ref)
 //     }
 // }
 
+/// Raise a RuntimeError within pocketpy. Return the result from the bridge.
 pub(super) unsafe fn raise(msg: &str) -> bool {
     let c_msg = create_raw_string!(msg);
     unsafe {
-        let ret_slot = pocketpy::py_retval();
-        pocketpy::py_newstr(ret_slot, c_msg);
+        // Pass the message as a argument so `%` in it is not treated as format.
+        let res = pocketpy::py_exception(
+            pocketpy::py_PredefinedType::tp_RuntimeError as pocketpy::py_Type,
+            c"%s".as_ptr(),
+            c_msg,
+        );
         free_raw_string!(c_msg);
 
-        return true;
+        res
+    }
+}
+
+/// The pocketpy bridge for object callbacks. `argv[1]` is the object handle.
+///
+/// Raises if the object was destroyed, then continues like `pocketpy_bridge` with the object as a host object.
+pub(super) unsafe extern "C" fn pocketpy_object_bridge(argc: i32, argv: pocketpy::py_StackRef) -> bool {
+    if argc < 2 {
+        return unsafe { raise("Python: object callback without a object.") };
+    }
+    let handle = unsafe { py_get_arg(argv, 1) };
+    if unsafe { pocketpy::py_typeof(handle) } as i32 != pocketpy::py_PredefinedType::tp_int as i32 {
+        return unsafe { raise("Python: object callback without a object.") };
+    }
+    let handle = unsafe { pocketpy::py_toint(handle) };
+    if get_object(handle).is_none() {
+        return unsafe { raise("object was destroyed") };
     }
+
+    unsafe { bridge(argc, argv, Some(handle)) }
 }
 
 /// The pocketpy bridge
 pub(super) unsafe extern "C" fn pocketpy_bridge(argc: i32, argv: pocketpy::py_StackRef) -> bool {
+    unsafe { bridge(argc, argv, None) }
+}
+
+/// Call the function named by `argv[0]`. A `object` replaces `argv[1]`.
+unsafe fn bridge(argc: i32, argv: pocketpy::py_StackRef, object: Option<i64>) -> bool {
     // let pyref_size = pocketpy::get_py_TValue_size();
     if argc < 1 {
         unsafe {
@@ -70,13 +99,24 @@ pub(super) unsafe extern "C" fn pocketpy_bridge(argc: i32, argv: pocketpy::py_St
 
     // Convert py_Ref into pxs_Var.
     for i in 1..argc {
+        if let (1, Some(object)) = (i, object) {
+            vars.push(pxs_Var::new_host_object(object));
+            continue;
+        }
         let arg_ref = unsafe { py_get_arg(argv, i as usize) };
-        vars.push(pocketpyref_to_var(arg_ref));
+        let var = pocketpyref_to_var(arg_ref);
+        if var.is_exception() {
+            return unsafe { raise(&var.get_exception().unwrap()) };
+        }
+        vars.push(var);
     }
 
     // Call internal function
     unsafe {
         let res = call_function(fn_idx, vars);
+        if res.is_exception() {
+            return raise(&res.get_exception().unwrap());
+        }
         let ret_slot = pocketpy::py_retval();
 
         var_to_pocketpyref(ret_slot, &res);
@@ -7,7 +7,7 @@
 // Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
 //
 use std::{
-    cell::RefCell,
+    cell::{Cell, RefCell},
     collections::{HashMap, HashSet},
 };
 
@@ -21,7 +21,12 @@ use crate::{
         module::create_module,
         var::{pocketpyref_to_var, var_to_pocketpyref},
     },
-    shared::{PixelScript, read_file, read_file_dir, var::{ObjectMethods, pxs_Var, pxs_VarList}},
+    resolve_module,
+    shared::{
+        PixelScript, pxs_Runtime, read_file, read_file_dir,
+        object::{pxs_PixelObject, release_wrappers},
+        var::{ObjectMethods, pxs_Var, pxs_VarList},
+    },
 };
 
 // Allow for the binidngs only
@@ -32,10 +37,13 @@ pub(self) mod pocketpy {
     include!(concat!(env!("OUT_DIR"), "/pocketpy_bindings.rs"));
 }
 
+mod array;
+mod enums;
 mod func;
 mod module;
 mod object;
 mod var;
+mod value;
 
 thread_local! {
     static PYSTATE: ReentrantMutex<State> = ReentrantMutex::new(init_state());
@@ -51,6 +59,18 @@ struct State {
 
     /// Current thread idx
     thread_idx: RefCell<i32>,
+
+    /// The `_pxs_Handle` type per VM
+    handle_types: RefCell<HashMap<i32, pocketpy::py_Type>>,
+
+    /// The `_pxs_ArrayView` type per VM
+    array_view_types: RefCell<HashMap<i32, pocketpy::py_Type>>,
+
+    /// Key of the next object pinned for the host.
+    next_pin: Cell<i64>,
+
+    /// Is pocketpy initialized?
+    running: Cell<bool>,
 }
 
 pub(self) fn exec_py(code: &str, name: &str, module: &str) -> String {
@@ -98,20 +118,10 @@ fn run_py(
     }
 }
 
-#[allow(unused)]
 pub(self) fn eval_main_py(code: &str, name: &str) -> String {
     run_py(code, name, pocketpy::py_CompileMode::EVAL_MODE, None)
 }
 
-pub(self) fn eval_py(code: &str, name: &str, module_name: &str) -> String {
-    run_py(
-        code,
-        name,
-        pocketpy::py_CompileMode::EVAL_MODE,
-        Some(module_name),
-    )
-}
-
 pub(self) fn exec_main_py(code: &str, name: &str) -> String {
     run_py(code, name, pocketpy::py_CompileMode::EXEC_MODE, None)
     // let c_code = create_raw_string!(code);
@@ -137,6 +147,10 @@ fn init_state() -> State {
         name_to_idx: RefCell::new(HashMap::new()),
         defined_objects: RefCell::new(HashMap::new()),
         thread_idx: RefCell::new(0),
+        handle_types: RefCell::new(HashMap::new()),
+        array_view_types: RefCell::new(HashMap::new()),
+        next_pin: Cell::new(0),
+        running: Cell::new(false),
     }
 }
 
@@ -174,33 +188,116 @@ pub(self) fn get_fn_idx_from_name(name: &str) -> Option<i32> {
     }
 }
 
+/// Remove a name idx fn of the current thread.
+pub(self) fn remove_name_idx_fn(name: &str) {
+    let state = get_py_state();
+    let t = state.thread_idx.borrow();
+    if let Some(m) = state.name_to_idx.borrow_mut().get_mut(&t) {
+        m.remove(name);
+    }
+}
+
 /// Add a new defined object
 pub(self) fn add_new_defined_object(name: &str) {
     let state = get_py_state();
     let t = state.thread_idx.borrow();
-    let mut names = {
-        if let Some(names) = state.defined_objects.borrow_mut().get(&t).cloned() {
-            names
-        } else {
-            let set = HashSet::new();
-            set
-        }
-    };
+    state
+        .defined_objects
+        .borrow_mut()
+        .entry(*t)
+        .or_default()
+        .insert(name.to_string());
+}
 
-    names.insert(name.to_string());
+/// Save the `_pxs_Handle` type of the current VM.
+pub(self) fn set_handle_type(tp: pocketpy::py_Type) {
+    let state = get_py_state();
+    let vm = unsafe { pocketpy::py_currentvm() };
+    state.handle_types.borrow_mut().insert(vm, tp);
 }
 
-/// Check if a object is already defined
-pub(self) fn is_object_defined(name: &str) -> bool {
+/// Get the `_pxs_Handle` type of the current VM.
+pub(self) fn get_handle_type() -> Option<pocketpy::py_Type> {
     let state = get_py_state();
-    let t = state.thread_idx.borrow();
-    if let Some(set) = state.defined_objects.borrow().get(&t) {
-        set.contains(name)
-    } else {
-        false
+    let vm = unsafe { pocketpy::py_currentvm() };
+    state.handle_types.borrow().get(&vm).cloned()
+}
+
+/// Save the `_pxs_ArrayView` type of the current VM.
+pub(self) fn set_array_view_type(tp: pocketpy::py_Type) {
+    let state = get_py_state();
+    let vm = unsafe { pocketpy::py_currentvm() };
+    state.array_view_types.borrow_mut().insert(vm, tp);
+}
+
+/// Get the `_pxs_ArrayView` type of the current VM.
+pub(self) fn get_array_view_type() -> Option<pocketpy::py_Type> {
+    let state = get_py_state();
+    let vm = unsafe { pocketpy::py_currentvm() };
+    state.array_view_types.borrow().get(&vm).cloned()
+}
+
+/// The live wrapper of a host object in one VM, saved in the object's Python wrapper slot.
+///
+/// Not rooted. The handle lives in the wrapper's dict and holds the wrapper in a slot, so the wrapper lives as long as
+/// the handle. The handle's finalizer removes the slot.
+struct PyWrapper {
+    /// The wrapper.
+    value: pocketpy::py_TValue,
+    /// The `_pxs_Handle` that owns the slot.
+    handle: usize,
+}
+
+/// Release logic of a Python wrapper slot.
+fn release_wrapper(ptr: *mut std::ffi::c_void) {
+    unsafe {
+        drop(Box::from_raw(ptr as *mut PyWrapper));
     }
 }
 
+/// Save the live wrapper of a host object in the current VM.
+pub(self) fn set_wrapper(object: &pxs_PixelObject, wrapper: pocketpy::py_Ref, handle: *mut std::ffi::c_void) {
+    let vm = unsafe { pocketpy::py_currentvm() };
+    let wrapper = PyWrapper {
+        value: unsafe { *wrapper },
+        handle: handle as usize,
+    };
+    let ptr = Box::into_raw(Box::new(wrapper));
+    object.set_wrapper(pxs_Runtime::pxs_Python, vm as i64, ptr as *mut std::ffi::c_void, release_wrapper);
+}
+
+/// Get the live wrapper of a host object in the current VM.
+pub(self) fn get_wrapper(object: &pxs_PixelObject) -> Option<pocketpy::py_TValue> {
+    let vm = unsafe { pocketpy::py_currentvm() };
+    let ptr = object.get_wrapper(pxs_Runtime::pxs_Python, vm as i64)?;
+    Some(unsafe { (*(ptr as *const PyWrapper)).value })
+}
+
+/// Remove the wrapper of a host object, if it is owned by this handle.
+pub(self) fn remove_wrapper(object: &pxs_PixelObject, handle: *mut std::ffi::c_void) {
+    let vm = unsafe { pocketpy::py_currentvm() };
+    let Some(ptr) = object.get_wrapper(pxs_Runtime::pxs_Python, vm as i64) else {
+        return;
+    };
+    if unsafe { (*(ptr as *const PyWrapper)).handle } == handle as usize {
+        object.remove_wrapper(pxs_Runtime::pxs_Python, vm as i64);
+    }
+}
+
+/// Get the next pin key.
+pub(self) fn next_pin_key() -> i64 {
+    let state = get_py_state();
+    let key = state.next_pin.get();
+    state.next_pin.set(key + 1);
+    key
+}
+
+/// Is pocketpy initialized?
+pub(self) fn is_py_running() -> bool {
+    let state = get_py_state();
+    state.running.get()
+}
+
 pub(self) fn make_private(name: &str) -> String {
     format!("_pxs_{}", name)
 }
@@ -235,11 +332,36 @@ unsafe extern "C" fn import_file(arg1: *const std::ffi::c_char) -> *mut std::ffi
     }
 }
 
+/// Resolve a module the host did not add, pocketpy asks this before looking for files.
+unsafe extern "C" fn lazy_import(arg1: *const std::ffi::c_char) -> pocketpy::py_GlobalRef {
+    let name = borrow_string!(arg1);
+    // Files win, the resolver is only for names nothing else has.
+    let slashed = name.replace(".", "/");
+    for path in [format!("{slashed}.py"), format!("{slashed}/__init__.py")] {
+        let c_path = create_raw_string!(path);
+        let contents = unsafe { import_file(c_path) };
+        free_raw_string!(c_path);
+        if !contents.is_null() {
+            free_raw_string!(contents);
+            return std::ptr::null_mut();
+        }
+    }
+
+    if !resolve_module(name) {
+        return std::ptr::null_mut();
+    }
+    unsafe { pocketpy::py_getmodule(arg1) }
+}
+
 /// This needs to be called in every PKPY VM.
 unsafe fn setup_module_loader() {
     unsafe {
         let callbacks = pocketpy::py_callbacks();
         (*callbacks).importfile = Some(import_file);
+        (*callbacks).lazyimport = Some(lazy_import);
+        object::setup_object_handles();
+        array::setup_array_view();
+        module::setup_live_vars();
     }
 }
 
@@ -257,10 +379,15 @@ impl PixelScript for PythonScripting {
             setup_module_loader();
         }
         // let _s = exec_main_py("1 + 1", "<init>");
-        let _state = get_py_state();
+        let state = get_py_state();
+        state.running.set(true);
     }
 
     fn stop() {
+        // Wrappers die with the VMs.
+        release_wrappers(pxs_Runtime::pxs_Python, None);
+        let state = get_py_state();
+        state.running.set(false);
         unsafe {
             pocketpy::py_finalize();
         }
@@ -270,6 +397,14 @@ impl PixelScript for PythonScripting {
         create_module(&source, None);
     }
 
+    fn remove_module(source: std::sync::Arc<crate::shared::module::pxs_Module>) {
+        module::remove_module(&source, None);
+    }
+
+    fn add_source_module(name: &str, source: &str) -> String {
+        module::add_source_module(name, source)
+    }
+
     fn execute(code: &str, file_name: &str) -> String {
         let res = exec_main_py(code, file_name);
         res
@@ -299,9 +434,17 @@ impl PixelScript for PythonScripting {
     }
     
     fn clear_state(call_gc: bool) {
-        // Drop defined objects
+        // Drop defined objects, their callbacks are gone with the function lookup.
         let state = get_py_state();
+        let t = *state.thread_idx.borrow();
+        if let Some(names) = state.defined_objects.borrow_mut().remove(&t) {
+            for name in names.iter() {
+                object::remove_class(name);
+            }
+        }
         state.defined_objects.borrow_mut().clear();
+        let vm = unsafe { pocketpy::py_currentvm() };
+        release_wrappers(pxs_Runtime::pxs_Python, Some(vm as i64));
 
         if call_gc {
             // Invoke GC
@@ -338,15 +481,13 @@ impl ObjectMethods for PythonScripting {
         // Call a method on it.
         unsafe {
             let pymethod_name = pocketpy::py_name(method_name);
-            pocketpy::py_getattr(obj_ref, pymethod_name);
-            // Get the result pushed to the stack.
-            let pymethod = pocketpy::py_retval();
             free_raw_string!(method_name);
 
-            // Push method
-            pocketpy::py_push(pymethod);
-            // Push self
-            pocketpy::py_push(obj_ref);
+            // [self] -> [method, self]
+            if !pocketpy::py_pushmethod(pymethod_name) {
+                pocketpy::py_pop();
+                return Err(anyhow!("Python object has no method {}", method));
+            }
 
             add_args(&args.vars);
 
@@ -355,7 +496,9 @@ impl ObjectMethods for PythonScripting {
             // Call it via vectrocall
             let ok = pocketpy::py_vectorcall(args.vars.len() as u16, 0);
             if !ok {
-                return Ok(pxs_Var::new_null());
+                let error = own_string!(pocketpy::py_formatexc());
+                pocketpy::py_clearexc(std::ptr::null_mut());
+                return Err(anyhow!(error));
             }
 
             let result_ref = pocketpy::py_retval();
@@ -494,4 +637,27 @@ impl ObjectMethods for PythonScripting {
     }
 
     
+
+    fn instantiate(class_name: &str, args: &mut pxs_VarList) -> Result<pxs_Var, anyhow::Error> {
+        // Find the class in __main__
+        let err = eval_main_py(class_name, "<instantiate>");
+        if !err.is_empty() {
+            return Err(anyhow!(err));
+        }
+
+        unsafe {
+            pocketpy::py_push(pocketpy::py_retval());
+            pocketpy::py_pushnil();
+        }
+        add_args(&args.vars);
+
+        let ok = unsafe { pocketpy::py_vectorcall(args.vars.len() as u16, 0) };
+        if !ok {
+            let error = unsafe { own_string!(pocketpy::py_formatexc()) };
+            unsafe { pocketpy::py_clearexc(std::ptr::null_mut()) };
+            return Err(anyhow!(error));
+        }
+
+        Ok(object::pin_object(unsafe { pocketpy::py_retval() }))
+    }
 }
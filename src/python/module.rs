@@ -6,7 +6,7 @@
 //
 // Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
 //
-use crate::{create_raw_string, free_raw_string, python::{add_new_name_idx_fn, exec_py, make_private, pocketpy, pocketpy_bridge, var_to_pocketpyref}, shared::{PtrMagic, module::pxs_Module, var::pxs_Var}};
+use crate::{borrow_string, create_raw_string, free_raw_string, python::{add_new_name_idx_fn, remove_name_idx_fn, func::{py_get_arg, raise}, get_fn_idx_from_name, var::pocketpyref_to_var, enums::create_enum, object::define_class, value::define_value_class, exec_py, make_private, pocketpy, pocketpy_bridge, var_to_pocketpyref}, shared::{PtrMagic, func::call_function, module::pxs_Module, pxs_Runtime, var::pxs_Var}};
 
 pub(super) fn create_module(module: &pxs_Module, parent: Option<&str>) {
     // Get module name
@@ -15,9 +15,17 @@ pub(super) fn create_module(module: &pxs_Module, parent: Option<&str>) {
         None => module.name.clone(),
     };
 
-    // Create module
+    // Create module. pocketpy can not delete modules, a removed or replaced one is emptied and reused.
     let c_module_name = create_raw_string!(module_name.clone());
-    let pymodule = unsafe { pocketpy::py_newmodule(c_module_name) };
+    let pymodule = unsafe {
+        let existing = pocketpy::py_getmodule(c_module_name);
+        if existing.is_null() {
+            pocketpy::py_newmodule(c_module_name)
+        } else {
+            clear_module(existing, &module_name);
+            existing
+        }
+    };
 
     // Add variables to module
     for var in module.variables.iter() {
@@ -35,7 +43,7 @@ pub(super) fn create_module(module: &pxs_Module, parent: Option<&str>) {
     }
     
     // Add callbacks to module... This also needs to go through the pybridge
-    for method in module.callbacks.iter() {
+    for method in module.callbacks.iter().filter(|c| c.is_for(pxs_Runtime::pxs_Python)) {
         let full_name = method.full_name.clone();
         // Save function
         add_new_name_idx_fn(full_name.clone(), method.idx);
@@ -44,8 +52,16 @@ pub(super) fn create_module(module: &pxs_Module, parent: Option<&str>) {
         let private_name = make_private(&full_name);
 
         let c_name = create_raw_string!(private_name.clone());
+        // The signature goes in the docstring, for `help()` and `__doc__`.
+        let doc = match method.signature.as_ref() {
+            Some(signature) => format!(
+                "\n    \"{}\"",
+                py_escape(&signature.describe(&method.name, pxs_Runtime::pxs_Python))
+            ),
+            None => String::new(),
+        };
         let bridge_code = format!(r#"
-def {}(*args):
+def {}(*args):{doc}
     return {private_name}('{}', *args)
 "#, method.name, full_name);
 
@@ -63,12 +79,280 @@ def {}(*args):
         }
     }
 
-    // Do the same for internal modules
+    // Add types as classes
+    for pixel_type in module.types.iter() {
+        let class = define_class(pixel_type);
+        if class.is_null() {
+            continue;
+        }
+        let c_type_name = create_raw_string!(pixel_type.name.clone());
+        unsafe {
+            pocketpy::py_setdict(pymodule, pocketpy::py_name(c_type_name), class);
+            free_raw_string!(c_type_name);
+        }
+    }
+
+    // Add value types as classes
+    for value_type in module.value_types.iter() {
+        let class = define_value_class(value_type);
+        if class.is_null() {
+            continue;
+        }
+        let c_type_name = create_raw_string!(value_type.name.clone());
+        unsafe {
+            pocketpy::py_setdict(pymodule, pocketpy::py_name(c_type_name), class);
+            free_raw_string!(c_type_name);
+        }
+    }
+
+    // Add enums
+    for module_enum in module.enums.iter() {
+        create_enum(module_enum, &module_name);
+    }
+
+    // Live variables are never stored in the module, reads fall through to `module_getattr`.
+    for live_var in module.live_vars.iter() {
+        add_new_name_idx_fn(live_var_name(&module_name, &live_var.name, "get"), live_var.getter);
+        if let Some(setter) = live_var.setter {
+            add_new_name_idx_fn(live_var_name(&module_name, &live_var.name, "set"), setter);
+        }
+    }
+
+    // Guard attribute writes, see `module_setattr`. Set last, the module is still filled through it's dict.
+    let constants = module
+        .variables
+        .iter()
+        .filter(|v| v.constant)
+        .map(|v| format!("'{}', ", v.name))
+        .collect::<String>();
+    exec_py(
+        &format!("_pxs_frozen = {}\n_pxs_constants = ({constants})", if module.frozen { "True" } else { "False" }),
+        &format!("<{module_name}>"),
+        &module_name,
+    );
+
+    // Do the same for internal modules, and link them so `from pxs import math` finds them
     for im in module.modules.iter() {
         create_module(im, Some(&module_name));
+        let c_child_path = create_raw_string!(format!("{module_name}.{}", im.name));
+        let c_child_name = create_raw_string!(im.name.clone());
+        unsafe {
+            let child = pocketpy::py_getmodule(c_child_path);
+            if !child.is_null() {
+                pocketpy::py_setdict(pymodule, pocketpy::py_name(c_child_name), child);
+            }
+            free_raw_string!(c_child_path);
+            free_raw_string!(c_child_name);
+        }
     }
 
     unsafe {
         free_raw_string!(c_module_name);
     }
 }
+
+/// Remove a module and it's internal modules.
+///
+/// pocketpy keeps every module it created, so the module is emptied instead.
+pub(super) fn remove_module(module: &pxs_Module, parent: Option<&str>) {
+    let module_name = match parent {
+        Some(s) => format!("{s}.{}", module.name),
+        None => module.name.clone(),
+    };
+
+    for im in module.modules.iter() {
+        remove_module(im, Some(&module_name));
+    }
+
+    for method in module.callbacks.iter() {
+        remove_name_idx_fn(&method.full_name);
+    }
+    for live_var in module.live_vars.iter() {
+        remove_name_idx_fn(&live_var_name(&module_name, &live_var.name, "get"));
+        remove_name_idx_fn(&live_var_name(&module_name, &live_var.name, "set"));
+    }
+
+    let c_module_name = create_raw_string!(module_name.clone());
+    unsafe {
+        let pymodule = pocketpy::py_getmodule(c_module_name);
+        if !pymodule.is_null() {
+            clear_module(pymodule, &module_name);
+        }
+        free_raw_string!(c_module_name);
+    }
+}
+
+/// Add a module from Python source, executed into a new module right away.
+///
+/// The code is named like the file it would be imported from, `helpers/math.py` for `helpers.math`.
+pub(super) fn add_source_module(name: &str, source: &str) -> String {
+    let c_name = create_raw_string!(name);
+    unsafe {
+        let existing = pocketpy::py_getmodule(c_name);
+        if existing.is_null() {
+            pocketpy::py_newmodule(c_name);
+        } else {
+            clear_module(existing, name);
+        }
+        free_raw_string!(c_name);
+    }
+
+    exec_py(source, &format!("{}.py", name.replace(".", "/")), name)
+}
+
+/// Empty a module, only `__name__`, `__package__` and `__path__` stay.
+unsafe fn clear_module(pymodule: pocketpy::py_Ref, module_path: &str) {
+    let (package, name) = module_path.rsplit_once('.').unwrap_or(("", module_path));
+    unsafe {
+        pocketpy::py_cleardict(pymodule);
+        for (key, value) in [(c"__name__", name), (c"__package__", package), (c"__path__", module_path)] {
+            let c_value = create_raw_string!(value);
+            pocketpy::py_newstr(pocketpy::py_emplacedict(pymodule, pocketpy::py_name(key.as_ptr())), c_value);
+            free_raw_string!(c_value);
+        }
+    }
+}
+
+/// Escape text for a double quoted Python string.
+fn py_escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+/// Name a live variable callback is saved under.
+fn live_var_name(module_path: &str, name: &str, kind: &str) -> String {
+    format!("{module_path}.{name}__{kind}")
+}
+
+/// Get the `__path__` of a module.
+unsafe fn module_path(module: pocketpy::py_Ref) -> String {
+    unsafe {
+        let path = pocketpy::py_getdict(module, pocketpy::py_name(c"__path__".as_ptr()));
+        if path.is_null() {
+            return String::new();
+        }
+        borrow_string!(pocketpy::py_tostr(path)).to_string()
+    }
+}
+
+/// `__getattr__` of modules. Only called for names that are not in the module.
+///
+/// Reads live variables, anything else is imported as a submodule like pocketpy does.
+unsafe extern "C" fn module_getattr(argc: i32, argv: pocketpy::py_StackRef) -> bool {
+    if argc != 2 {
+        return unsafe { raise("__getattr__ expects a name.") };
+    }
+    unsafe {
+        let path = module_path(py_get_arg(argv, 0));
+        let name = borrow_string!(pocketpy::py_tostr(py_get_arg(argv, 1)));
+
+        if let Some(idx) = get_fn_idx_from_name(&live_var_name(&path, name, "get")) {
+            let res = call_function(idx, vec![pxs_Var::new_i64(pxs_Runtime::pxs_Python as i64)]);
+            if res.is_exception() {
+                return raise(&res.get_exception().unwrap());
+            }
+            var_to_pocketpyref(pocketpy::py_retval(), &res);
+            return true;
+        }
+
+        let c_path = create_raw_string!(format!("{path}.{name}"));
+        let res = pocketpy::py_import(c_path);
+        free_raw_string!(c_path);
+        match res {
+            -1 => false,
+            1 => true,
+            _ => attribute_error(&format!("module '{path}' has no attribute '{name}'")),
+        }
+    }
+}
+
+/// Attribute writes of modules. Live variables call their setter, anything else is stored in the module.
+unsafe extern "C" fn module_setattr(module: pocketpy::py_Ref, name: pocketpy::py_Name, val: pocketpy::py_Ref) -> bool {
+    unsafe {
+        let path = module_path(module);
+        let r_name = borrow_string!(pocketpy::py_name2str(name));
+
+        if let Some(idx) = get_fn_idx_from_name(&live_var_name(&path, r_name, "set")) {
+            let argv = vec![pxs_Var::new_i64(pxs_Runtime::pxs_Python as i64), pocketpyref_to_var(val)];
+            let res = call_function(idx, argv);
+            if res.is_exception() {
+                return raise(&res.get_exception().unwrap());
+            }
+            return true;
+        }
+        if get_fn_idx_from_name(&live_var_name(&path, r_name, "get")).is_some() {
+            return raise(&format!("variable '{r_name}' is read-only"));
+        }
+        if let Some(msg) = read_only_error(module, &path, r_name) {
+            return attribute_error(&msg);
+        }
+
+        pocketpy::py_setdict(module, name, val);
+        true
+    }
+}
+
+/// Attribute deletes of modules, guarded like writes.
+unsafe extern "C" fn module_delattr(module: pocketpy::py_Ref, name: pocketpy::py_Name) -> bool {
+    unsafe {
+        let path = module_path(module);
+        let r_name = borrow_string!(pocketpy::py_name2str(name));
+        if let Some(msg) = read_only_error(module, &path, r_name) {
+            return attribute_error(&msg);
+        }
+
+        if !pocketpy::py_deldict(module, name) {
+            return attribute_error(&format!("module '{path}' has no attribute '{r_name}'"));
+        }
+        true
+    }
+}
+
+/// Why a attribute of a host module can not be written. None for script modules and writable attributes.
+unsafe fn read_only_error(module: pocketpy::py_Ref, path: &str, name: &str) -> Option<String> {
+    unsafe {
+        let frozen = pocketpy::py_getdict(module, pocketpy::py_name(c"_pxs_frozen".as_ptr()));
+        if frozen.is_null() {
+            return None;
+        }
+        if name.starts_with("_pxs_") || pocketpy::py_tobool(frozen) {
+            return Some(format!("module '{path}' is read-only"));
+        }
+
+        let constants = pocketpy::py_getdict(module, pocketpy::py_name(c"_pxs_constants".as_ptr()));
+        if constants.is_null() {
+            return None;
+        }
+        for i in 0..pocketpy::py_tuple_len(constants) {
+            if borrow_string!(pocketpy::py_tostr(pocketpy::py_tuple_getitem(constants, i))) == name {
+                return Some(format!("constant '{name}' is read-only"));
+            }
+        }
+        None
+    }
+}
+
+/// Raise a `AttributeError`.
+unsafe fn attribute_error(msg: &str) -> bool {
+    let c_msg = create_raw_string!(msg);
+    unsafe {
+        let res = pocketpy::py_exception(
+            pocketpy::py_PredefinedType::tp_AttributeError as pocketpy::py_Type,
+            c"%s".as_ptr(),
+            c_msg,
+        );
+        free_raw_string!(c_msg);
+        res
+    }
+}
+
+/// Route module attribute reads and writes through the live variable hooks.
+pub(super) unsafe fn setup_live_vars() {
+    unsafe {
+        let tp = pocketpy::py_PredefinedType::tp_module as pocketpy::py_Type;
+        pocketpy::py_bindmagic(tp, pocketpy::py_name(c"__getattr__".as_ptr()), Some(module_getattr));
+        pocketpy::py_tphookattributes(tp, None, Some(module_setattr), Some(module_delattr), None);
+    }
+}
@@ -6,67 +6,391 @@
 //
 // Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
 //
-use std::sync::Arc;
+use std::{
+    ffi::{CStr, c_void},
+    sync::Arc,
+};
 
 use crate::{
-    create_raw_string, free_raw_string,
+    borrow_string, create_raw_string, free_raw_string,
     python::{
-        add_new_defined_object, add_new_name_idx_fn, eval_py, exec_py, is_object_defined,
-        make_private, pocketpy, pocketpy_bridge,
+        add_new_defined_object, add_new_name_idx_fn,
+        array::{field_to_pocketpyref, pocketpyref_to_field},
+        exec_py,
+        func::{pocketpy_bridge, pocketpy_object_bridge, py_get_arg, raise},
+        get_handle_type, get_wrapper, is_py_running, next_pin_key, pocketpy,
+        remove_wrapper, set_handle_type, set_wrapper, var_to_pocketpyref,
+    },
+    shared::{
+        object::{
+            ObjectField, get_object, object_decref, object_incref, pxs_OperatorKind,
+            pxs_PixelObject,
+        },
+        types::pxs_PixelType,
+        var::pxs_Var,
     },
-    shared::object::pxs_PixelObject,
 };
 
-fn save_object_function(name: &str, idx: i32, module_name: &str) {
+/// Finalizer of `_pxs_Handle`. Releases the wrapper's reference on the object.
+unsafe extern "C" fn handle_dtor(ud: *mut c_void) {
+    if ud.is_null() {
+        return;
+    }
+    let idx = unsafe { *(ud as *const i64) };
+    // A destroyed object released it's slots already.
+    if let Some(object) = get_object(idx) {
+        remove_wrapper(&object, ud);
+    }
+    object_decref(idx);
+}
+
+/// Wrap a object in a new instance of `class`, left in `py_retval`.
+///
+/// pocketpy has no `__del__`, so each wrapper keeps a `_pxs_Handle` in it's dict and the handle's finalizer releases
+/// the object. The wrapper becomes the live wrapper of the object, so the host reaches script overrides through it.
+/// The handle holds the wrapper in a slot, a script keeping `_pxs_handle` keeps the cached wrapper alive too.
+unsafe fn wrap_object(idx: i64, object: &pxs_PixelObject, class: pocketpy::py_Type) -> bool {
+    let Some(handle_type) = get_handle_type() else {
+        return false;
+    };
+
+    unsafe {
+        let wrapper = pocketpy::py_pushtmp();
+        pocketpy::py_newobject(wrapper, class, -1, 0);
+        let handle = pocketpy::py_pushtmp();
+        let ud = pocketpy::py_newobject(handle, handle_type, 1, std::mem::size_of::<i64>() as i32);
+        *(ud as *mut i64) = idx;
+        pocketpy::py_setslot(handle, 0, wrapper);
+        object_incref(idx);
+        set_wrapper(object, wrapper, ud);
+
+        let ptr = pocketpy::py_pushtmp();
+        pocketpy::py_newint(ptr, idx);
+        pocketpy::py_setdict(wrapper, pocketpy::py_name(c"ptr".as_ptr()), ptr);
+        pocketpy::py_setdict(wrapper, pocketpy::py_name(c"_pxs_handle".as_ptr()), handle);
+        *pocketpy::py_retval() = *wrapper;
+        pocketpy::py_shrink(3);
+    }
+    true
+}
+
+/// Get the object a wrapper holds a handle on. Only handles created by `wrap_object` count, not the `ptr` attribute.
+unsafe fn wrapped_object(wrapper: pocketpy::py_Ref) -> Option<(i64, Arc<pxs_PixelObject>)> {
+    let handle_type = get_handle_type()?;
+    unsafe {
+        // Generated classes have a `_pxs_host` class attribute, and their instances a dict.
+        let marker = pocketpy::py_tpfindname(pocketpy::py_typeof(wrapper), pocketpy::py_name(c"_pxs_host".as_ptr()));
+        if marker.is_null() || !(*wrapper).is_ptr {
+            return None;
+        }
+        let handle = pocketpy::py_getdict(wrapper, pocketpy::py_name(c"_pxs_handle".as_ptr()));
+        if handle.is_null() || !pocketpy::py_istype(handle, handle_type) {
+            return None;
+        }
+        let idx = *(pocketpy::py_touserdata(handle) as *const i64);
+        get_object(idx).map(|object| (idx, object))
+    }
+}
+
+/// `_pxs_rewrap(obj, cls)` makes a instance of the script subclass `cls` the wrapper of the object `obj` wraps.
+///
+/// Used by the constructor of script subclasses. `cls` must be a subclass of the object's type.
+unsafe extern "C" fn rewrap(argc: i32, argv: pocketpy::py_StackRef) -> bool {
+    if argc != 2 {
+        return unsafe { raise("_pxs_rewrap expects a host object and a class.") };
+    }
+    unsafe {
+        let Some((idx, object)) = wrapped_object(py_get_arg(argv, 0)) else {
+            return raise("_pxs_rewrap expects a host object.");
+        };
+        let cls = py_get_arg(argv, 1);
+        let base = get_class(&object.type_name);
+        if base.is_null()
+            || !pocketpy::py_istype(cls, pocketpy::py_PredefinedType::tp_type as pocketpy::py_Type)
+            || !pocketpy::py_issubclass(pocketpy::py_totype(cls), pocketpy::py_totype(base))
+        {
+            return raise(&format!("_pxs_rewrap expects a subclass of '{}'.", object.type_name));
+        }
+        if !wrap_object(idx, &object, pocketpy::py_totype(cls)) {
+            return raise("_pxs_Handle is not registered.");
+        }
+    }
+    true
+}
+
+/// Get the live object of a `ptr` argument.
+unsafe fn object_arg(ptr: pocketpy::py_Ref) -> Option<Arc<pxs_PixelObject>> {
+    unsafe {
+        if pocketpy::py_typeof(ptr) as i32 != pocketpy::py_PredefinedType::tp_int as i32 {
+            return None;
+        }
+        get_object(pocketpy::py_toint(ptr))
+    }
+}
+
+/// Find the field `name` on the type of the object. Offsets and kinds only come from the type, never from the script.
+unsafe fn field_arg(object: &pxs_PixelObject, name: pocketpy::py_Ref) -> Result<ObjectField, String> {
+    unsafe {
+        if pocketpy::py_typeof(name) as i32 != pocketpy::py_PredefinedType::tp_str as i32 {
+            return Err("field name must be a str".to_string());
+        }
+        let name = borrow_string!(pocketpy::py_tostr(name));
+        object
+            .pixel_type
+            .members()
+            .fields
+            .iter()
+            .find(|field| field.name == name)
+            .cloned()
+            .ok_or_else(|| format!("'{}' has no field '{}'", object.type_name, name))
+    }
+}
+
+/// `_pxs_getfield(ptr, name)` reads a field straight from host memory.
+unsafe extern "C" fn get_field(argc: i32, argv: pocketpy::py_StackRef) -> bool {
+    if argc != 2 {
+        return unsafe { raise("_pxs_getfield expects a object id and name.") };
+    }
+    unsafe {
+        let Some(object) = object_arg(py_get_arg(argv, 0)) else {
+            return raise("object was destroyed");
+        };
+        let field = match field_arg(&object, py_get_arg(argv, 1)) {
+            Ok(field) => field,
+            Err(e) => return raise(&e),
+        };
+        field_to_pocketpyref(pocketpy::py_retval(), field.read(object.ptr));
+    }
+    true
+}
+
+/// `_pxs_setfield(ptr, name, value)` writes a field straight into host memory.
+unsafe extern "C" fn set_field(argc: i32, argv: pocketpy::py_StackRef) -> bool {
+    if argc != 3 {
+        return unsafe { raise("_pxs_setfield expects a object id, name and value.") };
+    }
+    unsafe {
+        let Some(object) = object_arg(py_get_arg(argv, 0)) else {
+            return raise("object was destroyed");
+        };
+        let field = match field_arg(&object, py_get_arg(argv, 1)) {
+            Ok(field) => field,
+            Err(e) => return raise(&e),
+        };
+        if field.readonly {
+            return raise(&format!("field '{}' is read-only", field.name));
+        }
+        let Some(value) = pocketpyref_to_field(py_get_arg(argv, 2)) else {
+            return raise(&format!("field '{}' can only be set to a number or bool", field.name));
+        };
+        if let Err(e) = field.write(object.ptr, value) {
+            return raise(&e);
+        }
+        pocketpy::py_newnone(pocketpy::py_retval());
+    }
+    true
+}
+
+/// Register the `_pxs_Handle` type and the object builtins. Needs to be called in every PKPY VM.
+pub(super) unsafe fn setup_object_handles() {
+    unsafe {
+        let handle_type = pocketpy::py_newtype(
+            c"_pxs_Handle".as_ptr(),
+            pocketpy::py_PredefinedType::tp_object as pocketpy::py_Type,
+            std::ptr::null_mut(),
+            Some(handle_dtor),
+        );
+        set_handle_type(handle_type);
+
+        let builtins = pocketpy::py_getmodule(c"builtins".as_ptr());
+        pocketpy::py_bindfunc(builtins, c"_pxs_rewrap".as_ptr(), Some(rewrap));
+        pocketpy::py_bindfunc(builtins, c"_pxs_getfield".as_ptr(), Some(get_field));
+        pocketpy::py_bindfunc(builtins, c"_pxs_setfield".as_ptr(), Some(set_field));
+
+        // Objects pinned for the host
+        let pinned = pocketpy::py_emplacedict(builtins, pocketpy::py_name(c"_pxs_pinned".as_ptr()));
+        pocketpy::py_newdict(pinned);
+    }
+}
+
+/// Get the `_pxs_pinned` dict of the current VM.
+unsafe fn pinned_dict() -> pocketpy::py_ItemRef {
+    unsafe {
+        let builtins = pocketpy::py_getmodule(c"builtins".as_ptr());
+        pocketpy::py_getdict(builtins, pocketpy::py_name(c"_pxs_pinned".as_ptr()))
+    }
+}
+
+/// Pin a object for the host. The returned object var keeps it alive until it is freed.
+pub(super) fn pin_object(obj: pocketpy::py_Ref) -> pxs_Var {
+    let key = next_pin_key();
+    // The var points at a copy of the ref, which stays valid while pinned.
+    // Copied first since `obj` may be `py_retval`.
+    let boxed = Box::into_raw(Box::new(unsafe { *obj }));
+    unsafe {
+        let pinned = pinned_dict();
+        if !pinned.is_null() {
+            pocketpy::py_dict_setitem_by_int(pinned, key, boxed);
+        }
+    }
+    pxs_Var::new_object(
+        boxed as *mut c_void,
+        Some(Box::new(move |ptr| unpin_object(key, ptr))),
+    )
+}
+
+/// Release a object pinned by `pin_object`.
+fn unpin_object(key: i64, ptr: *mut c_void) {
+    if !ptr.is_null() {
+        let _ = unsafe { Box::from_raw(ptr as pocketpy::py_Ref) };
+    }
+    // Everything was released with the VM.
+    if !is_py_running() {
+        return;
+    }
+    unsafe {
+        let pinned = pinned_dict();
+        if !pinned.is_null() {
+            pocketpy::py_dict_delitem_by_int(pinned, key);
+        }
+    }
+}
+
+/// Bind a type's callback into builtins, where the generated classes live.
+///
+/// Type callbacks share one builtin per bridge, the full name (first argument) picks the callback. Returns
+/// the builtin to call.
+fn save_object_function(name: &str, idx: i32, on_object: bool) -> &'static str {
     add_new_name_idx_fn(name.to_string(), idx);
 
-    // Create a private name
-    let private_name = make_private(name);
+    let (builtin, bridge): (&'static CStr, pocketpy::py_CFunction) = if on_object {
+        (c"_pxs_objectcall", Some(pocketpy_object_bridge))
+    } else {
+        (c"_pxs_typecall", Some(pocketpy_bridge))
+    };
+    unsafe {
+        let builtins = pocketpy::py_getmodule(c"builtins".as_ptr());
+        pocketpy::py_bindfunc(builtins, builtin.as_ptr(), bridge);
+    }
+    builtin.to_str().unwrap()
+}
 
-    // C stuff
-    let c_name = create_raw_string!(private_name.clone());
-    let c_main = create_raw_string!("__main__");
-    let bridge_code = format!(
+/// Python code for a binary operator. Optionally with the reflected version (i.e. `__radd__`).
+fn binary_dunders(name: &str, reflected: bool, call: &str) -> String {
+    let mut res = format!(
         r#"
-def {name}(*args):
-    return {private_name}('{name}', *args)
+    def __{name}__(self, other):
+        return {call}, other, False)
 "#
     );
-    let c_brige_name = format!("<callback_bridge for {private_name}>");
-    unsafe {
-        let global_scope = pocketpy::py_getmodule(c_main);
+    if reflected {
+        res.push_str(&format!(
+            r#"
+    def __r{name}__(self, other):
+        return {call}, other, True)
+"#
+        ));
+    }
+    res
+}
 
-        pocketpy::py_bindfunc(global_scope, c_name, Some(pocketpy_bridge));
+/// Get the class of a type, if it was defined in the current VM.
+pub(super) fn get_class(type_name: &str) -> pocketpy::py_ItemRef {
+    let c_name = create_raw_string!(format!("_{type_name}"));
+    unsafe {
+        let builtins = pocketpy::py_getmodule(c"builtins".as_ptr());
+        let class = pocketpy::py_getdict(builtins, pocketpy::py_name(c_name));
+        free_raw_string!(c_name);
+        class
+    }
+}
 
-        // Execute bridge
-        let _s = exec_py(&bridge_code, &c_brige_name, module_name);
+/// Remove the class of a type from the current VM.
+pub(super) fn remove_class(type_name: &str) {
+    let c_name = create_raw_string!(format!("_{type_name}"));
+    unsafe {
+        let builtins = pocketpy::py_getmodule(c"builtins".as_ptr());
+        pocketpy::py_deldict(builtins, pocketpy::py_name(c_name));
         free_raw_string!(c_name);
-        free_raw_string!(c_main);
     }
 }
 
-/// Create a object type in the Python Runtime.
+/// Get the members generation a class was built from.
+fn class_generation(class: pocketpy::py_ItemRef) -> Option<u64> {
+    unsafe {
+        let generation = pocketpy::py_getdict(class, pocketpy::py_name(c"_pxs_generation".as_ptr()));
+        if generation.is_null() || pocketpy::py_typeof(generation) as i32 != pocketpy::py_PredefinedType::tp_int as i32 {
+            return None;
+        }
+        Some(pocketpy::py_toint(generation) as u64)
+    }
+}
+
+/// Define the class of a type in the Python Runtime and return it.
 ///
-/// idx: is the saved object.
-/// source: is the object methods
-pub(super) fn create_object(idx: i32, source: Arc<pxs_PixelObject>, module_name: &str) {
-    // Check if object is defined.
-    let obj_exists = is_object_defined(&source.type_name);
-    if obj_exists {
-        eval_py(
-            format!("_{}({})", source.type_name, idx).as_str(),
-            format!("<create_{}>", &source.type_name).as_str(),
-            module_name,
-        );
-        return;
+/// Classes are defined once per VM in `builtins` as `_{type_name}`, so the class a module exposes and
+/// the class of every instance are the same, and `isinstance` works. When members were added since, the
+/// class is rebuilt under a temporary name and copied onto the live class, so existing instances and
+/// script subclasses see them too.
+pub(super) fn define_class(pixel_type: &pxs_PixelType) -> pocketpy::py_ItemRef {
+    let type_name = &pixel_type.name;
+    let members = pixel_type.members();
+    let class = get_class(type_name);
+    if !class.is_null() && class_generation(class) == Some(members.generation) {
+        return class;
+    }
+    let rebuild = !class.is_null();
+    let class_name = if rebuild {
+        format!("_{type_name}__rebuild")
+    } else {
+        format!("_{type_name}")
+    };
+
+    // Calling the class runs the host constructor. Existing objects are wrapped by `create_object`.
+    let mut methods_str = match &members.constructor {
+        Some(constructor) => {
+            let builtin = save_object_function(&constructor.full_name, constructor.idx, false);
+            format!(
+                r#"
+    def __new__(cls, *args):
+        self = {}('{}', *args)
+        if cls is not _{type_name}:
+            # Script subclass, it becomes the wrapper of the new object.
+            self = _pxs_rewrap(self, cls)
+        return self
+"#,
+                builtin,
+                constructor.full_name
+            )
+        }
+        None => format!(
+            r#"
+    def __new__(cls, *args):
+        raise TypeError("type '{type_name}' has no constructor")
+"#
+        ),
+    };
+
+    // Static methods
+    for method in members.statics.iter() {
+        let builtin = save_object_function(&method.full_name, method.idx, false);
+        methods_str.push_str(&format!(
+            r#"
+    @staticmethod
+    def {}(*args):
+        return {}('{}', *args)
+"#,
+            method.name,
+            builtin,
+            method.full_name
+        ));
     }
 
-    // Object does not exist
-    // First register callbacks
-    let mut methods_str = String::new();
-    for method in source.callbacks.iter() {
-        let private_name = make_private(&method.name);
-        save_object_function(&method.name, method.idx, module_name);
+    // Methods
+    for method in members.methods.iter() {
+        // Saved by full name so methods of different types do not collide.
+        let full_name = &method.full_name;
+        let builtin = save_object_function(full_name, method.idx, true);
         methods_str.push_str(
             format!(
                 r#"
@@ -74,50 +398,219 @@ pub(super) fn create_object(idx: i32, source: Arc<pxs_PixelObject>, module_name:
         return {}('{}', self.ptr, *args)
         
 "#,
-                method.name, private_name, method.name
+                method.name, builtin, full_name
             )
             .as_str(),
         );
     }
 
-    let object_string = format!(
+    // Then properties
+    for prop in members.properties.iter() {
+        let getter_name = &prop.getter.full_name;
+        let builtin = save_object_function(getter_name, prop.getter.idx, true);
+        methods_str.push_str(
+            format!(
+                r#"
+    @property
+    def {}(self):
+        return {}('{}', self.ptr)
+"#,
+                prop.name,
+                builtin,
+                getter_name
+            )
+            .as_str(),
+        );
+
+        // No setter means a read-only property, pocketpy raises a TypeError on assignment.
+        if let Some(setter) = &prop.setter {
+            let builtin = save_object_function(&setter.full_name, setter.idx, true);
+            methods_str.push_str(
+                format!(
+                    r#"
+    @{}.setter
+    def {}(self, value):
+        {}('{}', self.ptr, value)
+"#,
+                    prop.name,
+                    prop.name,
+                    builtin,
+                    setter.full_name
+                )
+                .as_str(),
+            );
+        }
+    }
+
+    // Then fields, no callback in between
+    for field in members.fields.iter() {
+        let args = format!("self.ptr, '{}'", field.name);
+        methods_str.push_str(
+            format!(
+                r#"
+    @property
+    def {name}(self):
+        return _pxs_getfield({args})
+"#,
+                name = field.name,
+            )
+            .as_str(),
+        );
+        if !field.readonly {
+            methods_str.push_str(
+                format!(
+                    r#"
+    @{name}.setter
+    def {name}(self, value):
+        _pxs_setfield({args}, value)
+"#,
+                    name = field.name,
+                )
+                .as_str(),
+            );
+        }
+    }
+
+    // Then operators
+    for op in members.operators.iter() {
+        let full_name = &op.callback.full_name;
+        let builtin = save_object_function(full_name, op.callback.idx, true);
+        let call = format!("{builtin}('{full_name}', self.ptr");
+        let dunders = match op.kind {
+            pxs_OperatorKind::pxs_OpAdd => binary_dunders("add", true, &call),
+            pxs_OperatorKind::pxs_OpSub => binary_dunders("sub", true, &call),
+            pxs_OperatorKind::pxs_OpMul => binary_dunders("mul", true, &call),
+            pxs_OperatorKind::pxs_OpDiv => binary_dunders("truediv", true, &call),
+            // pocketpy requires __ne__ alongside __eq__
+            pxs_OperatorKind::pxs_OpEq => format!(
+                r#"{}
+    def __ne__(self, other):
+        return not self.__eq__(other)
+"#,
+                binary_dunders("eq", false, &call)
+            ),
+            pxs_OperatorKind::pxs_OpLt => binary_dunders("lt", false, &call),
+            pxs_OperatorKind::pxs_OpLe => binary_dunders("le", false, &call),
+            pxs_OperatorKind::pxs_OpToString => format!(
+                r#"
+    def __str__(self):
+        return {call})
+
+    def __repr__(self):
+        return {call})
+"#
+            ),
+            pxs_OperatorKind::pxs_OpLen => format!(
+                r#"
+    def __len__(self):
+        return {call})
+"#
+            ),
+            pxs_OperatorKind::pxs_OpUnm => format!(
+                r#"
+    def __neg__(self):
+        return {call})
+"#
+            ),
+            pxs_OperatorKind::pxs_OpCall => format!(
+                r#"
+    def __call__(self, *args):
+        return {call}, *args)
+"#
+            ),
+            pxs_OperatorKind::pxs_OpIndex => format!(
+                r#"
+    def __getitem__(self, key):
+        return {call}, key)
+"#
+            ),
+            pxs_OperatorKind::pxs_OpNewIndex => format!(
+                r#"
+    def __setitem__(self, key, value):
+        {call}, key, value)
+"#
+            ),
+            // A generator, which gives the iterator it's __next__
+            pxs_OperatorKind::pxs_OpNext => format!(
+                r#"
+    def __iter__(self):
+        state = None
+        while True:
+            step = {call}, state)
+            if step is None:
+                return
+            state = step[0]
+            yield step[1]
+"#
+            ),
+            // Python has no concat operator
+            pxs_OperatorKind::pxs_OpConcat => continue,
+        };
+        methods_str.push_str(&dunders);
+    }
+    let mut object_string = format!(
         r#"
 # Bridge for pocketpy
-class _{}:
-    def __init__(self, ptr):
-        # Set the ptr
-        self.ptr = ptr
+class {class_name}:
+    _pxs_host = True
+    _pxs_generation = {generation}
 
-{}
+    def __init__(self, *args):
+        pass
 
+{methods_str}
 "#,
-        source.type_name, methods_str
+        generation = members.generation,
     );
-
-    // _{}({})
-    // , source.type_name, idx
-    // println!("{object_string}");
+    if rebuild {
+        object_string.push_str(&format!(
+            r#"
+for _pxs_name, _pxs_member in {class_name}.__dict__.items():
+    _{type_name}.__dict__[_pxs_name] = _pxs_member
+del {class_name}, _pxs_name, _pxs_member
+"#
+        ));
+    }
+    let constants = members.constants.clone();
+    // Release the members before running any Python
+    drop(members);
 
     // Execute it
-    let res = exec_py(
-        &object_string,
-        format!("<first_{}>", &source.type_name).as_str(),
-        module_name,
-    );
+    let res = exec_py(&object_string, &format!("<class _{type_name}>"), "builtins");
     if !res.is_empty() {
-        return;
+        return std::ptr::null_mut();
+    }
+    if !rebuild {
+        add_new_defined_object(type_name);
+    }
+
+    let class = get_class(type_name);
+    for constant in constants.iter() {
+        let c_name = create_raw_string!(constant.name.clone());
+        unsafe {
+            let r1 = pocketpy::py_getreg(1);
+            var_to_pocketpyref(r1, &constant.var);
+            pocketpy::py_setdict(class, pocketpy::py_name(c_name), r1);
+            free_raw_string!(c_name);
+        }
     }
 
-    // add it
-    add_new_defined_object(&source.type_name);
+    class
+}
 
-    // Ok but just create it now
-    let res = eval_py(
-        format!("_{}({})", source.type_name, idx).as_str(),
-        "<create_{}>",
-        module_name,
-    );
-    if !res.is_empty() {
-        println!("PYTHONSDNAOSDNOIDRes: {res}");
+/// Create a object in the Python Runtime. The object is left in `py_retval`.
+///
+/// idx: is the saved object.
+/// source: is the object
+pub(super) fn create_object(idx: i64, source: Arc<pxs_PixelObject>) {
+    // Reuse the live wrapper, it may be a script subclass.
+    if let Some(wrapper) = get_wrapper(&source) {
+        unsafe { *pocketpy::py_retval() = wrapper };
+        return;
+    }
+
+    let class = define_class(&source.pixel_type);
+    if class.is_null() || !unsafe { wrap_object(idx, &source, pocketpy::py_totype(class)) } {
+        unsafe { pocketpy::py_newnone(pocketpy::py_retval()) };
     }
 }
@@ -0,0 +1,140 @@
+// Copyright 2026 Jordan Castro <jordan@grupojvm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+//
+use crate::{
+    borrow_string, create_raw_string, free_raw_string,
+    python::{
+        add_new_defined_object,
+        array::{field_to_pocketpyref, pocketpyref_to_field},
+        exec_py,
+        object::get_class,
+        pocketpy,
+    },
+    shared::{
+        object::{FieldValue, pxs_FieldType},
+        types::{get_value_type, pxs_PixelValue, pxs_ValueType},
+    },
+};
+
+/// Default of a field in the generated `__init__`.
+fn field_default(field_type: pxs_FieldType) -> &'static str {
+    match field_type.zero() {
+        FieldValue::Int(_) => "0",
+        FieldValue::Float(_) => "0.0",
+        FieldValue::Bool(_) => "False",
+    }
+}
+
+/// Define the class of a value type in the Python Runtime and return it.
+///
+/// Like host types, the class lives in `builtins` as `_{name}`. It is a plain Python class, creating
+/// one never calls the host.
+pub(super) fn define_value_class(value_type: &pxs_ValueType) -> pocketpy::py_ItemRef {
+    let class = get_class(&value_type.name);
+    if !class.is_null() {
+        return class;
+    }
+
+    let name = &value_type.name;
+    let fields: Vec<&str> = value_type.fields.iter().map(|f| f.name.as_str()).collect();
+    let params = value_type
+        .fields
+        .iter()
+        .map(|f| format!("{}={}", f.name, field_default(f.field_type)))
+        .collect::<Vec<String>>()
+        .join(", ");
+    let assigns = fields
+        .iter()
+        .map(|f| format!("        self.{f} = {f}\n"))
+        .collect::<String>();
+    let repr = fields
+        .iter()
+        .map(|f| format!("{f}={{self.{f}}}"))
+        .collect::<Vec<String>>()
+        .join(", ");
+    let eq = fields
+        .iter()
+        .map(|f| format!(" and self.{f} == other.{f}"))
+        .collect::<String>();
+
+    let code = format!(
+        r#"
+class _{name}:
+    _pxs_value = '{name}'
+
+    def __init__(self, {params}):
+{assigns}
+    def __repr__(self):
+        return f"{name}({repr})"
+
+    def __eq__(self, other):
+        return type(other) is type(self){eq}
+
+    def __ne__(self, other):
+        return not self.__eq__(other)
+"#
+    );
+    exec_py(&code, &format!("<{name}>"), "builtins");
+    add_new_defined_object(name);
+
+    get_class(name)
+}
+
+/// Copy a value into a new instance of its class.
+pub(super) unsafe fn new_value(out: pocketpy::py_Ref, value: &pxs_PixelValue) {
+    let class = define_value_class(&value.value_type);
+    if class.is_null() {
+        unsafe { pocketpy::py_newnone(out) };
+        return;
+    }
+
+    unsafe {
+        // Skip `__init__`, the fields are already valid.
+        pocketpy::py_newobject(out, pocketpy::py_totype(class), -1, 0);
+        for (field, v) in value.value_type.fields.iter().zip(value.fields.iter()) {
+            let tmp = pocketpy::py_pushtmp();
+            field_to_pocketpyref(tmp, *v);
+            let c_name = create_raw_string!(field.name.clone());
+            pocketpy::py_setdict(out, pocketpy::py_name(c_name), tmp);
+            free_raw_string!(c_name);
+            pocketpy::py_pop();
+        }
+    }
+}
+
+/// Validate and pack a value class instance. None if it is not a value.
+pub(super) unsafe fn as_value(pref: pocketpy::py_Ref) -> Option<Result<pxs_PixelValue, String>> {
+    unsafe {
+        let tp = pocketpy::py_typeof(pref);
+        let marker = pocketpy::py_tpfindname(tp, pocketpy::py_name(c"_pxs_value".as_ptr()));
+        if marker.is_null() || pocketpy::py_typeof(marker) as i32 != pocketpy::py_PredefinedType::tp_str as i32 {
+            return None;
+        }
+        let name = borrow_string!(pocketpy::py_tostr(marker)).to_string();
+        let value_type = get_value_type(&name)?;
+
+        let mut values = vec![];
+        for field in value_type.fields.iter() {
+            let c_name = create_raw_string!(field.name.clone());
+            let item = pocketpy::py_getdict(pref, pocketpy::py_name(c_name));
+            free_raw_string!(c_name);
+            if item.is_null() || pocketpy::py_typeof(item) as i32 == pocketpy::py_PredefinedType::tp_NoneType as i32 {
+                values.push(None);
+                continue;
+            }
+            match pocketpyref_to_field(item) {
+                Some(v) => values.push(Some(v)),
+                None => {
+                    let tp_name = borrow_string!(pocketpy::py_tpname(pocketpy::py_typeof(item)));
+                    return Some(Err(format!("{}.{} can not be a {}", name, field.name, tp_name)));
+                }
+            }
+        }
+        Some(value_type.pack(values))
+    }
+}
@@ -11,9 +11,12 @@ use std::{ffi::c_void, sync::Arc};
 use crate::{
     borrow_string, create_raw_string, free_raw_string,
     python::{
+        array::{as_array_view, new_array_view},
+        enums::enum_value,
         func::py_assign,
         object::create_object,
-        pocketpy::{self, py_getreg},
+        pocketpy,
+        value::{as_value, new_value},
     },
     shared::{
         object::get_object,
@@ -65,11 +68,45 @@ pub(super) fn pocketpyref_to_var(pref: pocketpy::py_Ref) -> pxs_Var {
     } else if tp == pocketpy::py_PredefinedType::tp_function as i32 {
         // Just like object, save the raw pointer
         pxs_Var::new_function(pref as *mut c_void, None)
+    } else if let Some(value) = unsafe { enum_value(pref) } {
+        // Enum members are their value for the host.
+        pxs_Var::new_i64(value)
+    } else if let Some(idx) = host_object_idx(pref) {
+        pxs_Var::new_host_object(idx)
+    } else if let Some(view) = unsafe { as_array_view(pref) } {
+        // Same host buffer
+        pxs_Var::new_array_view(view)
+    } else if let Some(value) = unsafe { as_value(pref) } {
+        // Invalid values become exceptions, the bridge raises them.
+        match value {
+            Ok(value) => pxs_Var::new_value(value),
+            Err(e) => pxs_Var::new_exception(e),
+        }
     } else {
         pxs_Var::new_object(pref as *mut c_void, None)
     }
 }
 
+/// Get the object idx if this is a instance of a generated host object class.
+fn host_object_idx(pref: pocketpy::py_Ref) -> Option<i64> {
+    unsafe {
+        let tp = pocketpy::py_typeof(pref);
+        // Generated classes have a `_pxs_host` class attribute.
+        let marker = pocketpy::py_tpfindname(tp, pocketpy::py_name(c"_pxs_host".as_ptr()));
+        if marker.is_null() {
+            return None;
+        }
+
+        let ptr = pocketpy::py_getdict(pref, pocketpy::py_name(c"ptr".as_ptr()));
+        if ptr.is_null() || pocketpy::py_typeof(ptr) as i32 != pocketpy::py_PredefinedType::tp_int as i32 {
+            return None;
+        }
+
+        let idx = pocketpy::py_toint(ptr);
+        get_object(idx).map(|_| idx)
+    }
+}
+
 /// Convert a Var into a PocketPy ref
 pub(super) fn var_to_pocketpyref(out: pocketpy::py_Ref, var: &pxs_Var) {
     unsafe {
@@ -106,32 +143,13 @@ pub(super) fn var_to_pocketpyref(out: pocketpy::py_Ref, var: &pxs_Var) {
             }
             crate::shared::var::pxs_VarType::pxs_HostObject => {
                 let idx = var.value.host_object_val;
-                let pixel_object = get_object(idx).unwrap();
-                // DO NOT FREE POCKETPY memory.
-                pixel_object.update_free_lang_ptr(false);
-                let lang_ptr_is_null = pixel_object.lang_ptr.lock().unwrap().is_null();
-                if lang_ptr_is_null {
-                    // Find current module
-                    let cmod = pocketpy::py_inspect_currentmodule();
-                    let c_name = create_raw_string!("__name__");
-                    let pyname = pocketpy::py_name(c_name);
-                    pocketpy::py_getattr(cmod, pyname);
-                    let r0 = py_getreg(0);
-                    let module_name = pocketpy::py_tostr(r0);
-                    let module_name = borrow_string!(module_name);
-                    // Create the object for the first time...
-                    create_object(idx, Arc::clone(&pixel_object), module_name);
-                    // Get py_retval
-                    let pyobj = pocketpy::py_retval();
-                    // Set that as the pointer
-                    pixel_object.update_lang_ptr(pyobj as *mut c_void);
-                    free_raw_string!(c_name);
-                }
-                // Get PTR again
-                let lang_ptr = pixel_object.lang_ptr.lock().unwrap();
-                // Assign again
-                py_assign(out, *lang_ptr as pocketpy::py_Ref);
-                // *out = *(*lang_ptr as pocketpy::py_Ref);
+                let Some(pixel_object) = get_object(idx) else {
+                    pocketpy::py_newnone(out);
+                    return;
+                };
+                // Each wrapper holds its own handle, which releases the reference when collected.
+                create_object(idx, Arc::clone(&pixel_object));
+                py_assign(out, pocketpy::py_retval());
             }
             pxs_VarType::pxs_List => {
                 // Ok take vars and convet them into pylist
@@ -144,11 +162,22 @@ pub(super) fn var_to_pocketpyref(out: pocketpy::py_Ref, var: &pxs_Var) {
                         let tmp = pocketpy::py_pushtmp();
                         var_to_pocketpyref(tmp, item);
                         pocketpy::py_list_append(out, tmp);
+                        pocketpy::py_pop();
                     }
                 }
 
                 // Donezo
             },
+            pxs_VarType::pxs_Exception => {
+                // Exceptions are raised by the bridge, never stored.
+                pocketpy::py_newnone(out);
+            },
+            pxs_VarType::pxs_ArrayView => {
+                new_array_view(out, *var.get_array_view().unwrap());
+            },
+            pxs_VarType::pxs_Value => {
+                new_value(out, var.get_value().unwrap());
+            },
             pxs_VarType::pxs_Function => {
                 if var.value.function_val.is_null() {
                     pocketpy::py_newnone(out);
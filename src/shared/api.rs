@@ -0,0 +1,435 @@
+// Copyright 2026 Jordan Castro <jordan@grupojvm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+//
+use anyhow::{Result, anyhow, bail};
+
+use crate::shared::{
+    PXS_ALL_RUNTIMES, PtrMagic,
+    json::Json,
+    module::{ModuleCallback, lookup_modules, pxs_Module},
+    pxs_Runtime,
+    signature::{pxs_ParamType, pxs_Signature},
+    stubs::{field_param_type, var_param_type},
+    var::{pxs_Var, pxs_VarType},
+};
+
+/// Version of the API description, bumped when the format changes.
+pub const PXS_API_VERSION: i64 = 1;
+
+/// Kind of a symbol in the API description.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiKind {
+    Module,
+    Function,
+    /// A function of a object, called with the object.
+    Method,
+    Variable,
+    Constant,
+    /// A host type or value type.
+    Class,
+    Enum,
+    EnumMember,
+    /// A field or property of a object.
+    Field,
+}
+
+impl ApiKind {
+    const NAMES: [(ApiKind, &'static str); 9] = [
+        (ApiKind::Module, "module"),
+        (ApiKind::Function, "function"),
+        (ApiKind::Method, "method"),
+        (ApiKind::Variable, "variable"),
+        (ApiKind::Constant, "constant"),
+        (ApiKind::Class, "class"),
+        (ApiKind::Enum, "enum"),
+        (ApiKind::EnumMember, "enum_member"),
+        (ApiKind::Field, "field"),
+    ];
+
+    pub fn name(&self) -> &'static str {
+        Self::NAMES.iter().find(|(k, _)| k == self).unwrap().1
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::NAMES
+            .iter()
+            .find(|(_, n)| *n == name)
+            .map(|(k, _)| *k)
+    }
+}
+
+/// A module, function, type... that a host added. The language server reads these.
+#[derive(Clone)]
+pub struct ApiSymbol {
+    pub name: String,
+    pub kind: ApiKind,
+    /// The runtimes it is added to.
+    pub runtimes: u32,
+    /// Type of variables, constants and fields.
+    pub value_type: pxs_ParamType,
+    /// Value of constants and enum members.
+    pub value: Option<pxs_Var>,
+    /// Parameters and docs of functions, methods and classes.
+    pub signature: Option<pxs_Signature>,
+    /// Functions of a module, methods of a class, members of a enum...
+    pub members: Vec<ApiSymbol>,
+}
+
+impl ApiSymbol {
+    fn new(name: &str, kind: ApiKind) -> Self {
+        ApiSymbol {
+            name: name.to_string(),
+            kind,
+            runtimes: PXS_ALL_RUNTIMES,
+            value_type: pxs_ParamType::pxs_ParamAny,
+            value: None,
+            signature: None,
+            members: vec![],
+        }
+    }
+
+    /// Is this symbol added to `runtime`?
+    pub fn is_for(&self, runtime: pxs_Runtime) -> bool {
+        self.runtimes & runtime.mask() != 0
+    }
+
+    /// Get a member by name.
+    pub fn member(&self, name: &str) -> Option<&ApiSymbol> {
+        self.members.iter().find(|m| m.name == name)
+    }
+
+    /// Serialize to JSON.
+    pub fn to_json(&self) -> Json {
+        let mut pairs = vec![
+            ("name", Json::string(&self.name)),
+            ("kind", Json::string(self.kind.name())),
+        ];
+        if self.runtimes != PXS_ALL_RUNTIMES {
+            pairs.push(("runtimes", Json::Number(self.runtimes as f64)));
+        }
+        if self.value_type != pxs_ParamType::pxs_ParamAny {
+            pairs.push(("type", Json::string(type_name(self.value_type))));
+        }
+        if let Some(value) = self.value.as_ref() {
+            pairs.push(("value", var_to_json(value)));
+        }
+        if let Some(signature) = self.signature.as_ref() {
+            pairs.push(("signature", signature_to_json(signature)));
+        }
+        if !self.members.is_empty() {
+            pairs.push((
+                "members",
+                Json::Array(self.members.iter().map(|m| m.to_json()).collect()),
+            ));
+        }
+        Json::object(pairs)
+    }
+
+    /// Deserialize from JSON.
+    pub fn from_json(json: &Json) -> Result<Self> {
+        let name = json
+            .get("name")
+            .and_then(|n| n.as_str())
+            .ok_or_else(|| anyhow!("Symbol without a name"))?;
+        let kind = json
+            .get("kind")
+            .and_then(|k| k.as_str())
+            .unwrap_or_default();
+        let kind = ApiKind::from_name(kind)
+            .ok_or_else(|| anyhow!("'{name}' has a unknown kind '{kind}'"))?;
+
+        let mut symbol = ApiSymbol::new(name, kind);
+        if let Some(runtimes) = json.get("runtimes").and_then(|r| r.as_i64()) {
+            symbol.runtimes = runtimes as u32;
+        }
+        if let Some(value_type) = json.get("type") {
+            symbol.value_type = type_from_json(value_type)?;
+        }
+        symbol.value = json
+            .get("value")
+            .and_then(|v| json_to_var(v, symbol.value_type));
+        if let Some(signature) = json.get("signature") {
+            symbol.signature = Some(signature_from_json(signature, name)?);
+        }
+        if let Some(members) = json.get("members") {
+            symbol.members = members
+                .items()
+                .iter()
+                .map(ApiSymbol::from_json)
+                .collect::<Result<_>>()?;
+        }
+        Ok(symbol)
+    }
+}
+
+/// Describe every added module.
+pub fn describe_modules() -> Vec<ApiSymbol> {
+    lookup_modules()
+        .iter()
+        .map(|m| describe_module(m))
+        .collect()
+}
+
+/// The JSON document of the added modules, what `pxs_genapi` writes.
+pub fn api_json() -> Json {
+    Json::object(vec![
+        ("version", Json::Number(PXS_API_VERSION as f64)),
+        (
+            "modules",
+            Json::Array(describe_modules().iter().map(|m| m.to_json()).collect()),
+        ),
+    ])
+}
+
+/// Read the modules of a API description.
+pub fn parse_api(text: &str) -> Result<Vec<ApiSymbol>> {
+    let json = Json::parse(text)?;
+    let version = json
+        .get("version")
+        .and_then(|v| v.as_i64())
+        .unwrap_or_default();
+    if version != PXS_API_VERSION {
+        bail!("Unsupported API description version {version}, expected {PXS_API_VERSION}");
+    }
+    json.get("modules")
+        .map(|modules| modules.items())
+        .unwrap_or_default()
+        .iter()
+        .map(ApiSymbol::from_json)
+        .collect()
+}
+
+fn describe_module(module: &pxs_Module) -> ApiSymbol {
+    let mut symbol = ApiSymbol::new(&module.name, ApiKind::Module);
+    symbol.runtimes = module.runtimes;
+
+    for child in module.modules.iter() {
+        symbol.members.push(describe_module(child));
+    }
+    for variable in module.variables.iter() {
+        let kind = if variable.constant {
+            ApiKind::Constant
+        } else {
+            ApiKind::Variable
+        };
+        let mut var_symbol = ApiSymbol::new(&variable.name, kind);
+        var_symbol.value_type = var_param_type(variable.var);
+        if variable.constant && !variable.var.is_null() {
+            var_symbol.value = Some(unsafe { pxs_Var::from_borrow(variable.var) }.clone());
+        }
+        symbol.members.push(var_symbol);
+    }
+    for live_var in module.live_vars.iter() {
+        symbol
+            .members
+            .push(ApiSymbol::new(&live_var.name, ApiKind::Variable));
+    }
+    for callback in module.callbacks.iter() {
+        symbol
+            .members
+            .push(describe_callback(callback, ApiKind::Function));
+    }
+
+    for pixel_type in module.types.iter() {
+        let members = pixel_type.members();
+        let mut class = ApiSymbol::new(&pixel_type.name, ApiKind::Class);
+        if members.constructor.is_some() {
+            let mut signature = pxs_Signature::new(String::new());
+            signature.variadic = true;
+            signature.returns = pxs_ParamType::pxs_ParamObject;
+            class.signature = Some(signature);
+        }
+        for constant in members.constants.iter() {
+            let mut const_symbol = ApiSymbol::new(&constant.name, ApiKind::Constant);
+            const_symbol.value_type = pxs_ParamType::of_var(&constant.var.tag);
+            const_symbol.value = Some(constant.var.clone());
+            class.members.push(const_symbol);
+        }
+        for property in members.properties.iter() {
+            class
+                .members
+                .push(ApiSymbol::new(&property.name, ApiKind::Field));
+        }
+        for field in members.fields.iter() {
+            let mut field_symbol = ApiSymbol::new(&field.name, ApiKind::Field);
+            field_symbol.value_type = field_param_type(field.field_type);
+            class.members.push(field_symbol);
+        }
+        for method in members.methods.iter() {
+            class
+                .members
+                .push(describe_callback(method, ApiKind::Method));
+        }
+        for method in members.statics.iter() {
+            class
+                .members
+                .push(describe_callback(method, ApiKind::Function));
+        }
+        symbol.members.push(class);
+    }
+
+    for value_type in module.value_types.iter() {
+        let mut class = ApiSymbol::new(&value_type.name, ApiKind::Class);
+        // Created with it's fields in order, missing fields are zero.
+        let mut signature = pxs_Signature::new(String::new());
+        signature.returns = pxs_ParamType::pxs_ParamObject;
+        for field in value_type.fields.iter() {
+            let field_type = field_param_type(field.field_type);
+            signature.add_param(&field.name, field_type, Some(zero_var(field_type)));
+
+            let mut field_symbol = ApiSymbol::new(&field.name, ApiKind::Field);
+            field_symbol.value_type = field_type;
+            class.members.push(field_symbol);
+        }
+        class.signature = Some(signature);
+        symbol.members.push(class);
+    }
+
+    for module_enum in module.enums.iter() {
+        let mut enum_symbol = ApiSymbol::new(&module_enum.name, ApiKind::Enum);
+        for member in module_enum.members.iter() {
+            let mut member_symbol = ApiSymbol::new(&member.name, ApiKind::EnumMember);
+            member_symbol.value_type = pxs_ParamType::pxs_ParamInt;
+            member_symbol.value = Some(pxs_Var::new_i64(member.value));
+            enum_symbol.members.push(member_symbol);
+        }
+        symbol.members.push(enum_symbol);
+    }
+
+    symbol
+}
+
+fn describe_callback(callback: &ModuleCallback, kind: ApiKind) -> ApiSymbol {
+    let mut symbol = ApiSymbol::new(&callback.name, kind);
+    symbol.runtimes = callback.runtimes;
+    symbol.signature = callback.signature.as_ref().map(|s| s.as_ref().clone());
+    symbol
+}
+
+/// The zero value of a field type.
+fn zero_var(param_type: pxs_ParamType) -> pxs_Var {
+    match param_type {
+        pxs_ParamType::pxs_ParamFloat => pxs_Var::new_f64(0.0),
+        pxs_ParamType::pxs_ParamBool => pxs_Var::new_bool(false),
+        _ => pxs_Var::new_i64(0),
+    }
+}
+
+const TYPE_NAMES: [(pxs_ParamType, &str); 9] = [
+    (pxs_ParamType::pxs_ParamAny, "any"),
+    (pxs_ParamType::pxs_ParamInt, "int"),
+    (pxs_ParamType::pxs_ParamFloat, "float"),
+    (pxs_ParamType::pxs_ParamBool, "bool"),
+    (pxs_ParamType::pxs_ParamString, "string"),
+    (pxs_ParamType::pxs_ParamList, "list"),
+    (pxs_ParamType::pxs_ParamFunction, "function"),
+    (pxs_ParamType::pxs_ParamObject, "object"),
+    (pxs_ParamType::pxs_ParamNull, "null"),
+];
+
+/// The runtime independent name of a type.
+fn type_name(param_type: pxs_ParamType) -> &'static str {
+    TYPE_NAMES.iter().find(|(t, _)| *t == param_type).unwrap().1
+}
+
+fn type_from_json(json: &Json) -> Result<pxs_ParamType> {
+    let name = json.as_str().unwrap_or_default();
+    TYPE_NAMES
+        .iter()
+        .find(|(_, n)| *n == name)
+        .map(|(t, _)| *t)
+        .ok_or_else(|| anyhow!("Unknown type '{name}'"))
+}
+
+/// Primitive vars as JSON, anything else is null.
+fn var_to_json(var: &pxs_Var) -> Json {
+    match var.tag {
+        pxs_VarType::pxs_Int64 => var
+            .get_i64()
+            .map(|v| Json::Number(v as f64))
+            .unwrap_or(Json::Null),
+        pxs_VarType::pxs_UInt64 => var
+            .get_u64()
+            .map(|v| Json::Number(v as f64))
+            .unwrap_or(Json::Null),
+        pxs_VarType::pxs_Float64 => var.get_f64().map(Json::Number).unwrap_or(Json::Null),
+        pxs_VarType::pxs_Bool => var.get_bool().map(Json::Bool).unwrap_or(Json::Null),
+        pxs_VarType::pxs_String => var.get_string().map(Json::String).unwrap_or(Json::Null),
+        _ => Json::Null,
+    }
+}
+
+/// A var from JSON, numbers are floats for float types.
+fn json_to_var(json: &Json, param_type: pxs_ParamType) -> Option<pxs_Var> {
+    match json {
+        Json::Null => Some(pxs_Var::new_null()),
+        Json::Bool(b) => Some(pxs_Var::new_bool(*b)),
+        Json::Number(n) if param_type == pxs_ParamType::pxs_ParamFloat || n.fract() != 0.0 => {
+            Some(pxs_Var::new_f64(*n))
+        }
+        Json::Number(n) => Some(pxs_Var::new_i64(*n as i64)),
+        Json::String(s) => Some(pxs_Var::new_string(s.clone())),
+        _ => None,
+    }
+}
+
+fn signature_to_json(signature: &pxs_Signature) -> Json {
+    let params = signature
+        .params
+        .iter()
+        .map(|param| {
+            let mut pairs = vec![
+                ("name", Json::string(&param.name)),
+                ("type", Json::string(type_name(param.param_type))),
+            ];
+            if let Some(default) = param.default.as_ref() {
+                pairs.push(("default", var_to_json(default)));
+            }
+            Json::object(pairs)
+        })
+        .collect();
+    Json::object(vec![
+        ("params", Json::Array(params)),
+        ("variadic", Json::Bool(signature.variadic)),
+        ("returns", Json::string(type_name(signature.returns))),
+        ("doc", Json::string(&signature.doc)),
+    ])
+}
+
+fn signature_from_json(json: &Json, name: &str) -> Result<pxs_Signature> {
+    let mut signature = pxs_Signature::new(
+        json.get("doc")
+            .and_then(|d| d.as_str())
+            .unwrap_or_default()
+            .to_string(),
+    );
+    signature.name = name.to_string();
+    for param in json.get("params").map(|p| p.items()).unwrap_or_default() {
+        let param_name = param
+            .get("name")
+            .and_then(|n| n.as_str())
+            .ok_or_else(|| anyhow!("'{name}' has a parameter without a name"))?;
+        let param_type = param
+            .get("type")
+            .map(type_from_json)
+            .transpose()?
+            .unwrap_or(pxs_ParamType::pxs_ParamAny);
+        let default = param
+            .get("default")
+            .and_then(|d| json_to_var(d, param_type));
+        signature.add_param(param_name, param_type, default);
+    }
+    signature.variadic = json
+        .get("variadic")
+        .and_then(|v| v.as_bool())
+        .unwrap_or_default();
+    if let Some(returns) = json.get("returns") {
+        signature.returns = type_from_json(returns)?;
+    }
+    Ok(signature)
+}
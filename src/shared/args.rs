@@ -0,0 +1,316 @@
+// Copyright 2026 Jordan Castro <jordan@grupojvm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+//
+use std::{
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+    sync::Arc,
+};
+
+use anyhow::{Error, anyhow};
+
+use crate::shared::{
+    PtrMagic,
+    object::{get_object, pxs_PixelObject},
+    pxs_Runtime,
+    var::{pxs_Var, pxs_VarList, pxs_VarType},
+};
+
+/// Convert a single borrowed `pxs_Var` into a Rust value.
+pub trait FromPxsVar: Sized {
+    /// Name of the expected type, used in error messages.
+    const EXPECTED: &'static str;
+
+    /// Convert the var. Errors describe the type mismatch.
+    fn from_pxs_var(var: &pxs_Var) -> Result<Self, Error>;
+
+    /// Value to use when the argument was not passed at all.
+    ///
+    /// Only optional types return something here.
+    fn from_missing() -> Option<Self> {
+        None
+    }
+}
+
+/// Convert a whole callback args list into a typed tuple.
+///
+/// Index 0 is always the runtime, and for object callbacks index 1 is the object.
+/// Both are part of the tuple so that they are checked like any other argument:
+/// ```rust,ignore
+/// let (rt, this, name, count): (pxs_Runtime, HostRef<TodoList>, String, i64) = pxs_args!(args);
+/// ```
+pub trait FromPxsArgs: Sized {
+    /// Convert a borrowed list.
+    fn from_pxs_list(list: &pxs_VarList) -> Result<Self, Error>;
+
+    /// Convert the raw `args` pointer given to a `pxs_Func`.
+    ///
+    /// # Safety
+    /// `args` must be null or a valid `pxs_Var` pointer.
+    unsafe fn from_args(args: *mut pxs_Var) -> Result<Self, Error> {
+        if args.is_null() {
+            return Err(anyhow!("Arguments are null."));
+        }
+
+        let args = unsafe { pxs_Var::from_borrow(args) };
+        match args.get_list() {
+            Some(list) => Self::from_pxs_list(list),
+            None => Err(anyhow!("Arguments are not a list, found a: {:#?}", args.tag)),
+        }
+    }
+}
+
+/// Build the type mismatch error. Host objects are named by their type.
+fn mismatch(expected: &str, var: &pxs_Var) -> Error {
+    match var.is_host_object().then(|| get_object(var.get_object_ptr())).flatten() {
+        Some(object) => anyhow!("expected {}, found {}", expected, object.type_name),
+        None => anyhow!("expected {}, found {:?}", expected, var.tag),
+    }
+}
+
+impl FromPxsVar for i64 {
+    const EXPECTED: &'static str = "int";
+
+    fn from_pxs_var(var: &pxs_Var) -> Result<Self, Error> {
+        match var.tag {
+            pxs_VarType::pxs_Int64 => var.get_i64(),
+            pxs_VarType::pxs_UInt64 => Ok(var.get_u64()? as i64),
+            // Only floats without a fraction are safe to use as ints.
+            pxs_VarType::pxs_Float64 => {
+                let val = var.get_f64()?;
+                if val.fract() != 0.0 {
+                    Err(mismatch(Self::EXPECTED, var))
+                } else if val >= -(2f64.powi(63)) && val < 2f64.powi(63) {
+                    Ok(val as i64)
+                } else {
+                    Err(anyhow!("float {} does not fit in a i64", val))
+                }
+            }
+            _ => Err(mismatch(Self::EXPECTED, var)),
+        }
+    }
+}
+
+impl FromPxsVar for i32 {
+    const EXPECTED: &'static str = "int";
+
+    fn from_pxs_var(var: &pxs_Var) -> Result<Self, Error> {
+        let val = i64::from_pxs_var(var)?;
+        i32::try_from(val).map_err(|_| anyhow!("int {} does not fit in a i32", val))
+    }
+}
+
+impl FromPxsVar for u64 {
+    const EXPECTED: &'static str = "uint";
+
+    fn from_pxs_var(var: &pxs_Var) -> Result<Self, Error> {
+        match var.tag {
+            pxs_VarType::pxs_UInt64 => var.get_u64(),
+            _ => {
+                let val = i64::from_pxs_var(var).map_err(|_| mismatch(Self::EXPECTED, var))?;
+                u64::try_from(val).map_err(|_| anyhow!("expected uint, found negative int {}", val))
+            }
+        }
+    }
+}
+
+impl FromPxsVar for f64 {
+    const EXPECTED: &'static str = "float";
+
+    fn from_pxs_var(var: &pxs_Var) -> Result<Self, Error> {
+        match var.tag {
+            pxs_VarType::pxs_Float64 => var.get_f64(),
+            pxs_VarType::pxs_Int64 => Ok(var.get_i64()? as f64),
+            pxs_VarType::pxs_UInt64 => Ok(var.get_u64()? as f64),
+            _ => Err(mismatch(Self::EXPECTED, var)),
+        }
+    }
+}
+
+impl FromPxsVar for bool {
+    const EXPECTED: &'static str = "bool";
+
+    fn from_pxs_var(var: &pxs_Var) -> Result<Self, Error> {
+        match var.tag {
+            pxs_VarType::pxs_Bool => var.get_bool(),
+            _ => Err(mismatch(Self::EXPECTED, var)),
+        }
+    }
+}
+
+impl FromPxsVar for String {
+    const EXPECTED: &'static str = "string";
+
+    fn from_pxs_var(var: &pxs_Var) -> Result<Self, Error> {
+        match var.tag {
+            pxs_VarType::pxs_String => var.get_string(),
+            _ => Err(mismatch(Self::EXPECTED, var)),
+        }
+    }
+}
+
+impl FromPxsVar for pxs_Runtime {
+    const EXPECTED: &'static str = "runtime";
+
+    fn from_pxs_var(var: &pxs_Var) -> Result<Self, Error> {
+        let val = var.get_i64().map_err(|_| mismatch(Self::EXPECTED, var))?;
+        pxs_Runtime::from_i64(val).ok_or_else(|| anyhow!("{} is not a valid runtime", val))
+    }
+}
+
+impl FromPxsVar for pxs_Var {
+    const EXPECTED: &'static str = "any";
+
+    fn from_pxs_var(var: &pxs_Var) -> Result<Self, Error> {
+        Ok(var.clone())
+    }
+}
+
+impl<T: FromPxsVar> FromPxsVar for Option<T> {
+    const EXPECTED: &'static str = T::EXPECTED;
+
+    fn from_pxs_var(var: &pxs_Var) -> Result<Self, Error> {
+        if var.is_null() {
+            Ok(None)
+        } else {
+            T::from_pxs_var(var).map(Some)
+        }
+    }
+
+    fn from_missing() -> Option<Self> {
+        Some(None)
+    }
+}
+
+impl<T: FromPxsVar> FromPxsVar for Vec<T> {
+    const EXPECTED: &'static str = "list";
+
+    fn from_pxs_var(var: &pxs_Var) -> Result<Self, Error> {
+        let list = var.get_list().ok_or_else(|| mismatch(Self::EXPECTED, var))?;
+        let mut res = Vec::with_capacity(list.vars.len());
+        for (i, item) in list.vars.iter().enumerate() {
+            res.push(T::from_pxs_var(item).map_err(|e| anyhow!("item {}: {}", i, e))?);
+        }
+        Ok(res)
+    }
+}
+
+/// A host struct registered as a `pxs_PixelType`.
+///
+/// ```rust,ignore
+/// impl HostType for TodoList {
+///     const TYPE_NAME: &'static str = "TodoList";
+/// }
+/// ```
+pub trait HostType {
+    /// Name of the type, as passed to `pxs_newtype`.
+    const TYPE_NAME: &'static str;
+}
+
+/// A typed reference to the host data of a `pxs_PixelObject`.
+///
+/// Only host objects of `T`'s type convert. Keeps the object alive while the callback runs and derefs into `T`.
+pub struct HostRef<T: HostType> {
+    idx: i64,
+    object: Arc<pxs_PixelObject>,
+    _marker: PhantomData<T>,
+}
+
+impl<T: HostType> HostRef<T> {
+    /// The object handle within the object lookup.
+    pub fn idx(&self) -> i64 {
+        self.idx
+    }
+
+    /// The raw host pointer.
+    pub fn ptr(&self) -> *mut T {
+        self.object.ptr as *mut T
+    }
+
+    /// The PixelObject this references.
+    pub fn object(&self) -> &Arc<pxs_PixelObject> {
+        &self.object
+    }
+
+    /// Create a new HostObject var pointing to the same object.
+    pub fn to_var(&self) -> pxs_Var {
+        pxs_Var::new_host_object(self.idx)
+    }
+}
+
+impl<T: HostType> FromPxsVar for HostRef<T> {
+    const EXPECTED: &'static str = T::TYPE_NAME;
+
+    fn from_pxs_var(var: &pxs_Var) -> Result<Self, Error> {
+        if !var.is_host_object() {
+            return Err(mismatch(Self::EXPECTED, var));
+        }
+
+        let idx = var.get_object_ptr();
+        let object = get_object(idx).ok_or_else(|| anyhow!("object was destroyed"))?;
+        if object.type_name != T::TYPE_NAME {
+            return Err(mismatch(Self::EXPECTED, var));
+        }
+        if object.ptr.is_null() {
+            return Err(anyhow!("host object {} has a null pointer", idx));
+        }
+
+        Ok(HostRef {
+            idx,
+            object,
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<T: HostType> Deref for HostRef<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*(self.object.ptr as *const T) }
+    }
+}
+
+impl<T: HostType> DerefMut for HostRef<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *(self.object.ptr as *mut T) }
+    }
+}
+
+/// Implement FromPxsArgs for tuples.
+macro_rules! impl_from_pxs_args {
+    ($len:expr; $($t:ident => $i:tt),+) => {
+        impl<$($t: FromPxsVar),+> FromPxsArgs for ($($t,)+) {
+            fn from_pxs_list(list: &pxs_VarList) -> Result<Self, Error> {
+                let argc = list.vars.len();
+                if argc > $len {
+                    return Err(anyhow!("Expected at most {} arguments, got {}.", $len, argc));
+                }
+
+                Ok(($(
+                    match list.vars.get($i) {
+                        Some(var) => $t::from_pxs_var(var)
+                            .map_err(|e| anyhow!("Argument {}: {}.", $i, e))?,
+                        None => $t::from_missing().ok_or_else(|| {
+                            anyhow!("Expected {} arguments, got {}. Missing argument {} ({}).", $len, argc, $i, $t::EXPECTED)
+                        })?,
+                    },
+                )+))
+            }
+        }
+    };
+}
+
+impl_from_pxs_args!(1; A => 0);
+impl_from_pxs_args!(2; A => 0, B => 1);
+impl_from_pxs_args!(3; A => 0, B => 1, C => 2);
+impl_from_pxs_args!(4; A => 0, B => 1, C => 2, D => 3);
+impl_from_pxs_args!(5; A => 0, B => 1, C => 2, D => 3, E => 4);
+impl_from_pxs_args!(6; A => 0, B => 1, C => 2, D => 3, E => 4, F => 5);
+impl_from_pxs_args!(7; A => 0, B => 1, C => 2, D => 3, E => 4, F => 5, G => 6);
+impl_from_pxs_args!(8; A => 0, B => 1, C => 2, D => 3, E => 4, F => 5, G => 6, H => 7);
@@ -74,4 +74,23 @@ macro_rules! own_var {
     ($var:expr) => {{
         pxs_Var::from_raw($var)    
     }};
-}
\ No newline at end of file
+}
+/// Extract typed arguments from a callback `args` pointer.
+///
+/// On failure the callback returns a exception var, which is raised in the calling script.
+/// ```rust,ignore
+/// let (_rt, name, count): (pxs_Runtime, String, i64) = pxs_args!(args);
+/// ```
+#[macro_export]
+macro_rules! pxs_args {
+    ($args:expr) => {{
+        match unsafe { $crate::shared::args::FromPxsArgs::from_args($args) } {
+            Ok(res) => res,
+            Err(e) => {
+                return $crate::shared::PtrMagic::into_raw(
+                    $crate::shared::var::pxs_Var::new_exception(e.to_string()),
+                );
+            }
+        }
+    }};
+}
@@ -8,6 +8,8 @@
 //
 use crate::shared::{
     PtrMagic,
+    object::get_object,
+    signature::pxs_Signature,
     var::{pxs_VarList, pxs_VarType, pxs_VarValue},
 };
 
@@ -15,7 +17,7 @@ use super::var::pxs_Var;
 use std::{
     collections::HashMap,
     ffi::c_void,
-    sync::{Mutex, OnceLock},
+    sync::{Arc, Mutex, OnceLock},
 };
 
 /// Function reference used in C.
@@ -29,11 +31,20 @@ use std::{
 #[allow(non_camel_case_types)]
 pub type pxs_Func = unsafe extern "C" fn(args: *mut pxs_Var, opaque: *mut c_void) -> *mut pxs_Var;
 
+/// A `pxs_Func` that can be NULL. Same as `Option<pxs_Func>`, written out so it is a plain function
+/// pointer in C.
+#[allow(non_camel_case_types)]
+pub type pxs_OptFunc = Option<unsafe extern "C" fn(args: *mut pxs_Var, opaque: *mut c_void) -> *mut pxs_Var>;
+
 /// Basic rust structure to track Funcs and opaques together.
 pub struct Function {
     pub name: String,
     pub func: pxs_Func,
     pub opaque: *mut c_void,
+    /// Arguments are checked against it before `func` is called.
+    pub signature: Option<Arc<pxs_Signature>>,
+    /// Type name of the object this is a member of. `args[1]` must be a host object of this type.
+    pub owner: Option<String>,
 }
 
 unsafe impl Send for Function {}
@@ -45,6 +56,8 @@ pub struct FunctionLookup {
     ///
     /// Negative numbers are valid here.
     pub function_hash: HashMap<i32, Function>,
+    /// Next free idx. Released idxs are never reused, so a stale callback can not call into a new function.
+    pub next_idx: i32,
 }
 
 impl FunctionLookup {
@@ -53,16 +66,20 @@ impl FunctionLookup {
     }
     pub fn add_function(&mut self, name: &str, func: pxs_Func, opaque: *mut c_void) -> i32 {
         // TODO: Allow for negative idxs.
+        let idx = self.next_idx;
+        self.next_idx += 1;
         self.function_hash.insert(
-            self.function_hash.len() as i32,
+            idx,
             Function {
                 name: name.to_string(),
                 func,
                 opaque,
+                signature: None,
+                owner: None,
             },
         );
 
-        return (self.function_hash.len() - 1) as i32;
+        idx
     }
 }
 
@@ -75,6 +92,7 @@ fn get_function_lookup() -> std::sync::MutexGuard<'static, FunctionLookup> {
         .get_or_init(|| {
             Mutex::new(FunctionLookup {
                 function_hash: HashMap::new(),
+                next_idx: 0,
             })
         })
         .lock()
@@ -84,19 +102,36 @@ fn get_function_lookup() -> std::sync::MutexGuard<'static, FunctionLookup> {
 /// Add a function to the lookup
 pub fn lookup_add_function(name: &str, func: pxs_Func, opaque: *mut c_void) -> i32 {
     let mut lookup = get_function_lookup();
-    let idx = lookup.function_hash.len();
-    lookup.function_hash.insert(
-        idx as i32,
-        Function {
-            name: name.to_string(),
-            func,
-            opaque,
-        },
-    );
-    idx as i32
+    lookup.add_function(name, func, opaque)
+}
+
+/// Add a function whose arguments are checked against `signature`.
+pub fn lookup_add_function_sig(name: &str, func: pxs_Func, opaque: *mut c_void, signature: Arc<pxs_Signature>) -> i32 {
+    let mut lookup = get_function_lookup();
+    let idx = lookup.add_function(name, func, opaque);
+    if let Some(function) = lookup.function_hash.get_mut(&idx) {
+        function.signature = Some(signature);
+    }
+    idx
+}
+
+/// Add a member function of a type. It is only called with a object of that type as `args[1]`.
+pub fn lookup_add_member(name: &str, func: pxs_Func, opaque: *mut c_void, owner: &str) -> i32 {
+    let mut lookup = get_function_lookup();
+    let idx = lookup.add_function(name, func, opaque);
+    if let Some(function) = lookup.function_hash.get_mut(&idx) {
+        function.owner = Some(owner.to_string());
+    }
+    idx
+}
+
+/// Release a function. Calling it afterwards gives an exception.
+pub fn lookup_remove_function(idx: i32) {
+    let mut lookup = get_function_lookup();
+    lookup.function_hash.remove(&idx);
 }
 
-/// Clear function lookup hash
+/// Clear function lookup hash. Idxs keep counting, see `next_idx`.
 pub fn clear_function_lookup() {
     let mut lookup = get_function_lookup();
     lookup.function_hash.clear();
@@ -105,17 +140,35 @@ pub fn clear_function_lookup() {
 /// Call a function that is saved in the lookup by a idx.
 ///
 /// This should only be used within languages and never from a end user.
+///
+/// If the function has a signature, bad arguments give a exception and the function is not called. The same
+/// goes for members called without a object of their type.
 pub unsafe fn call_function(fn_idx: i32, args: Vec<pxs_Var>) -> pxs_Var {
-    let (func, opaque) = {
+    let (func, opaque, signature, owner) = {
         let fl = get_function_lookup();
         let function = fl.get_function(fn_idx);
         if function.is_none() {
-            return pxs_Var::new_null();
+            // A script can still hold a function of a removed module or member.
+            return pxs_Var::new_exception(format!("function {} was removed", fn_idx));
         }
 
         let function = function.unwrap();
 
-        (function.func, function.opaque)
+        (function.func, function.opaque, function.signature.clone(), function.owner.clone())
+    };
+
+    if let Some(owner) = owner
+        && let Err(msg) = check_owner(&owner, args.get(1))
+    {
+        return pxs_Var::new_exception(msg);
+    }
+
+    let args = match signature {
+        Some(signature) => match signature.check(args) {
+            Ok(args) => args,
+            Err(msg) => return pxs_Var::new_exception(msg),
+        },
+        None => args,
     };
 
     // Convert the pxs_Var vector into a list.
@@ -125,7 +178,7 @@ pub unsafe fn call_function(fn_idx: i32, args: Vec<pxs_Var>) -> pxs_Var {
         value: pxs_VarValue {
             list_val: pxs_VarList { vars: args }.into_raw(),
         },
-        deleter: None
+        deleter: std::cell::Cell::new(None)
     };
     let args_ptr = args.into_raw();
 
@@ -141,3 +194,17 @@ pub unsafe fn call_function(fn_idx: i32, args: Vec<pxs_Var>) -> pxs_Var {
         }
     }
 }
+
+/// Check that `object` is a live host object of the type `owner`.
+fn check_owner(owner: &str, object: Option<&pxs_Var>) -> Result<(), String> {
+    let found = match object {
+        Some(var) if var.is_host_object() => match get_object(var.get_object_ptr()) {
+            Some(object) if object.type_name == owner => return Ok(()),
+            Some(object) => object.type_name.clone(),
+            None => return Err("object was destroyed".to_string()),
+        },
+        Some(var) => format!("{:?}", var.tag),
+        None => "nothing".to_string(),
+    };
+    Err(format!("expected a {} object, found {}", owner, found))
+}
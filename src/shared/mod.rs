@@ -14,16 +14,31 @@ use std::{
 
 use parking_lot::{ReentrantMutex, ReentrantMutexGuard};
 
-use crate::{own_string, shared::var::pxs_Var};
+use crate::{
+    own_string,
+    shared::{module::pxs_Module, var::pxs_Var},
+};
 
+/// Serialized description of the added modules, read by the language server.
+pub mod api;
+/// Typed argument extraction for host callbacks.
+pub mod args;
 /// Helper methods/macros for using PixelScript
 pub mod ffi;
 /// The internal PixelScript function logic.
 pub mod func;
+/// A small JSON value, for the API description and the language server.
+pub mod json;
 /// The internal PixelScript Module structure.
 pub mod module;
 /// The internal PixelScript PixelObject logic.
 pub mod object;
+/// Signatures and docs of callbacks.
+pub mod signature;
+/// Editor type stubs of the added modules.
+pub mod stubs;
+/// The internal PixelScript type registry.
+pub mod types;
 /// The internal PixelScript Var logic.
 pub mod var;
 
@@ -87,14 +102,20 @@ impl Drop for pxs_DirHandle {
 pub type LoadFileFn = unsafe extern "C" fn(file_path: *const c_char) -> *mut c_char;
 /// Function Type for writing a file.
 pub type WriteFileFn = unsafe extern "C" fn(file_path: *const c_char, contents: *const c_char);
+/// Function Type for writing a file and reporting failures. Returns true when the file was written.
+pub type WriteFileExFn = unsafe extern "C" fn(file_path: *const c_char, contents: *const c_char) -> bool;
 /// Function Type for reading a Dir.
 pub type ReadDirFn = unsafe extern "C" fn(dir_path: *const c_char) -> pxs_DirHandle;
+/// Function Type for resolving a unknown module. Returns a new module or null.
+pub type ResolveModFn = unsafe extern "C" fn(name: *const c_char) -> *mut pxs_Module;
 
 /// This is the PixelScript state.
 pub(crate) struct PixelState {
     pub load_file: RefCell<Option<LoadFileFn>>,
     pub write_file: RefCell<Option<WriteFileFn>>,
+    pub write_file_ex: RefCell<Option<WriteFileExFn>>,
     pub read_dir: RefCell<Option<ReadDirFn>>,
+    pub resolve_module: RefCell<Option<ResolveModFn>>,
 }
 
 /// The State static variable for Lua.
@@ -106,7 +127,9 @@ pub(crate) fn get_pixel_state() -> ReentrantMutexGuard<'static, PixelState> {
         ReentrantMutex::new(PixelState {
             load_file: RefCell::new(None),
             write_file: RefCell::new(None),
+            write_file_ex: RefCell::new(None),
             read_dir: RefCell::new(None),
+            resolve_module: RefCell::new(None),
         })
     });
     // This will
@@ -134,22 +157,33 @@ pub fn read_file(file_path: &str) -> String {
     res_owned
 }
 
-/// Write a file
-pub fn write_file(file_path: &str, contents: &str) {
+/// Is a file writer set?
+pub fn has_file_writer() -> bool {
+    let state = get_pixel_state();
+    state.write_file.borrow().is_some() || state.write_file_ex.borrow().is_some()
+}
+
+/// Write a file. The `WriteFileExFn` writer is used over the `WriteFileFn` one.
+///
+/// Returns false if it was not written. Only the `WriteFileExFn` writer reports failures.
+pub fn write_file(file_path: &str, contents: &str) -> bool {
     // Get state
     let state = get_pixel_state();
-    // Get callback
-    let cbk = state.write_file.borrow();
-    if cbk.is_none() {
-        return;
-    }
-    let cbk = cbk.unwrap();
     // Convert to *const c_char
     let c_file_path = CString::new(file_path).unwrap();
     let c_contents = CString::new(contents).unwrap();
 
     // Call it
-    unsafe { cbk(c_file_path.as_ptr(), c_contents.as_ptr()) };
+    if let Some(cbk) = *state.write_file_ex.borrow() {
+        return unsafe { cbk(c_file_path.as_ptr(), c_contents.as_ptr()) };
+    }
+    match *state.write_file.borrow() {
+        Some(cbk) => {
+            unsafe { cbk(c_file_path.as_ptr(), c_contents.as_ptr()) };
+            true
+        }
+        None => false,
+    }
 }
 
 /// Read a Directory.
@@ -168,6 +202,24 @@ pub fn read_file_dir(dir_path: &str) -> Vec<String> {
     res.into_vec()
 }
 
+/// Ask the host to build a module it did not add.
+pub fn call_module_resolver(name: &str) -> Option<pxs_Module> {
+    // Copy the callback out, the host can use PixelScript while building the module.
+    let cbk = {
+        let state = get_pixel_state();
+        let cbk = *state.resolve_module.borrow();
+        cbk?
+    };
+
+    let c_name = CString::new(name).ok()?;
+    let res = unsafe { cbk(c_name.as_ptr()) };
+    if res.is_null() {
+        None
+    } else {
+        Some(pxs_Module::from_raw(res))
+    }
+}
+
 /// A shared trait for converting from/to a pointer. Specifically a (* mut Self)
 pub trait PtrMagic: Sized {
     /// Moves the object to the heap and returns a raw pointer.
@@ -199,6 +251,10 @@ pub trait PixelScript {
 
     /// Add a global module to the runtime.
     fn add_module(source: Arc<module::pxs_Module>);
+    /// Remove a module added with `add_module`.
+    fn remove_module(source: Arc<module::pxs_Module>);
+    /// Add a module from source code of this runtime. Returns the error, empty if there was none.
+    fn add_source_module(name: &str, source: &str) -> String;
     /// Execute a script in this runtime.
     fn execute(code: &str, file_name: &str) -> String;
     /// Allows the language to start a new thread. In this new thread all callbacks/objects/variables will be empty.
@@ -211,6 +267,7 @@ pub trait PixelScript {
 
 /// Public enum for supported runtimes.
 #[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[allow(non_camel_case_types)]
 pub enum pxs_Runtime {
     /// Lua v5.4 with mlua.
@@ -227,7 +284,15 @@ pub enum pxs_Runtime {
     pxs_PHP
 }
 
+/// Runtime mask with every runtime.
+pub const PXS_ALL_RUNTIMES: u32 = u32::MAX;
+
 impl pxs_Runtime {
+    /// The bit of this runtime in a runtime mask, `1 << runtime`.
+    pub fn mask(&self) -> u32 {
+        1 << *self as u32
+    }
+
     ///
     pub fn from_i64(val: i64) -> Option<Self> {
         match val {
@@ -6,7 +6,12 @@
 //
 // Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
 //
-use crate::shared::{PtrMagic, var::pxs_Var};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, OnceLock},
+};
+
+use crate::shared::{PXS_ALL_RUNTIMES, PtrMagic, pxs_Runtime, signature::pxs_Signature, types::{pxs_PixelType, pxs_ValueType}, var::pxs_Var};
 
 /// A Module is a C representation of data that needs to be (imported,required, etc)
 ///
@@ -32,7 +37,7 @@ use crate::shared::{PtrMagic, var::pxs_Var};
 /// You never free the module pointer because the runtime takes ownership.
 ///
 /// Callbacks within modules use the same FUNCTION_LOOKUP global static variable.
-#[derive(Clone)]
+// Not `Clone`, the module owns the vars of it's `variables`.
 #[allow(non_camel_case_types)]
 pub struct pxs_Module {
     /// Name of the module.
@@ -42,7 +47,19 @@ pub struct pxs_Module {
     /// Variables that need to be added.
     pub variables: Vec<ModuleVariable>,
     /// Internal modules
-    pub modules: Vec<pxs_Module>,
+    pub modules: Vec<Arc<pxs_Module>>,
+    /// Host types exposed as classes.
+    pub types: Vec<Arc<pxs_PixelType>>,
+    /// Value types exposed as constructors.
+    pub value_types: Vec<Arc<pxs_ValueType>>,
+    /// Enums exposed as frozen tables or classes.
+    pub enums: Vec<ModuleEnum>,
+    /// Variables backed by host callbacks.
+    pub live_vars: Vec<ModuleLiveVar>,
+    /// Runtimes this module is added to, a mask of `1 << pxs_Runtime`.
+    pub runtimes: u32,
+    /// Can scripts assign attributes of the module?
+    pub frozen: bool,
 }
 
 /// Wraps a idx with a name.
@@ -51,6 +68,17 @@ pub struct ModuleCallback {
     pub name: String,
     pub full_name: String,
     pub idx: i32,
+    /// Runtimes this callback is added to, a mask of `1 << pxs_Runtime`.
+    pub runtimes: u32,
+    /// Parameters and docs, if the host described them.
+    pub signature: Option<Arc<pxs_Signature>>,
+}
+
+impl ModuleCallback {
+    /// Is this callback added to `runtime`?
+    pub fn is_for(&self, runtime: pxs_Runtime) -> bool {
+        self.runtimes & runtime.mask() != 0
+    }
 }
 
 /// Wraps a Var with a name.
@@ -58,6 +86,45 @@ pub struct ModuleCallback {
 pub struct ModuleVariable {
     pub name: String,
     pub var: *mut pxs_Var,
+    /// Read-only even when the module is not frozen.
+    pub constant: bool,
+}
+
+/// A variable read and written through host callbacks on every access.
+#[derive(Clone)]
+pub struct ModuleLiveVar {
+    pub name: String,
+    /// Getter idx, called with `[runtime]`.
+    pub getter: i32,
+    /// Setter idx, called with `[runtime, value]`. None for a read-only variable.
+    pub setter: Option<i32>,
+}
+
+/// A host enum. Members cross into the host as their value.
+#[derive(Clone)]
+pub struct ModuleEnum {
+    pub name: String,
+    /// Members in declaration order.
+    pub members: Vec<EnumMember>,
+}
+
+/// A named value of a enum.
+#[derive(Clone)]
+pub struct EnumMember {
+    pub name: String,
+    pub value: i64,
+}
+
+impl ModuleEnum {
+    /// Find a member by name.
+    pub fn by_name(&self, name: &str) -> Option<&EnumMember> {
+        self.members.iter().find(|m| m.name == name)
+    }
+
+    /// Find a member by value. The first member wins if values repeat.
+    pub fn by_value(&self, value: i64) -> Option<&EnumMember> {
+        self.members.iter().find(|m| m.value == value)
+    }
 }
 
 impl pxs_Module {
@@ -68,15 +135,35 @@ impl pxs_Module {
             callbacks: vec![],
             variables: vec![],
             modules: vec![],
+            types: vec![],
+            value_types: vec![],
+            enums: vec![],
+            live_vars: vec![],
+            runtimes: PXS_ALL_RUNTIMES,
+            frozen: true,
         }
     }
 
+    /// Is this module added to `runtime`?
+    pub fn is_for(&self, runtime: pxs_Runtime) -> bool {
+        self.runtimes & runtime.mask() != 0
+    }
+
     /// Add a callback to current module.
-    pub fn add_callback(&mut self, name: &str, full_name: &str, idx: i32) {
+    pub fn add_callback(
+        &mut self,
+        name: &str,
+        full_name: &str,
+        idx: i32,
+        runtimes: u32,
+        signature: Option<Arc<pxs_Signature>>,
+    ) {
         self.callbacks.push(ModuleCallback {
             name: name.to_string(),
             full_name: full_name.to_string(),
             idx,
+            runtimes,
+            signature,
         });
     }
 
@@ -85,15 +172,96 @@ impl pxs_Module {
         self.variables.push(ModuleVariable {
             name: name.to_string(),
             var: var,
+            constant: false,
+        });
+    }
+
+    /// Add a constant to current module.
+    pub fn add_constant(&mut self, name: &str, var: *mut pxs_Var) {
+        self.variables.push(ModuleVariable {
+            name: name.to_string(),
+            var,
+            constant: true,
+        });
+    }
+
+    /// Add a live variable.
+    pub fn add_live_var(&mut self, name: &str, getter: i32, setter: Option<i32>) {
+        self.live_vars.push(ModuleLiveVar {
+            name: name.to_string(),
+            getter,
+            setter,
+        });
+    }
+
+    /// Add a host type.
+    pub fn add_type(&mut self, pixel_type: Arc<pxs_PixelType>) {
+        self.types.push(pixel_type);
+    }
+
+    /// Add a value type.
+    pub fn add_value_type(&mut self, value_type: Arc<pxs_ValueType>) {
+        self.value_types.push(value_type);
+    }
+
+    /// Add a enum.
+    pub fn add_enum(&mut self, name: &str, members: Vec<EnumMember>) {
+        self.enums.push(ModuleEnum {
+            name: name.to_string(),
+            members,
         });
     }
 
     /// Add a internal module.
     pub fn add_module(&mut self, child: pxs_Module) {
-        self.modules.push(child);
+        self.modules.push(Arc::new(child));
+    }
+
+    /// Idxs of the functions this module and it's internal modules registered.
+    ///
+    /// Type methods are not included, types are shared between modules.
+    pub fn function_idxs(&self) -> Vec<i32> {
+        let mut idxs: Vec<i32> = self.callbacks.iter().map(|c| c.idx).collect();
+        for live_var in self.live_vars.iter() {
+            idxs.push(live_var.getter);
+            idxs.extend(live_var.setter);
+        }
+        for child in self.modules.iter() {
+            idxs.extend(child.function_idxs());
+        }
+        idxs
     }
 }
 
+/// Modules added to the runtimes, by name.
+static MODULE_LOOKUP: OnceLock<Mutex<HashMap<String, Arc<pxs_Module>>>> = OnceLock::new();
+
+fn get_module_lookup() -> std::sync::MutexGuard<'static, HashMap<String, Arc<pxs_Module>>> {
+    MODULE_LOOKUP.get_or_init(|| Mutex::new(HashMap::new())).lock().unwrap()
+}
+
+/// Save a added module. Returns the module it replaced.
+pub(crate) fn lookup_add_module(module: Arc<pxs_Module>) -> Option<Arc<pxs_Module>> {
+    get_module_lookup().insert(module.name.clone(), module)
+}
+
+/// Every added module, by name.
+pub(crate) fn lookup_modules() -> Vec<Arc<pxs_Module>> {
+    let mut modules: Vec<Arc<pxs_Module>> = get_module_lookup().values().cloned().collect();
+    modules.sort_by(|a, b| a.name.cmp(&b.name));
+    modules
+}
+
+/// Take a added module out of the lookup.
+pub(crate) fn lookup_remove_module(name: &str) -> Option<Arc<pxs_Module>> {
+    get_module_lookup().remove(name)
+}
+
+/// Clear the module lookup.
+pub(crate) fn clear_module_lookup() {
+    get_module_lookup().clear();
+}
+
 impl PtrMagic for pxs_Module {}
 
 unsafe impl Send for pxs_Module {}
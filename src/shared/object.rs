@@ -7,13 +7,16 @@
 // Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
 //
 use std::{
-    collections::HashMap,
     os::raw::c_void,
-    ptr,
     sync::{Arc, Mutex, OnceLock},
 };
 
-use crate::shared::{PtrMagic, module::ModuleCallback};
+use crate::shared::{
+    PtrMagic,
+    module::ModuleCallback,
+    pxs_Runtime,
+    types::{get_or_create_type, pxs_PixelType},
+};
 
 pub type FreeMethod = unsafe extern "C" fn(ptr: *mut c_void);
 
@@ -87,56 +90,363 @@ pub struct pxs_PixelObject {
     pub type_name: String,
     /// The Host pointer
     pub ptr: *mut c_void,
-    /// The language object pointer.
-    pub lang_ptr: Mutex<*mut c_void>,
-    /// Should the lang_ptr be freed by PixelScript?
-    pub free_lang_ptr: Mutex<bool>,
     /// The Method for freeing
     pub free_method: FreeMethod,
-    /// Callbacks with names.
+    /// The type, which holds the methods, properties and operators.
+    ///
+    /// Shared by every instance of the type. PixelObject does not hold variables.
+    pub pixel_type: Arc<pxs_PixelType>,
+    /// Script wrappers of this object, one slot per runtime context.
+    ///
+    /// A Lua mod and a Python mod can wrap the same object at the same time, each runtime only touches it's own slot.
+    wrappers: Mutex<Vec<WrapperSlot>>,
+}
+
+/// Frees the runtime data of a `WrapperSlot`.
+pub type ReleaseWrapper = fn(ptr: *mut c_void);
+
+/// The script wrapper of a object within one runtime.
+struct WrapperSlot {
+    /// The runtime owning the wrapper.
+    runtime: pxs_Runtime,
+    /// Runtime defined context, i.e. the Lua state or the Python VM.
+    context: i64,
+    /// Runtime data, only the owning runtime knows what it points to.
+    ptr: *mut c_void,
+    /// The owning runtime's release logic for `ptr`.
+    release: ReleaseWrapper,
+}
+
+impl WrapperSlot {
+    fn is(&self, runtime: pxs_Runtime, context: i64) -> bool {
+        self.runtime == runtime && self.context == context
+    }
+}
+
+impl Drop for WrapperSlot {
+    fn drop(&mut self) {
+        (self.release)(self.ptr);
+    }
+}
+
+/// Operator kinds a PixelType can overload.
+///
+/// Callback args per kind:
+/// - Binary (Add, Sub, Mul, Div, Eq, Lt, Le, Concat): `[runtime, object, other, reflected]`.
+///   `reflected` is true when the object was the right hand operand (i.e. `2 * v`).
+/// - Unary (ToString, Len, Unm): `[runtime, object]`
+/// - Call: `[runtime, object, args...]`
+/// - Index: `[runtime, object, key]`
+/// - NewIndex: `[runtime, object, key, value]`
+/// - Next: `[runtime, object, state]`. `state` is null on the first call, then the state of the previous step.
+///   Return a list `[state, value]` for the next item or null when done. `state` must not be null.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub enum pxs_OperatorKind {
+    /// Lua (__add), Python (__add__, __radd__)
+    pxs_OpAdd,
+    /// Lua (__sub), Python (__sub__, __rsub__)
+    pxs_OpSub,
+    /// Lua (__mul), Python (__mul__, __rmul__)
+    pxs_OpMul,
+    /// Lua (__div), Python (__truediv__, __rtruediv__)
+    pxs_OpDiv,
+    /// Lua (__eq), Python (__eq__)
+    pxs_OpEq,
+    /// Lua (__lt), Python (__lt__)
+    pxs_OpLt,
+    /// Lua (__le), Python (__le__)
+    pxs_OpLe,
+    /// Lua (__tostring), Python (__str__, __repr__)
+    pxs_OpToString,
+    /// Lua (__len), Python (__len__)
+    pxs_OpLen,
+    /// Lua (__call), Python (__call__)
+    pxs_OpCall,
+    /// Lua (__index), Python (__getitem__)
+    ///
+    /// In Lua this is only called after methods and properties.
+    pxs_OpIndex,
+    /// Lua (__newindex), Python (__setitem__)
+    pxs_OpNewIndex,
+    /// Lua (__concat), Python has no concat operator.
+    pxs_OpConcat,
+    /// Lua (__unm), Python (__neg__)
+    pxs_OpUnm,
+    /// Lua (__pairs), Python (__iter__)
+    ///
+    /// In Lua `for state, value in pairs(obj)`, in Python `for value in obj`.
+    pxs_OpNext,
+}
+
+impl pxs_OperatorKind {
+    /// Short name used for the function lookup.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::pxs_OpAdd => "add",
+            Self::pxs_OpSub => "sub",
+            Self::pxs_OpMul => "mul",
+            Self::pxs_OpDiv => "div",
+            Self::pxs_OpEq => "eq",
+            Self::pxs_OpLt => "lt",
+            Self::pxs_OpLe => "le",
+            Self::pxs_OpToString => "tostring",
+            Self::pxs_OpLen => "len",
+            Self::pxs_OpCall => "call",
+            Self::pxs_OpIndex => "index",
+            Self::pxs_OpNewIndex => "newindex",
+            Self::pxs_OpConcat => "concat",
+            Self::pxs_OpUnm => "unm",
+            Self::pxs_OpNext => "next",
+        }
+    }
+
+    /// Is this a binary operator (gets `other` and `reflected`)?
+    pub fn is_binary(&self) -> bool {
+        matches!(
+            self,
+            Self::pxs_OpAdd
+                | Self::pxs_OpSub
+                | Self::pxs_OpMul
+                | Self::pxs_OpDiv
+                | Self::pxs_OpEq
+                | Self::pxs_OpLt
+                | Self::pxs_OpLe
+                | Self::pxs_OpConcat
+        )
+    }
+}
+
+/// A operator overload on a PixelType.
+#[derive(Clone)]
+pub struct ObjectOperator {
+    pub kind: pxs_OperatorKind,
+    pub callback: ModuleCallback,
+}
+
+/// A property on a PixelType.
+///
+/// The getter is called with `[runtime, object]` and the setter with `[runtime, object, value]`.
+#[derive(Clone)]
+pub struct ObjectProperty {
+    pub name: String,
+    /// The getter callback.
+    pub getter: ModuleCallback,
+    /// The setter callback. None means the property is read-only.
+    pub setter: Option<ModuleCallback>,
+}
+
+/// Primitive types a field, or a element of a `pxs_VarArrayView`, can have.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub enum pxs_FieldType {
+    /// `int32_t`
+    pxs_FieldI32,
+    /// `int64_t`
+    pxs_FieldI64,
+    /// `float`
+    pxs_FieldF32,
+    /// `double`
+    pxs_FieldF64,
+    /// `bool`
+    pxs_FieldBool,
+}
+
+impl pxs_FieldType {
+    pub fn from_i64(val: i64) -> Option<Self> {
+        match val {
+            0 => Some(Self::pxs_FieldI32),
+            1 => Some(Self::pxs_FieldI64),
+            2 => Some(Self::pxs_FieldF32),
+            3 => Some(Self::pxs_FieldF64),
+            4 => Some(Self::pxs_FieldBool),
+            _ => None,
+        }
+    }
+
+    /// Size in bytes.
+    pub fn size(&self) -> usize {
+        match self {
+            Self::pxs_FieldI32 | Self::pxs_FieldF32 => 4,
+            Self::pxs_FieldI64 | Self::pxs_FieldF64 => 8,
+            Self::pxs_FieldBool => 1,
+        }
+    }
+
+    /// Convert a value to this type. Numbers convert between each other, bools only go into bools.
+    ///
+    /// Floats are truncated into ints. Numbers that do not fit the type are a error.
+    pub fn convert(&self, value: FieldValue) -> Result<FieldValue, String> {
+        match (self, value) {
+            (Self::pxs_FieldI32, FieldValue::Int(v)) => i32::try_from(v)
+                .map(|v| FieldValue::Int(v as i64))
+                .map_err(|_| format!("int {} does not fit in a i32", v)),
+            (Self::pxs_FieldI32, FieldValue::Float(v)) => float_to_int(v, 32, "i32").map(FieldValue::Int),
+            (Self::pxs_FieldI64, FieldValue::Int(v)) => Ok(FieldValue::Int(v)),
+            (Self::pxs_FieldI64, FieldValue::Float(v)) => float_to_int(v, 64, "i64").map(FieldValue::Int),
+            (Self::pxs_FieldF32, FieldValue::Int(v)) => Ok(FieldValue::Float(v as f32 as f64)),
+            (Self::pxs_FieldF32, FieldValue::Float(v)) => Ok(FieldValue::Float(v as f32 as f64)),
+            (Self::pxs_FieldF64, FieldValue::Int(v)) => Ok(FieldValue::Float(v as f64)),
+            (Self::pxs_FieldF64, FieldValue::Float(v)) => Ok(FieldValue::Float(v)),
+            (Self::pxs_FieldBool, FieldValue::Bool(v)) => Ok(FieldValue::Bool(v)),
+            (field_type, value) => Err(format!("expects {:?}, got {:?}", field_type, value)),
+        }
+    }
+
+    /// The zero value of this type.
+    pub fn zero(&self) -> FieldValue {
+        match self {
+            Self::pxs_FieldI32 | Self::pxs_FieldI64 => FieldValue::Int(0),
+            Self::pxs_FieldF32 | Self::pxs_FieldF64 => FieldValue::Float(0.0),
+            Self::pxs_FieldBool => FieldValue::Bool(false),
+        }
+    }
+
+    /// Read a value of this type.
+    ///
+    /// # Safety
+    /// `ptr` must point to a value of this type.
+    pub unsafe fn read(&self, ptr: *const u8) -> FieldValue {
+        unsafe {
+            match self {
+                Self::pxs_FieldI32 => FieldValue::Int((ptr as *const i32).read_unaligned() as i64),
+                Self::pxs_FieldI64 => FieldValue::Int((ptr as *const i64).read_unaligned()),
+                Self::pxs_FieldF32 => FieldValue::Float((ptr as *const f32).read_unaligned() as f64),
+                Self::pxs_FieldF64 => FieldValue::Float((ptr as *const f64).read_unaligned()),
+                Self::pxs_FieldBool => FieldValue::Bool(*ptr != 0),
+            }
+        }
+    }
+
+    /// Write a value of this type. The value is converted first, see `convert`.
+    ///
+    /// # Safety
+    /// `ptr` must point to a value of this type.
+    pub unsafe fn write(&self, ptr: *mut u8, value: FieldValue) -> Result<(), String> {
+        let value = self.convert(value)?;
+        unsafe {
+            match (self, value) {
+                (Self::pxs_FieldI32, FieldValue::Int(v)) => (ptr as *mut i32).write_unaligned(v as i32),
+                (Self::pxs_FieldI64, FieldValue::Int(v)) => (ptr as *mut i64).write_unaligned(v),
+                (Self::pxs_FieldF32, FieldValue::Float(v)) => (ptr as *mut f32).write_unaligned(v as f32),
+                (Self::pxs_FieldF64, FieldValue::Float(v)) => (ptr as *mut f64).write_unaligned(v),
+                (Self::pxs_FieldBool, FieldValue::Bool(v)) => *ptr = v as u8,
+                (field_type, value) => unreachable!("{:?} converted into {:?}", field_type, value),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Truncate a float into a int of `bits`, if it fits.
+fn float_to_int(v: f64, bits: i32, name: &str) -> Result<i64, String> {
+    let limit = 2f64.powi(bits - 1);
+    let t = v.trunc();
+    if t >= -limit && t < limit {
+        Ok(t as i64)
+    } else {
+        Err(format!("float {} does not fit in a {}", v, name))
+    }
+}
+
+/// A value read from, or written to, a field.
+#[derive(Debug, Clone, Copy)]
+pub enum FieldValue {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+}
+
+/// A field on a PixelType. Read and written directly at `ptr + offset` of the host object, without a callback.
+#[derive(Debug, Clone)]
+pub struct ObjectField {
+    pub name: String,
+    /// Byte offset within the host struct.
+    pub offset: usize,
+    pub field_type: pxs_FieldType,
+    pub readonly: bool,
+}
+
+impl ObjectField {
+    /// Read the field of a host object.
     ///
-    /// Important to note that a PixelObject can not have `static` callbacks.
+    /// # Safety
+    /// `ptr` must point to a host struct with this field.
+    pub unsafe fn read(&self, ptr: *mut c_void) -> FieldValue {
+        unsafe { self.field_type.read((ptr as *mut u8).add(self.offset)) }
+    }
+
+    /// Write the field of a host object. Numbers are converted to the field type, bools only go into bool fields.
     ///
-    /// The first Var will always be the ptr.
-    pub callbacks: Vec<ModuleCallback>,
-    // PixelObject does not hold variables. They are all getters/setters
+    /// # Safety
+    /// `ptr` must point to a host struct with this field.
+    pub unsafe fn write(&self, ptr: *mut c_void, value: FieldValue) -> Result<(), String> {
+        unsafe { self.field_type.write((ptr as *mut u8).add(self.offset), value) }
+            .map_err(|e| format!("field '{}' {}", self.name, e))
+    }
 }
 
 impl pxs_PixelObject {
+    /// Create a object of a registered type. The type is created if it does not exist yet.
     pub fn new(ptr: *mut c_void, free_method: FreeMethod, type_name: &str) -> Self {
+        Self::with_type(ptr, free_method, get_or_create_type(type_name))
+    }
+
+    /// Create a instance of a type.
+    pub fn with_type(ptr: *mut c_void, free_method: FreeMethod, pixel_type: Arc<pxs_PixelType>) -> Self {
         Self {
             ptr,
             free_method,
-            callbacks: vec![],
-            lang_ptr: Mutex::new(ptr::null_mut()),
-            type_name: type_name.to_string(),
-            free_lang_ptr: Mutex::new(true),
+            type_name: pixel_type.name.clone(),
+            pixel_type,
+            wrappers: Mutex::new(vec![]),
         }
     }
 
-    pub fn add_callback(&mut self, name: &str, full_name: &str, idx: i32) {
-        self.callbacks.push(ModuleCallback {
-            name: name.to_string(),
-            full_name: full_name.to_string(),
-            idx,
-        });
+    /// Get the wrapper data of a runtime context.
+    pub fn get_wrapper(&self, runtime: pxs_Runtime, context: i64) -> Option<*mut c_void> {
+        let wrappers = self.wrappers.lock().unwrap();
+        wrappers
+            .iter()
+            .find(|slot| slot.is(runtime, context))
+            .map(|slot| slot.ptr)
     }
 
-    pub fn update_lang_ptr(&self, n_ptr: *mut c_void) {
-        let mut guard = self.lang_ptr.lock().unwrap();
-
-        if !guard.is_null() {
-            eprintln!("Can not mutate if ptr is already set.");
-            return;
-        }
+    /// Set the wrapper data of a runtime context. A previous wrapper of that context is released.
+    pub fn set_wrapper(&self, runtime: pxs_Runtime, context: i64, ptr: *mut c_void, release: ReleaseWrapper) {
+        let old = self.take_wrapper(runtime, context);
+        self.wrappers.lock().unwrap().push(WrapperSlot {
+            runtime,
+            context,
+            ptr,
+            release,
+        });
+        drop(old);
+    }
 
-        *guard = n_ptr;
+    /// Release the wrapper of a runtime context.
+    pub fn remove_wrapper(&self, runtime: pxs_Runtime, context: i64) {
+        // Released outside of the lock.
+        drop(self.take_wrapper(runtime, context));
     }
 
-    pub fn update_free_lang_ptr(&self, val: bool) {
-        let mut guard = self.free_lang_ptr.lock().unwrap();
+    /// Take the slot of a runtime context out of the object.
+    fn take_wrapper(&self, runtime: pxs_Runtime, context: i64) -> Option<WrapperSlot> {
+        let mut wrappers = self.wrappers.lock().unwrap();
+        let pos = wrappers.iter().position(|slot| slot.is(runtime, context))?;
+        Some(wrappers.swap_remove(pos))
+    }
 
-        *guard = val;
+    /// Take every slot of a runtime. A None context matches every context of the runtime.
+    fn take_wrappers(&self, runtime: pxs_Runtime, context: Option<i64>) -> Vec<WrapperSlot> {
+        let mut wrappers = self.wrappers.lock().unwrap();
+        let (taken, kept) = std::mem::take(&mut *wrappers)
+            .into_iter()
+            .partition(|slot| slot.runtime == runtime && context.is_none_or(|c| c == slot.context));
+        *wrappers = kept;
+        taken
     }
 }
 
@@ -145,14 +455,8 @@ unsafe impl Send for pxs_PixelObject {}
 unsafe impl Sync for pxs_PixelObject {}
 impl Drop for pxs_PixelObject {
     fn drop(&mut self) {
-        let mut lang_ptr = self.lang_ptr.lock().unwrap();
-        if *self.free_lang_ptr.lock().unwrap() {
-            if !lang_ptr.is_null() {
-                //  Free Language memory
-                let _ = unsafe { Box::from_raw(*lang_ptr) };
-            }
-        }
-        *lang_ptr = ptr::null_mut();
+        // Release script wrappers first, they may point at the host memory.
+        self.wrappers.get_mut().unwrap().clear();
         // Free host memory
         unsafe {
             (self.free_method)(self.ptr);
@@ -160,12 +464,69 @@ impl Drop for pxs_PixelObject {
     }
 }
 
+/// Build a object handle from a slot index and generation.
+///
+/// Handles are shared between all runtimes (`_id` in Lua, `ptr` in Python). The low 32 bits are the slot
+/// and the high 32 bits the generation, so a handle to a destroyed object never reaches a reused slot.
+pub fn make_handle(index: u32, generation: u32) -> i64 {
+    ((generation as i64) << 32) | index as i64
+}
+
+/// Get the slot index of a handle.
+pub fn handle_index(handle: i64) -> u32 {
+    (handle & 0xFFFF_FFFF) as u32
+}
+
+/// Get the generation of a handle.
+pub fn handle_generation(handle: i64) -> u32 {
+    (handle >> 32) as u32
+}
+
+/// A slot within the object lookup.
+struct ObjectSlot {
+    /// Bumped each time the slot is freed.
+    generation: u32,
+    /// The object, None when the slot is free.
+    object: Option<Arc<pxs_PixelObject>>,
+    /// Live references. Host `pxs_HostObject` vars and script wrappers each count as one.
+    ///
+    /// When it drops to 0 the object is removed and `free_method` is called.
+    refs: usize,
+}
+
+impl ObjectSlot {
+    /// Free the slot and return the object it held.
+    fn release(&mut self) -> Option<Arc<pxs_PixelObject>> {
+        self.generation = self.generation.wrapping_add(1).max(1);
+        self.refs = 0;
+        self.object.take()
+    }
+}
+
 /// Lookup state structure
 pub struct ObjectLookup {
-    /// Object hash shared between all runtimes.
-    ///
-    /// Negative numbers are valid here.
-    pub object_hash: HashMap<i32, Arc<pxs_PixelObject>>,
+    /// Object slots shared between all runtimes.
+    slots: Vec<ObjectSlot>,
+    /// Free slot indexes, reused before growing.
+    free_slots: Vec<u32>,
+}
+
+impl ObjectLookup {
+    /// Get the live slot of a handle.
+    fn slot_mut(&mut self, handle: i64) -> Option<&mut ObjectSlot> {
+        let slot = self.slots.get_mut(handle_index(handle) as usize)?;
+        if slot.generation != handle_generation(handle) || slot.object.is_none() {
+            return None;
+        }
+        Some(slot)
+    }
+
+    /// Free the slot of a handle and return the object.
+    fn release(&mut self, handle: i64) -> Option<Arc<pxs_PixelObject>> {
+        let removed = self.slot_mut(handle)?.release();
+        self.free_slots.push(handle_index(handle));
+        removed
+    }
 }
 
 /// The object lookup!
@@ -176,7 +537,8 @@ fn get_object_lookup() -> std::sync::MutexGuard<'static, ObjectLookup> {
     OBJECT_LOOKUP
         .get_or_init(|| {
             Mutex::new(ObjectLookup {
-                object_hash: HashMap::new(),
+                slots: vec![],
+                free_slots: vec![],
             })
         })
         .lock()
@@ -184,30 +546,101 @@ fn get_object_lookup() -> std::sync::MutexGuard<'static, ObjectLookup> {
 }
 
 pub(crate) fn clear_object_lookup() {
-    let mut lookup = get_object_lookup();
-    lookup.object_hash.clear();
+    // Objects are dropped outside of the lock, free methods can call back into PixelScript.
+    let objects: Vec<_> = {
+        let mut lookup = get_object_lookup();
+        let lookup = &mut *lookup;
+        lookup.free_slots.clear();
+        let mut objects = vec![];
+        for (i, slot) in lookup.slots.iter_mut().enumerate() {
+            // Bump every generation so old handles stay invalid.
+            objects.push(slot.release());
+            lookup.free_slots.push(i as u32);
+        }
+        objects
+    };
+    drop(objects);
 }
 
-// add_object(Arc::clone(&pixel_arc))
-pub(crate) fn lookup_add_object(pixel_obj: Arc<pxs_PixelObject>) -> i32 {
+/// Add a object to the lookup, returns its handle.
+pub(crate) fn lookup_add_object(pixel_obj: Arc<pxs_PixelObject>) -> i64 {
     let mut lookup = get_object_lookup();
 
-    let idx = lookup.object_hash.len();
+    let index = match lookup.free_slots.pop() {
+        Some(index) => index,
+        None => {
+            lookup.slots.push(ObjectSlot {
+                generation: 1,
+                object: None,
+                refs: 0,
+            });
+            (lookup.slots.len() - 1) as u32
+        }
+    };
 
-    lookup
-        .object_hash
-        .insert(idx as i32, Arc::clone(&pixel_obj));
+    let slot = &mut lookup.slots[index as usize];
+    slot.object = Some(pixel_obj);
+    slot.refs = 0;
 
-    idx as i32
+    make_handle(index, slot.generation)
 }
 
-/// Get a PixelObject Arc
-pub(crate) fn get_object(idx: i32) -> Option<Arc<pxs_PixelObject>> {
-    let lookup = get_object_lookup();
+/// Add a reference to a object.
+pub(crate) fn object_incref(handle: i64) {
+    let mut lookup = get_object_lookup();
+    if let Some(slot) = lookup.slot_mut(handle) {
+        slot.refs += 1;
+    }
+}
 
-    if let Some(res) = lookup.object_hash.get(&idx) {
-        Some(res.to_owned())
-    } else {
-        None
+/// Remove a reference from a object. The last reference removes it from the lookup.
+///
+/// Stale handles are ignored.
+pub(crate) fn object_decref(handle: i64) {
+    let removed = {
+        let mut lookup = get_object_lookup();
+        let Some(slot) = lookup.slot_mut(handle) else {
+            return;
+        };
+        slot.refs = slot.refs.saturating_sub(1);
+        if slot.refs > 0 {
+            return;
+        }
+        lookup.release(handle)
+    };
+    // Free outside of the lock. Anyone still holding the Arc keeps it alive until they are done.
+    drop(removed);
+}
+
+/// Destroy a object even if references remain. Those references become stale.
+///
+/// Returns false if the handle was already stale.
+pub(crate) fn destroy_object(handle: i64) -> bool {
+    let removed = {
+        let mut lookup = get_object_lookup();
+        lookup.release(handle)
+    };
+    let destroyed = removed.is_some();
+    drop(removed);
+    destroyed
+}
+
+/// Get a PixelObject Arc. None if the handle is stale.
+pub(crate) fn get_object(handle: i64) -> Option<Arc<pxs_PixelObject>> {
+    let mut lookup = get_object_lookup();
+    lookup.slot_mut(handle).and_then(|slot| slot.object.clone())
+}
+
+/// Release the wrappers a runtime holds on every live object. A None context releases every context of the runtime.
+///
+/// Called when a runtime clears it's state or stops.
+pub(crate) fn release_wrappers(runtime: pxs_Runtime, context: Option<i64>) {
+    let objects: Vec<_> = {
+        let lookup = get_object_lookup();
+        lookup.slots.iter().filter_map(|slot| slot.object.clone()).collect()
+    };
+    // Released outside of the lookup lock.
+    for object in objects {
+        drop(object.take_wrappers(runtime, context));
     }
 }
@@ -0,0 +1,311 @@
+// Copyright 2026 Jordan Castro <jordan@grupojvm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+//
+use crate::shared::{
+    PtrMagic,
+    args::FromPxsVar,
+    object::get_object,
+    pxs_Runtime,
+    var::{pxs_Var, pxs_VarType},
+};
+
+/// Type of a parameter or return value in a signature.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(non_camel_case_types)]
+pub enum pxs_ParamType {
+    /// Anything.
+    pxs_ParamAny,
+    pxs_ParamInt,
+    pxs_ParamFloat,
+    pxs_ParamBool,
+    pxs_ParamString,
+    /// A list. Tables and dicts without list items (`pxs_Object`) and array views match too.
+    pxs_ParamList,
+    pxs_ParamFunction,
+    /// A host object.
+    pxs_ParamObject,
+    /// Nothing, only for return values.
+    pxs_ParamNull,
+}
+
+impl pxs_ParamType {
+    /// The parameter type a var would match without coercion.
+    pub fn of_var(tag: &pxs_VarType) -> Self {
+        match tag {
+            pxs_VarType::pxs_Int64 | pxs_VarType::pxs_UInt64 => Self::pxs_ParamInt,
+            pxs_VarType::pxs_Float64 => Self::pxs_ParamFloat,
+            pxs_VarType::pxs_Bool => Self::pxs_ParamBool,
+            pxs_VarType::pxs_String => Self::pxs_ParamString,
+            pxs_VarType::pxs_List | pxs_VarType::pxs_Object | pxs_VarType::pxs_ArrayView => Self::pxs_ParamList,
+            pxs_VarType::pxs_Function => Self::pxs_ParamFunction,
+            pxs_VarType::pxs_HostObject => Self::pxs_ParamObject,
+            pxs_VarType::pxs_Null => Self::pxs_ParamNull,
+            _ => Self::pxs_ParamAny,
+        }
+    }
+
+    /// Convert a argument to this type. Only lossless coercions are done: ints to floats and floats
+    /// without a fraction to ints, with the same rules as the typed args (`FromPxsVar`).
+    pub fn coerce(&self, var: pxs_Var) -> Result<pxs_Var, pxs_Var> {
+        match (self, &var.tag) {
+            (Self::pxs_ParamAny, _) => Ok(var),
+            (param_type, tag) if Self::of_var(tag) == *param_type => Ok(var),
+            (Self::pxs_ParamFloat, pxs_VarType::pxs_Int64 | pxs_VarType::pxs_UInt64) => {
+                f64::from_pxs_var(&var).map(pxs_Var::new_f64).map_err(|_| var)
+            }
+            (Self::pxs_ParamInt, pxs_VarType::pxs_Float64) => {
+                i64::from_pxs_var(&var).map(pxs_Var::new_i64).map_err(|_| var)
+            }
+            _ => Err(var),
+        }
+    }
+
+    /// Name of the type in a runtime.
+    pub fn type_name(&self, runtime: pxs_Runtime) -> &'static str {
+        match runtime {
+            pxs_Runtime::pxs_Lua => match self {
+                Self::pxs_ParamAny => "any",
+                Self::pxs_ParamInt => "integer",
+                Self::pxs_ParamFloat => "number",
+                Self::pxs_ParamBool => "boolean",
+                Self::pxs_ParamString => "string",
+                Self::pxs_ParamList => "table",
+                Self::pxs_ParamFunction => "function",
+                Self::pxs_ParamObject => "table",
+                Self::pxs_ParamNull => "nil",
+            },
+            pxs_Runtime::pxs_JavaScript | pxs_Runtime::pxs_Easyjs => match self {
+                Self::pxs_ParamAny => "any",
+                Self::pxs_ParamInt => "number",
+                Self::pxs_ParamFloat => "number",
+                Self::pxs_ParamBool => "boolean",
+                Self::pxs_ParamString => "string",
+                Self::pxs_ParamList => "any[]",
+                Self::pxs_ParamFunction => "Function",
+                Self::pxs_ParamObject => "object",
+                Self::pxs_ParamNull => "void",
+            },
+            _ => match self {
+                Self::pxs_ParamAny => "Any",
+                Self::pxs_ParamInt => "int",
+                Self::pxs_ParamFloat => "float",
+                Self::pxs_ParamBool => "bool",
+                Self::pxs_ParamString => "str",
+                Self::pxs_ParamList => "list",
+                Self::pxs_ParamFunction => "Callable",
+                Self::pxs_ParamObject => "object",
+                Self::pxs_ParamNull => "None",
+            },
+        }
+    }
+}
+
+/// A parameter of a signature.
+#[derive(Clone)]
+pub struct SigParam {
+    pub name: String,
+    pub param_type: pxs_ParamType,
+    /// Used when the argument is not passed. None for a required parameter.
+    pub default: Option<pxs_Var>,
+}
+
+/// Describes the parameters, return type and docs of a callback.
+///
+/// The runtime is not part of the signature, parameters start at the first script argument.
+///
+/// example:
+/// ```c
+/// pxs_Signature* sig = pxs_newsig("Print a line, `times` times.");
+/// pxs_sigaddparam(sig, "text", pxs_ParamString, NULL);
+/// pxs_sigaddparam(sig, "times", pxs_ParamInt, pxs_newint(1));
+/// pxs_addfunc_ex(module, "println", println, NULL, sig);
+/// ```
+#[derive(Clone)]
+#[allow(non_camel_case_types)]
+pub struct pxs_Signature {
+    /// Name of the function in errors, `module.name`. Set when the callback is added.
+    pub name: String,
+    pub params: Vec<SigParam>,
+    /// Does it take more arguments after `params`?
+    pub variadic: bool,
+    pub returns: pxs_ParamType,
+    pub doc: String,
+}
+
+impl pxs_Signature {
+    /// Create a signature without parameters.
+    pub fn new(doc: String) -> Self {
+        pxs_Signature {
+            name: String::new(),
+            params: vec![],
+            variadic: false,
+            returns: pxs_ParamType::pxs_ParamAny,
+            doc,
+        }
+    }
+
+    /// Add a parameter.
+    pub fn add_param(&mut self, name: &str, param_type: pxs_ParamType, default: Option<pxs_Var>) {
+        self.params.push(SigParam {
+            name: name.to_string(),
+            param_type,
+            default,
+        });
+    }
+
+    /// The signature line of a function in a runtime. `add(a: int, b: int = 1) -> int` for Python.
+    pub fn signature_line(&self, name: &str, runtime: pxs_Runtime) -> String {
+        let mut params: Vec<String> = self
+            .params
+            .iter()
+            .map(|p| {
+                let mut param = format!("{}: {}", p.name, p.param_type.type_name(runtime));
+                if let Some(default) = p.default.as_ref() {
+                    param.push_str(&format!(" = {}", default_repr(default, runtime)));
+                }
+                param
+            })
+            .collect();
+        if self.variadic {
+            params.push(match runtime {
+                pxs_Runtime::pxs_Lua => "...".to_string(),
+                _ => "*args".to_string(),
+            });
+        }
+
+        format!("{}({}) -> {}", name, params.join(", "), self.returns.type_name(runtime))
+    }
+
+    /// The signature line followed by the doc.
+    pub fn describe(&self, name: &str, runtime: pxs_Runtime) -> String {
+        let line = self.signature_line(name, runtime);
+        if self.doc.is_empty() {
+            line
+        } else {
+            format!("{}\n\n{}", line, self.doc)
+        }
+    }
+
+    /// Check the arguments of a call before the host sees them.
+    ///
+    /// `args` are the callback args, index 0 is the runtime. Arguments are coerced and missing or null
+    /// optional arguments get their default. The error names the function, parameter and expected type.
+    pub fn check(&self, mut args: Vec<pxs_Var>) -> Result<Vec<pxs_Var>, String> {
+        let runtime = args
+            .first()
+            .and_then(|rt| rt.get_i64().ok())
+            .and_then(pxs_Runtime::from_i64)
+            .unwrap_or(pxs_Runtime::pxs_Python);
+        let given = args.len().saturating_sub(1);
+        if given > self.params.len() && !self.variadic {
+            return Err(format!(
+                "{}() takes at most {} arguments ({} given)",
+                self.name,
+                self.params.len(),
+                given
+            ));
+        }
+
+        for (i, param) in self.params.iter().enumerate() {
+            // Trailing arguments a script leaves out, and nulls, take the default.
+            let passed = i < given;
+            if !passed || args[i + 1].tag == pxs_VarType::pxs_Null {
+                if let Some(default) = param.default.as_ref() {
+                    if passed {
+                        args[i + 1] = default.clone();
+                    } else {
+                        args.push(default.clone());
+                    }
+                    continue;
+                }
+                if !passed {
+                    return Err(format!(
+                        "{}() missing argument {} '{}' ({})",
+                        self.name,
+                        i + 1,
+                        param.name,
+                        param.param_type.type_name(runtime)
+                    ));
+                }
+            }
+
+            let arg = std::mem::replace(&mut args[i + 1], pxs_Var::new_null());
+            match param.param_type.coerce(arg) {
+                Ok(arg) => args[i + 1] = arg,
+                Err(arg) => {
+                    return Err(format!(
+                        "{}() argument {} '{}': expected {}, found {}",
+                        self.name,
+                        i + 1,
+                        param.name,
+                        param.param_type.type_name(runtime),
+                        found_name(&arg, runtime)
+                    ));
+                }
+            }
+        }
+
+        Ok(args)
+    }
+}
+
+/// Name of the type of a argument in errors. Host objects and values are named by their type.
+fn found_name(var: &pxs_Var, runtime: pxs_Runtime) -> String {
+    match &var.tag {
+        pxs_VarType::pxs_HostObject => match get_object(var.get_object_ptr()) {
+            Some(object) => object.type_name.clone(),
+            None => "destroyed object".to_string(),
+        },
+        pxs_VarType::pxs_Value => var
+            .get_value()
+            .map(|value| value.value_type.name.clone())
+            .unwrap_or_default(),
+        pxs_VarType::pxs_Exception => "exception".to_string(),
+        tag => pxs_ParamType::of_var(tag).type_name(runtime).to_string(),
+    }
+}
+
+/// Can the var be a default? Defaults are copied into every call that leaves the argument out, so only
+/// plain values are allowed.
+pub fn valid_default(var: &pxs_Var) -> bool {
+    matches!(
+        var.tag,
+        pxs_VarType::pxs_Int64
+            | pxs_VarType::pxs_UInt64
+            | pxs_VarType::pxs_Float64
+            | pxs_VarType::pxs_Bool
+            | pxs_VarType::pxs_String
+            | pxs_VarType::pxs_Null
+    )
+}
+
+/// How a default value is written in a runtime.
+pub fn default_repr(var: &pxs_Var, runtime: pxs_Runtime) -> String {
+    let lua = runtime == pxs_Runtime::pxs_Lua;
+    match var.tag {
+        pxs_VarType::pxs_Int64 => var.get_i64().map(|v| v.to_string()).unwrap_or_default(),
+        pxs_VarType::pxs_UInt64 => var.get_u64().map(|v| v.to_string()).unwrap_or_default(),
+        pxs_VarType::pxs_Float64 => var.get_f64().map(|v| format!("{:?}", v)).unwrap_or_default(),
+        pxs_VarType::pxs_Bool => match (var.get_bool().unwrap_or_default(), lua) {
+            (true, true) => "true".to_string(),
+            (false, true) => "false".to_string(),
+            (true, false) => "True".to_string(),
+            (false, false) => "False".to_string(),
+        },
+        pxs_VarType::pxs_String => format!("{:?}", var.get_string().unwrap_or_default()),
+        pxs_VarType::pxs_Null if lua => "nil".to_string(),
+        pxs_VarType::pxs_Null => "None".to_string(),
+        _ => "...".to_string(),
+    }
+}
+
+impl PtrMagic for pxs_Signature {}
+
+unsafe impl Send for pxs_Signature {}
+unsafe impl Sync for pxs_Signature {}
@@ -0,0 +1,636 @@
+// Copyright 2026 Jordan Castro <jordan@grupojvm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+//
+use crate::shared::{
+    PtrMagic,
+    module::{ModuleCallback, ModuleEnum, lookup_modules, pxs_Module},
+    object::{FieldValue, pxs_FieldType},
+    pxs_Runtime,
+    signature::{default_repr, pxs_ParamType},
+    types::{pxs_PixelType, pxs_ValueType},
+    var::pxs_Var,
+};
+
+/// First line of every stub file.
+const HEADER: &str = "Generated by PixelScript, do not edit.";
+
+/// Lua keywords, they can not be used after a `.`.
+const LUA_KEYWORDS: &[&str] = &[
+    "and", "break", "do", "else", "elseif", "end", "false", "for", "function", "goto", "if", "in",
+    "local", "nil", "not", "or", "repeat", "return", "then", "true", "until", "while",
+];
+
+/// Python keywords, they can not be declared in a stub.
+const PY_KEYWORDS: &[&str] = &[
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue",
+    "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import",
+    "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while",
+    "with", "yield",
+];
+
+/// JavaScript reserved words, they can not be declared in a stub.
+const JS_KEYWORDS: &[&str] = &[
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "debugger",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "enum",
+    "export",
+    "extends",
+    "false",
+    "finally",
+    "for",
+    "function",
+    "if",
+    "import",
+    "in",
+    "instanceof",
+    "new",
+    "null",
+    "return",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "var",
+    "void",
+    "while",
+    "with",
+];
+
+/// A generated stub file. The path is relative to the output directory.
+pub struct StubFile {
+    pub path: String,
+    pub contents: String,
+}
+
+/// Generate the editor stubs of every added module.
+///
+/// Python gets a `.pyi` per module, `name/__init__.pyi` for modules with internal modules. Lua gets a
+/// `---@meta` file per module, named like the module. JavaScript gets a `.d.ts` per top level module.
+pub fn generate_stubs(runtime: pxs_Runtime) -> Vec<StubFile> {
+    let mut files = vec![];
+    for module in lookup_modules().iter().filter(|m| m.is_for(runtime)) {
+        match runtime {
+            pxs_Runtime::pxs_Lua => lua_module(module, &module.name, &mut files),
+            pxs_Runtime::pxs_Python | pxs_Runtime::pxs_RustPython => {
+                py_module(module, &module.name.replace(".", "/"), &mut files)
+            }
+            pxs_Runtime::pxs_JavaScript | pxs_Runtime::pxs_Easyjs => files.push(StubFile {
+                path: format!("{}.d.ts", module.name),
+                contents: format!(
+                    "// {HEADER}\n\ndeclare module \"{}\" {{\n{}}}\n",
+                    module.name,
+                    ts_items(module, 1)
+                ),
+            }),
+            pxs_Runtime::pxs_PHP => {}
+        }
+    }
+    files
+}
+
+/// The parameter type of a field.
+pub(crate) fn field_param_type(field_type: pxs_FieldType) -> pxs_ParamType {
+    match field_type.zero() {
+        FieldValue::Int(_) => pxs_ParamType::pxs_ParamInt,
+        FieldValue::Float(_) => pxs_ParamType::pxs_ParamFloat,
+        FieldValue::Bool(_) => pxs_ParamType::pxs_ParamBool,
+    }
+}
+
+/// The parameter type of a module variable.
+pub(crate) fn var_param_type(var: *mut pxs_Var) -> pxs_ParamType {
+    if var.is_null() {
+        return pxs_ParamType::pxs_ParamAny;
+    }
+    match pxs_ParamType::of_var(&unsafe { pxs_Var::from_borrow(var) }.tag) {
+        pxs_ParamType::pxs_ParamNull => pxs_ParamType::pxs_ParamAny,
+        param_type => param_type,
+    }
+}
+
+/// The callbacks of a module that are added to `runtime`.
+fn callbacks_for(
+    module: &pxs_Module,
+    runtime: pxs_Runtime,
+) -> impl Iterator<Item = &ModuleCallback> {
+    module.callbacks.iter().filter(move |c| c.is_for(runtime))
+}
+
+// Python
+
+/// Write the `.pyi` of a module and it's internal modules. `path` is the module path with slashes.
+fn py_module(module: &pxs_Module, path: &str, files: &mut Vec<StubFile>) {
+    let rt = pxs_Runtime::pxs_Python;
+    let mut out = format!("# {HEADER}\nfrom typing import Any, Callable, Final\n\n");
+
+    for child in module.modules.iter() {
+        out.push_str(&format!("from . import {0} as {0}\n", child.name));
+    }
+    if !module.modules.is_empty() {
+        out.push('\n');
+    }
+
+    for variable in module.variables.iter() {
+        let type_name = var_param_type(variable.var).type_name(rt);
+        if variable.constant {
+            out.push_str(&format!("{}: Final[{}]\n", variable.name, type_name));
+        } else {
+            out.push_str(&format!("{}: {}\n", variable.name, type_name));
+        }
+    }
+    for live_var in module.live_vars.iter() {
+        out.push_str(&format!("{}: Any\n", live_var.name));
+    }
+
+    for callback in callbacks_for(module, rt) {
+        out.push_str(&format!("\n{}", py_def(callback, None, "")));
+    }
+    for pixel_type in module.types.iter() {
+        out.push_str(&format!("\n{}", py_type(pixel_type)));
+    }
+    for value_type in module.value_types.iter() {
+        out.push_str(&format!("\n{}", py_value_type(value_type)));
+    }
+    for module_enum in module.enums.iter() {
+        out.push_str(&format!("\n{}", py_enum(module_enum)));
+    }
+
+    if module.modules.is_empty() {
+        files.push(StubFile {
+            path: format!("{path}.pyi"),
+            contents: out,
+        });
+    } else {
+        files.push(StubFile {
+            path: format!("{path}/__init__.pyi"),
+            contents: out,
+        });
+        for child in module.modules.iter() {
+            py_module(child, &format!("{path}/{}", child.name), files);
+        }
+    }
+}
+
+/// A `def`, with `self_param` for methods.
+fn py_def(callback: &ModuleCallback, self_param: Option<&str>, indent: &str) -> String {
+    let rt = pxs_Runtime::pxs_Python;
+    if PY_KEYWORDS.contains(&callback.name.as_str()) {
+        return format!("{indent}# '{}' is a keyword, use getattr\n", callback.name);
+    }
+    let mut params: Vec<String> = self_param.map(|p| p.to_string()).into_iter().collect();
+    let (returns, doc) = match callback.signature.as_ref() {
+        Some(signature) => {
+            for param in signature.params.iter() {
+                let mut p = format!("{}: {}", param.name, param.param_type.type_name(rt));
+                if let Some(default) = param.default.as_ref() {
+                    p.push_str(&format!(" = {}", default_repr(default, rt)));
+                }
+                params.push(p);
+            }
+            if signature.variadic {
+                params.push("*args: Any".to_string());
+            }
+            (signature.returns.type_name(rt), signature.doc.as_str())
+        }
+        None => {
+            params.push("*args: Any".to_string());
+            ("Any", "")
+        }
+    };
+
+    let head = format!(
+        "{indent}def {}({}) -> {}:",
+        callback.name,
+        params.join(", "),
+        returns
+    );
+    if doc.is_empty() {
+        format!("{head} ...\n")
+    } else {
+        format!(
+            "{head}\n{indent}    \"\"\"{}\"\"\"\n{indent}    ...\n",
+            doc.replace("\"\"\"", "\\\"\\\"\\\"")
+        )
+    }
+}
+
+/// A class for a host type.
+fn py_type(pixel_type: &pxs_PixelType) -> String {
+    let rt = pxs_Runtime::pxs_Python;
+    let members = pixel_type.members();
+    let mut out = format!("class {}:\n", pixel_type.name);
+
+    for constant in members.constants.iter() {
+        out.push_str(&format!(
+            "    {}: Final[{}]\n",
+            constant.name,
+            pxs_ParamType::of_var(&constant.var.tag).type_name(rt)
+        ));
+    }
+    for property in members.properties.iter() {
+        out.push_str(&format!("    {}: Any\n", property.name));
+    }
+    for field in members.fields.iter() {
+        out.push_str(&format!(
+            "    {}: {}\n",
+            field.name,
+            field_param_type(field.field_type).type_name(rt)
+        ));
+    }
+    if members.constructor.is_some() {
+        out.push_str("    def __init__(self, *args: Any) -> None: ...\n");
+    }
+    for method in members.methods.iter() {
+        out.push_str(&py_def(method, Some("self"), "    "));
+    }
+    for method in members.statics.iter() {
+        if !PY_KEYWORDS.contains(&method.name.as_str()) {
+            out.push_str("    @staticmethod\n");
+        }
+        out.push_str(&py_def(method, None, "    "));
+    }
+    if out.ends_with(":\n") {
+        out.push_str("    ...\n");
+    }
+    out
+}
+
+/// A class for a value type.
+fn py_value_type(value_type: &pxs_ValueType) -> String {
+    let rt = pxs_Runtime::pxs_Python;
+    let mut out = format!("class {}:\n", value_type.name);
+    let mut params = vec!["self".to_string()];
+    for field in value_type.fields.iter() {
+        let type_name = field_param_type(field.field_type).type_name(rt);
+        out.push_str(&format!("    {}: {}\n", field.name, type_name));
+        params.push(format!("{}: {} = ...", field.name, type_name));
+    }
+    out.push_str(&format!(
+        "    def __init__({}) -> None: ...\n",
+        params.join(", ")
+    ));
+    out
+}
+
+/// A class for a enum, members are instances of it.
+fn py_enum(module_enum: &ModuleEnum) -> String {
+    let name = &module_enum.name;
+    let mut out = format!("class {name}:\n    name: str\n    value: int\n");
+    for member in module_enum.members.iter() {
+        out.push_str(&format!("    {}: {name}\n", member.name));
+    }
+    out
+}
+
+// Lua
+
+/// Write the `---@meta` file of a module and it's internal modules. `path` is the full module name.
+fn lua_module(module: &pxs_Module, path: &str, files: &mut Vec<StubFile>) {
+    let rt = pxs_Runtime::pxs_Lua;
+    let mut out = format!("---@meta {path}\n-- {HEADER}\n\n---@class {path}\nlocal M = {{}}\n");
+
+    for variable in module.variables.iter() {
+        out.push_str(&format!(
+            "\n---@type {}\n{} = nil\n",
+            var_param_type(variable.var).type_name(rt),
+            lua_field("M", &variable.name)
+        ));
+    }
+    for live_var in module.live_vars.iter() {
+        out.push_str(&format!(
+            "\n---@type any\n{} = nil\n",
+            lua_field("M", &live_var.name)
+        ));
+    }
+    for child in module.modules.iter() {
+        out.push_str(&format!(
+            "\n{} = require(\"{path}.{}\")\n",
+            lua_field("M", &child.name),
+            child.name
+        ));
+    }
+
+    for callback in callbacks_for(module, rt) {
+        out.push_str(&format!("\n{}", lua_function(callback, "M", false)));
+    }
+    for pixel_type in module.types.iter() {
+        out.push_str(&format!("\n{}", lua_type(pixel_type, path)));
+    }
+    for value_type in module.value_types.iter() {
+        out.push_str(&format!("\n{}", lua_value_type(value_type, path)));
+    }
+    for module_enum in module.enums.iter() {
+        out.push_str(&format!(
+            "\n---@enum {path}.{}\n{} = {{\n",
+            module_enum.name,
+            lua_field("M", &module_enum.name)
+        ));
+        for member in module_enum.members.iter() {
+            out.push_str(&format!("    {} = {},\n", member.name, member.value));
+        }
+        out.push_str("}\n");
+    }
+
+    out.push_str("\nreturn M\n");
+    files.push(StubFile {
+        path: format!("{path}.lua"),
+        contents: out,
+    });
+
+    for child in module.modules.iter() {
+        lua_module(child, &format!("{path}.{}", child.name), files);
+    }
+}
+
+/// A field of a table, `M.name` or `M["end"]` for keywords.
+fn lua_field(table: &str, name: &str) -> String {
+    if LUA_KEYWORDS.contains(&name) {
+        format!("{table}[\"{name}\"]")
+    } else {
+        format!("{table}.{name}")
+    }
+}
+
+/// A annotated function of `table`. Methods take `self`.
+fn lua_function(callback: &ModuleCallback, table: &str, method: bool) -> String {
+    let rt = pxs_Runtime::pxs_Lua;
+    let mut out = String::new();
+    let mut names = vec![];
+    match callback.signature.as_ref() {
+        Some(signature) => {
+            for line in signature.doc.lines() {
+                out.push_str(&format!("--- {line}\n"));
+            }
+            for param in signature.params.iter() {
+                let optional = if param.default.is_some() { "?" } else { "" };
+                out.push_str(&format!(
+                    "---@param {}{} {}\n",
+                    param.name,
+                    optional,
+                    param.param_type.type_name(rt)
+                ));
+                names.push(param.name.clone());
+            }
+            if signature.variadic {
+                out.push_str("---@param ... any\n");
+                names.push("...".to_string());
+            }
+            if signature.returns != pxs_ParamType::pxs_ParamNull {
+                out.push_str(&format!("---@return {}\n", signature.returns.type_name(rt)));
+            }
+        }
+        None => {
+            out.push_str("---@param ... any\n---@return any\n");
+            names.push("...".to_string());
+        }
+    }
+    // `function M["end"]()` is not valid Lua, keywords are assigned.
+    if LUA_KEYWORDS.contains(&callback.name.as_str()) || table.contains('[') {
+        if method {
+            names.insert(0, "self".to_string());
+        }
+        out.push_str(&format!(
+            "{} = function({}) end\n",
+            lua_field(table, &callback.name),
+            names.join(", ")
+        ));
+    } else {
+        let sep = if method { ":" } else { "." };
+        out.push_str(&format!(
+            "function {table}{sep}{}({}) end\n",
+            callback.name,
+            names.join(", ")
+        ));
+    }
+    out
+}
+
+/// A class for a host type.
+fn lua_type(pixel_type: &pxs_PixelType, path: &str) -> String {
+    let rt = pxs_Runtime::pxs_Lua;
+    let name = &pixel_type.name;
+    let members = pixel_type.members();
+    let mut out = format!("---@class {path}.{name}\n");
+
+    for constant in members.constants.iter() {
+        out.push_str(&format!(
+            "---@field {} {}\n",
+            constant.name,
+            pxs_ParamType::of_var(&constant.var.tag).type_name(rt)
+        ));
+    }
+    for property in members.properties.iter() {
+        out.push_str(&format!("---@field {} any\n", property.name));
+    }
+    for field in members.fields.iter() {
+        out.push_str(&format!(
+            "---@field {} {}\n",
+            field.name,
+            field_param_type(field.field_type).type_name(rt)
+        ));
+    }
+    if members.constructor.is_some() {
+        out.push_str(&format!("---@overload fun(...: any): {path}.{name}\n"));
+    }
+    out.push_str(&format!("{} = {{}}\n", lua_field("M", name)));
+
+    for method in members.methods.iter() {
+        out.push_str(&format!(
+            "\n{}",
+            lua_function(method, &lua_field("M", name), true)
+        ));
+    }
+    for method in members.statics.iter() {
+        out.push_str(&format!(
+            "\n{}",
+            lua_function(method, &lua_field("M", name), false)
+        ));
+    }
+    out
+}
+
+/// A class for a value type, created with it's fields in order.
+fn lua_value_type(value_type: &pxs_ValueType, path: &str) -> String {
+    let rt = pxs_Runtime::pxs_Lua;
+    let name = &value_type.name;
+    let mut out = format!("---@class {path}.{name}\n");
+    let mut params = vec![];
+    for field in value_type.fields.iter() {
+        let type_name = field_param_type(field.field_type).type_name(rt);
+        out.push_str(&format!("---@field {} {}\n", field.name, type_name));
+        params.push(format!("{}?: {}", field.name, type_name));
+    }
+    out.push_str(&format!(
+        "---@overload fun({}): {path}.{name}\n{} = {{}}\n",
+        params.join(", "),
+        lua_field("M", name)
+    ));
+    out
+}
+
+// JavaScript
+
+/// The declarations of a module, internal modules are namespaces.
+fn ts_items(module: &pxs_Module, depth: usize) -> String {
+    let rt = pxs_Runtime::pxs_JavaScript;
+    let indent = "    ".repeat(depth);
+    let mut out = String::new();
+
+    for variable in module.variables.iter() {
+        let keyword = if variable.constant { "const" } else { "let" };
+        out.push_str(&format!(
+            "{indent}export {keyword} {}: {};\n",
+            variable.name,
+            var_param_type(variable.var).type_name(rt)
+        ));
+    }
+    for live_var in module.live_vars.iter() {
+        out.push_str(&format!("{indent}export let {}: any;\n", live_var.name));
+    }
+    for callback in callbacks_for(module, rt) {
+        out.push_str(&ts_function(
+            callback,
+            &format!("{indent}export function "),
+            &indent,
+        ));
+    }
+
+    for pixel_type in module.types.iter() {
+        let members = pixel_type.members();
+        out.push_str(&format!("{indent}export class {} {{\n", pixel_type.name));
+        for constant in members.constants.iter() {
+            out.push_str(&format!(
+                "{indent}    static readonly {}: {};\n",
+                constant.name,
+                pxs_ParamType::of_var(&constant.var.tag).type_name(rt)
+            ));
+        }
+        for property in members.properties.iter() {
+            let readonly = if property.setter.is_none() {
+                "readonly "
+            } else {
+                ""
+            };
+            out.push_str(&format!("{indent}    {readonly}{}: any;\n", property.name));
+        }
+        for field in members.fields.iter() {
+            let readonly = if field.readonly { "readonly " } else { "" };
+            out.push_str(&format!(
+                "{indent}    {readonly}{}: {};\n",
+                field.name,
+                field_param_type(field.field_type).type_name(rt)
+            ));
+        }
+        if members.constructor.is_some() {
+            out.push_str(&format!("{indent}    constructor(...args: any[]);\n"));
+        }
+        for method in members.methods.iter() {
+            out.push_str(&ts_function(
+                method,
+                &format!("{indent}    "),
+                &format!("{indent}    "),
+            ));
+        }
+        for method in members.statics.iter() {
+            out.push_str(&ts_function(
+                method,
+                &format!("{indent}    static "),
+                &format!("{indent}    "),
+            ));
+        }
+        out.push_str(&format!("{indent}}}\n"));
+    }
+
+    for value_type in module.value_types.iter() {
+        out.push_str(&format!("{indent}export class {} {{\n", value_type.name));
+        let mut params = vec![];
+        for field in value_type.fields.iter() {
+            let type_name = field_param_type(field.field_type).type_name(rt);
+            out.push_str(&format!("{indent}    {}: {};\n", field.name, type_name));
+            params.push(format!("{}?: {}", field.name, type_name));
+        }
+        out.push_str(&format!(
+            "{indent}    constructor({});\n{indent}}}\n",
+            params.join(", ")
+        ));
+    }
+
+    for module_enum in module.enums.iter() {
+        out.push_str(&format!("{indent}export enum {} {{\n", module_enum.name));
+        for member in module_enum.members.iter() {
+            out.push_str(&format!(
+                "{indent}    {} = {},\n",
+                member.name, member.value
+            ));
+        }
+        out.push_str(&format!("{indent}}}\n"));
+    }
+
+    for child in module.modules.iter() {
+        out.push_str(&format!("{indent}export namespace {} {{\n", child.name));
+        out.push_str(&ts_items(child, depth + 1));
+        out.push_str(&format!("{indent}}}\n"));
+    }
+    out
+}
+
+/// A function declaration, `prefix` goes before the name.
+fn ts_function(callback: &ModuleCallback, prefix: &str, indent: &str) -> String {
+    let rt = pxs_Runtime::pxs_JavaScript;
+    if JS_KEYWORDS.contains(&callback.name.as_str()) {
+        return format!("{indent}// '{}' is a reserved word\n", callback.name);
+    }
+    let mut out = String::new();
+    let mut params = vec![];
+    let returns = match callback.signature.as_ref() {
+        Some(signature) => {
+            if !signature.doc.is_empty() {
+                out.push_str(&format!(
+                    "{indent}/** {} */\n",
+                    signature.doc.replace("*/", "*\\/")
+                ));
+            }
+            for param in signature.params.iter() {
+                let optional = if param.default.is_some() { "?" } else { "" };
+                params.push(format!(
+                    "{}{}: {}",
+                    param.name,
+                    optional,
+                    param.param_type.type_name(rt)
+                ));
+            }
+            if signature.variadic {
+                params.push("...args: any[]".to_string());
+            }
+            signature.returns.type_name(rt)
+        }
+        None => {
+            params.push("...args: any[]".to_string());
+            "any"
+        }
+    };
+    out.push_str(&format!(
+        "{prefix}{}({}): {};\n",
+        callback.name,
+        params.join(", "),
+        returns
+    ));
+    out
+}
@@ -0,0 +1,403 @@
+// Copyright 2026 Jordan Castro <jordan@grupojvm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+//
+use std::{
+    collections::HashMap,
+    os::raw::c_void,
+    sync::{Arc, Mutex, OnceLock, RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
+
+use crate::shared::{
+    PXS_ALL_RUNTIMES,
+    func::{lookup_add_function, lookup_add_member, lookup_remove_function, pxs_Func},
+    module::ModuleCallback,
+    object::{FieldValue, ObjectField, ObjectOperator, ObjectProperty, pxs_FieldType, pxs_OperatorKind},
+    var::pxs_Var,
+};
+
+/// A PixelScript Type.
+///
+/// Holds the methods, properties and operators shared by every instance (`pxs_PixelObject`) of a host class.
+/// Callbacks are registered in the function lookup once per type, not per instance.
+///
+/// example:
+/// ```c
+/// pxs_PixelType* person = pxs_newtype("Person");
+/// pxs_type_setctor(person, new_person, NULL);
+/// pxs_type_addmethod(person, "set_name", person_set_name, NULL);
+/// pxs_type_addprop(person, "name", person_get_name, person_set_name, NULL);
+/// pxs_type_addstatic(person, "from_json", person_from_json, NULL);
+/// pxs_type_addconst(person, "MAX_AGE", pxs_newint(150));
+/// pxs_addtype(module, person);
+///
+/// // Within the constructor callback
+/// return pxs_newinstance(person, p, free_person);
+/// ```
+///
+/// Types live until `pxs_finalize` or `pxs_clearstate`. Members can be added or replaced at any time,
+/// runtimes rebuild their class/metatable the next time the type is used.
+#[allow(non_camel_case_types)]
+pub struct pxs_PixelType {
+    /// Name of the type.
+    pub name: String,
+    /// The members.
+    members: RwLock<TypeMembers>,
+}
+
+/// The members of a PixelType.
+#[derive(Clone, Default)]
+pub struct TypeMembers {
+    /// Methods with names.
+    ///
+    /// The first Var will always be the ptr.
+    pub methods: Vec<ModuleCallback>,
+    /// Static methods with names. Called like module functions, without a object.
+    pub statics: Vec<ModuleCallback>,
+    /// Class constants with names.
+    pub constants: Vec<TypeConstant>,
+    /// The constructor, called when the type itself is called from a script.
+    pub constructor: Option<ModuleCallback>,
+    /// Properties with names. They are all getters/setters.
+    pub properties: Vec<ObjectProperty>,
+    /// Fields with names. Read and written directly in the host struct.
+    pub fields: Vec<ObjectField>,
+    /// Operator overloads.
+    pub operators: Vec<ObjectOperator>,
+    /// Bumped on every change, runtimes compare it against the one their class/metatable was built from.
+    pub generation: u64,
+}
+
+/// A constant value on a type.
+#[derive(Clone)]
+pub struct TypeConstant {
+    pub name: String,
+    pub var: pxs_Var,
+}
+
+impl TypeMembers {
+    /// Get the operator overload of a kind.
+    pub fn get_operator(&self, kind: pxs_OperatorKind) -> Option<&ObjectOperator> {
+        self.operators.iter().find(|op| op.kind == kind)
+    }
+}
+
+impl pxs_PixelType {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            members: RwLock::new(TypeMembers::default()),
+        }
+    }
+
+    /// Read the members.
+    pub fn members(&self) -> RwLockReadGuard<'_, TypeMembers> {
+        self.members.read().unwrap()
+    }
+
+    /// Write the members, bumping the generation.
+    fn members_mut(&self) -> RwLockWriteGuard<'_, TypeMembers> {
+        let mut members = self.members.write().unwrap();
+        members.generation += 1;
+        members
+    }
+
+    /// Register a callback in the function lookup.
+    ///
+    /// Full names are `_{type}.{name}`, with a `:` suffix for the other member kinds. `.` and `:` can not be
+    /// in a identifier, so members of different types never share a full name.
+    ///
+    /// Callbacks `on_object` are only called with a instance of this type as `args[1]`.
+    fn register(
+        &self,
+        name: &str,
+        full_name: String,
+        callback: pxs_Func,
+        opaque: *mut c_void,
+        on_object: bool,
+    ) -> ModuleCallback {
+        let idx = if on_object {
+            lookup_add_member(full_name.as_str(), callback, opaque, &self.name)
+        } else {
+            lookup_add_function(full_name.as_str(), callback, opaque)
+        };
+        ModuleCallback {
+            name: name.to_string(),
+            full_name,
+            idx,
+            runtimes: PXS_ALL_RUNTIMES,
+            signature: None,
+        }
+    }
+
+    /// Does the type have a method?
+    pub fn has_method(&self, name: &str) -> bool {
+        self.members().methods.iter().any(|m| m.name == name)
+    }
+
+    /// Add a method. Replaces any existing method of the same name.
+    pub fn add_method(&self, name: &str, callback: pxs_Func, opaque: *mut c_void) {
+        let method = self.register(name, format!("_{}.{}", self.name, name), callback, opaque, true);
+
+        let mut members = self.members_mut();
+        release_where(&mut members.methods, |m| m.name == name);
+        members.methods.push(method);
+    }
+
+    /// Add a static method. Replaces any existing static method of the same name.
+    pub fn add_static(&self, name: &str, callback: pxs_Func, opaque: *mut c_void) {
+        let method = self.register(name, format!("_{}.{}:static", self.name, name), callback, opaque, false);
+
+        let mut members = self.members_mut();
+        release_where(&mut members.statics, |m| m.name == name);
+        members.statics.push(method);
+    }
+
+    /// Add a class constant. Replaces any existing constant of the same name.
+    pub fn add_constant(&self, name: &str, var: pxs_Var) {
+        let mut members = self.members_mut();
+        members.constants.retain(|c| c.name != name);
+        members.constants.push(TypeConstant {
+            name: name.to_string(),
+            var,
+        });
+    }
+
+    /// Set the constructor.
+    pub fn set_constructor(&self, callback: pxs_Func, opaque: *mut c_void) {
+        let constructor = self.register(&self.name, format!("_{}:new", self.name), callback, opaque, false);
+
+        let mut members = self.members_mut();
+        if let Some(old) = members.constructor.replace(constructor) {
+            lookup_remove_function(old.idx);
+        }
+    }
+
+    /// Add a property. A None setter makes it read-only.
+    pub fn add_property(
+        &self,
+        name: &str,
+        getter: pxs_Func,
+        setter: Option<pxs_Func>,
+        opaque: *mut c_void,
+    ) {
+        let getter = self.register(name, format!("_{}.{}:get", self.name, name), getter, opaque, true);
+        let setter = setter
+            .map(|setter| self.register(name, format!("_{}.{}:set", self.name, name), setter, opaque, true));
+
+        let mut members = self.members_mut();
+        members.properties.retain(|p| {
+            if p.name != name {
+                return true;
+            }
+            lookup_remove_function(p.getter.idx);
+            if let Some(setter) = &p.setter {
+                lookup_remove_function(setter.idx);
+            }
+            false
+        });
+        members.properties.push(ObjectProperty {
+            name: name.to_string(),
+            getter,
+            setter,
+        });
+    }
+
+    /// Add a field. Replaces any existing field of the same name.
+    pub fn add_field(&self, name: &str, offset: usize, field_type: pxs_FieldType, readonly: bool) {
+        let mut members = self.members_mut();
+        members.fields.retain(|f| f.name != name);
+        members.fields.push(ObjectField {
+            name: name.to_string(),
+            offset,
+            field_type,
+            readonly,
+        });
+    }
+
+    /// Add a operator overload. Replaces any existing overload of the same kind.
+    pub fn add_operator(&self, kind: pxs_OperatorKind, callback: pxs_Func, opaque: *mut c_void) {
+        let callback = self.register(
+            kind.name(),
+            format!("_{}:{}", self.name, kind.name()),
+            callback,
+            opaque,
+            true,
+        );
+
+        let mut members = self.members_mut();
+        members.operators.retain(|op| {
+            if op.kind != kind {
+                return true;
+            }
+            lookup_remove_function(op.callback.idx);
+            false
+        });
+        members.operators.push(ObjectOperator { kind, callback });
+    }
+}
+
+/// Remove the callbacks matching `replaced`, releasing their function idxs.
+fn release_where(callbacks: &mut Vec<ModuleCallback>, replaced: impl Fn(&ModuleCallback) -> bool) {
+    callbacks.retain(|callback| {
+        if !replaced(callback) {
+            return true;
+        }
+        lookup_remove_function(callback.idx);
+        false
+    });
+}
+
+unsafe impl Send for pxs_PixelType {}
+unsafe impl Sync for pxs_PixelType {}
+
+/// A PixelScript Value Type.
+///
+/// A small struct (Vec2, Color, Rect) copied into the scripts instead of living in the host as a object.
+/// Lua gets a plain table, Python a instance of a lightweight class. Scripts can create values locally with
+/// `Vec2(1, 2)`, the host is only involved when a value crosses back, where it is validated and packed.
+///
+/// example:
+/// ```c
+/// const char* names[] = {"x", "y"};
+/// pxs_FieldType types[] = {pxs_FieldF64, pxs_FieldF64};
+/// pxs_ValueType* vec2 = pxs_newvaluetype("Vec2", names, types, 2);
+/// pxs_addvaluetype(module, vec2);
+/// ```
+#[allow(non_camel_case_types)]
+pub struct pxs_ValueType {
+    /// Name of the type.
+    pub name: String,
+    /// The fields, in constructor order.
+    pub fields: Vec<ValueField>,
+}
+
+/// A field of a value type.
+#[derive(Debug, Clone)]
+pub struct ValueField {
+    pub name: String,
+    pub field_type: pxs_FieldType,
+}
+
+impl pxs_ValueType {
+    /// Get the index of a field.
+    pub fn field_index(&self, name: &str) -> Option<usize> {
+        self.fields.iter().position(|f| f.name == name)
+    }
+
+    /// Validate and pack values, in field order. Missing values are zero.
+    pub fn pack(self: &Arc<Self>, values: Vec<Option<FieldValue>>) -> Result<pxs_PixelValue, String> {
+        if values.len() > self.fields.len() {
+            return Err(format!(
+                "{} takes at most {} fields, got {}",
+                self.name,
+                self.fields.len(),
+                values.len()
+            ));
+        }
+        let mut packed = Vec::with_capacity(self.fields.len());
+        for (i, field) in self.fields.iter().enumerate() {
+            let value = match values.get(i).copied().flatten() {
+                Some(value) => field
+                    .field_type
+                    .convert(value)
+                    .map_err(|e| format!("{}.{} {}", self.name, field.name, e))?,
+                None => field.field_type.zero(),
+            };
+            packed.push(value);
+        }
+        Ok(pxs_PixelValue {
+            value_type: Arc::clone(self),
+            fields: packed,
+        })
+    }
+}
+
+/// A instance of a value type. Held by a `pxs_Value` var.
+#[derive(Clone)]
+#[allow(non_camel_case_types)]
+pub struct pxs_PixelValue {
+    pub value_type: Arc<pxs_ValueType>,
+    /// Packed fields, in field order.
+    pub fields: Vec<FieldValue>,
+}
+
+impl pxs_PixelValue {
+    /// Get a field by name.
+    pub fn get(&self, name: &str) -> Option<FieldValue> {
+        self.value_type.field_index(name).map(|i| self.fields[i])
+    }
+
+    /// Set a field by name. The value is converted to the field type.
+    pub fn set(&mut self, name: &str, value: FieldValue) -> Result<(), String> {
+        let Some(i) = self.value_type.field_index(name) else {
+            return Err(format!("{} has no field '{}'", self.value_type.name, name));
+        };
+        self.fields[i] = self.value_type.fields[i]
+            .field_type
+            .convert(value)
+            .map_err(|e| format!("{}.{} {}", self.value_type.name, name, e))?;
+        Ok(())
+    }
+}
+
+/// Lookup state structure
+pub struct TypeLookup {
+    /// Types by name, shared between all runtimes.
+    pub types: HashMap<String, Arc<pxs_PixelType>>,
+    /// Value types by name, shared between all runtimes.
+    pub value_types: HashMap<String, Arc<pxs_ValueType>>,
+}
+
+/// The type lookup!
+static TYPE_LOOKUP: OnceLock<Mutex<TypeLookup>> = OnceLock::new();
+
+/// Get the Type lookup global state. Shared between all runtimes.
+fn get_type_lookup() -> std::sync::MutexGuard<'static, TypeLookup> {
+    TYPE_LOOKUP
+        .get_or_init(|| {
+            Mutex::new(TypeLookup {
+                types: HashMap::new(),
+                value_types: HashMap::new(),
+            })
+        })
+        .lock()
+        .unwrap()
+}
+
+pub(crate) fn clear_type_lookup() {
+    let mut lookup = get_type_lookup();
+    lookup.types.clear();
+    lookup.value_types.clear();
+}
+
+/// Get a type by name, creating it if needed.
+pub(crate) fn get_or_create_type(name: &str) -> Arc<pxs_PixelType> {
+    let mut lookup = get_type_lookup();
+    Arc::clone(
+        lookup
+            .types
+            .entry(name.to_string())
+            .or_insert_with(|| Arc::new(pxs_PixelType::new(name))),
+    )
+}
+
+/// Register a value type. Replaces any value type of the same name.
+pub(crate) fn add_value_type(value_type: pxs_ValueType) -> Arc<pxs_ValueType> {
+    let mut lookup = get_type_lookup();
+    let value_type = Arc::new(value_type);
+    lookup
+        .value_types
+        .insert(value_type.name.clone(), Arc::clone(&value_type));
+    value_type
+}
+
+/// Get a value type by name.
+pub(crate) fn get_value_type(name: &str) -> Option<Arc<pxs_ValueType>> {
+    let lookup = get_type_lookup();
+    lookup.value_types.get(name).cloned()
+}
@@ -14,7 +14,11 @@ use anyhow::{Error, anyhow};
 
 use crate::{
     borrow_string, create_raw_string,
-    shared::{PtrMagic, object::get_object},
+    shared::{
+        PtrMagic,
+        object::{FieldValue, get_object, object_decref, object_incref, pxs_FieldType},
+        types::pxs_PixelValue,
+    },
 };
 
 /// Macro for writing out the Var:: get methods.
@@ -90,11 +94,22 @@ pub enum pxs_VarType {
     pxs_Object,
     /// Host object converted when created.
     /// Lua (Tree), Python (object), JS/easyjs (Prototype think '{}')
+    ///
+    /// Each var holds a reference. The object is freed once all vars and script references are gone.
     pxs_HostObject,
     /// Lua (Tree), Python (list), JS/easyjs (Array)
     pxs_List,
     /// Lua (Value), Python (def or lambda), JS/easyjs (anon function)
     pxs_Function,
+    /// A error message. When returned from a callback it is raised in the script.
+    /// Lua (error), Python (RuntimeError), JS/easyjs (Error)
+    pxs_Exception,
+    /// A typed view over a host buffer, nothing is copied.
+    /// Lua (userdata, 1 based), Python (`_pxs_ArrayView`, 0 based)
+    pxs_ArrayView,
+    /// A instance of a value type, copied in and out of the scripts.
+    /// Lua (plain table), Python (instance of the value class)
+    pxs_Value,
 }
 
 /// Holds data for a pxs_Var of list.
@@ -189,6 +204,63 @@ impl pxs_VarList {
     }
 }
 
+/// Holds data for a pxs_Var of array view.
+///
+/// Scripts index, write, `len()`/`#` and iterate it straight over the host buffer. Indexes are bounds checked.
+///
+/// The view does not own the buffer. The host must keep it alive, at the same address, while scripts can reach it.
+///
+/// ```c
+/// float positions[1024];
+/// pxs_Var* view = pxs_newarrayview(pxs_FieldF32, positions, 1024, false);
+/// ```
+#[derive(Debug, Clone, Copy)]
+#[allow(non_camel_case_types)]
+pub struct pxs_VarArrayView {
+    /// Type of each element.
+    pub elem_type: pxs_FieldType,
+    /// The host buffer.
+    pub ptr: *mut c_void,
+    /// Number of elements.
+    pub len: usize,
+    /// Scripts can not write to it.
+    pub readonly: bool,
+}
+
+impl PtrMagic for pxs_VarArrayView {}
+unsafe impl Send for pxs_VarArrayView {}
+unsafe impl Sync for pxs_VarArrayView {}
+
+impl pxs_VarArrayView {
+    /// Read a element. None if out of bounds.
+    pub fn get(&self, index: usize) -> Option<FieldValue> {
+        if index >= self.len {
+            return None;
+        }
+        unsafe {
+            Some(
+                self.elem_type
+                    .read((self.ptr as *const u8).add(index * self.elem_type.size())),
+            )
+        }
+    }
+
+    /// Write a element.
+    pub fn set(&self, index: usize, value: FieldValue) -> Result<(), String> {
+        if self.readonly {
+            return Err("array view is read-only".to_string());
+        }
+        if index >= self.len {
+            return Err(format!("index {} out of range for a array view of length {}", index, self.len));
+        }
+        unsafe {
+            self.elem_type
+                .write((self.ptr as *mut u8).add(index * self.elem_type.size()), value)
+                .map_err(|e| format!("array view {}", e))
+        }
+    }
+}
+
 /// The Variables actual value union.
 #[repr(C)]
 #[allow(non_camel_case_types)]
@@ -200,9 +272,11 @@ pub union pxs_VarValue {
     pub f64_val: f64,
     pub null_val: *const c_void,
     pub object_val: *mut c_void,
-    pub host_object_val: i32,
+    pub host_object_val: i64,
     pub list_val: *mut pxs_VarList,
     pub function_val: *mut c_void,
+    pub array_view_val: *mut pxs_VarArrayView,
+    pub value_val: *mut pxs_PixelValue,
 }
 
 type DeleterFn = Box<dyn Fn(*mut c_void) + Send + Sync>;
@@ -251,10 +325,12 @@ impl pxs_Var {
         unsafe { std::slice::from_raw_parts(argv, argc) }
     }
 
+    /// Get the host pointer. Null if the object was destroyed.
     pub fn get_host_ptr(&self) -> *mut c_void {
-        // TODO: type checks
-        let object = get_object(self.get_object_ptr()).unwrap();
-        object.ptr
+        match get_object(self.get_object_ptr()) {
+            Some(object) => object.ptr,
+            None => ptr::null_mut(),
+        }
     }
 
     /// Get the Rust string from the Var.
@@ -308,7 +384,10 @@ impl pxs_Var {
     }
 
     /// Create a new HostObject var.
-    pub fn new_host_object(ptr: i32) -> Self {
+    ///
+    /// The var holds a reference to the object until it is dropped.
+    pub fn new_host_object(ptr: i64) -> Self {
+        object_incref(ptr);
         pxs_Var {
             tag: pxs_VarType::pxs_HostObject,
             value: pxs_VarValue {
@@ -351,6 +430,107 @@ impl pxs_Var {
         }
     }
 
+    /// Create a new Exception var.
+    ///
+    /// Return this from a callback to raise `msg` as a error in the calling script.
+    ///
+    /// NUL characters are removed, they can not be passed to C.
+    pub fn new_exception(msg: String) -> Self {
+        let cstr = CString::new(msg.replace('\0', "")).unwrap_or_default();
+
+        pxs_Var {
+            tag: pxs_VarType::pxs_Exception,
+            value: pxs_VarValue {
+                string_val: cstr.into_raw(),
+            },
+            deleter: Cell::new(None)
+        }
+    }
+
+    /// Get the message of a Exception var.
+    pub fn get_exception(&self) -> Result<String, Error> {
+        if self.tag == pxs_VarType::pxs_Exception {
+            unsafe {
+                if self.value.string_val.is_null() {
+                    return Err(anyhow!("Exception pointer is null"));
+                }
+
+                Ok(borrow_string!(self.value.string_val).to_string())
+            }
+        } else {
+            Err(anyhow!("Var is not a exception."))
+        }
+    }
+
+    /// Create a new ArrayView var.
+    pub fn new_array_view(view: pxs_VarArrayView) -> Self {
+        pxs_Var {
+            tag: pxs_VarType::pxs_ArrayView,
+            value: pxs_VarValue {
+                array_view_val: view.into_raw(),
+            },
+            deleter: Cell::new(None)
+        }
+    }
+
+    /// Get the pxs_VarArrayView of a ArrayView var.
+    pub fn get_array_view(&self) -> Option<&pxs_VarArrayView> {
+        if !self.is_array_view() {
+            None
+        } else {
+            unsafe { Some(pxs_VarArrayView::from_borrow(self.value.array_view_val)) }
+        }
+    }
+
+    /// Create a Int64, Float64 or Bool var from a field value.
+    pub fn new_field(value: FieldValue) -> Self {
+        match value {
+            FieldValue::Int(v) => pxs_Var::new_i64(v),
+            FieldValue::Float(v) => pxs_Var::new_f64(v),
+            FieldValue::Bool(v) => pxs_Var::new_bool(v),
+        }
+    }
+
+    /// Get a field value from a number or bool var.
+    pub fn get_field(&self) -> Option<FieldValue> {
+        match self.tag {
+            pxs_VarType::pxs_Int64 => self.get_i64().ok().map(FieldValue::Int),
+            pxs_VarType::pxs_UInt64 => self.get_u64().ok().map(|v| FieldValue::Int(v as i64)),
+            pxs_VarType::pxs_Float64 => self.get_f64().ok().map(FieldValue::Float),
+            pxs_VarType::pxs_Bool => self.get_bool().ok().map(FieldValue::Bool),
+            _ => None,
+        }
+    }
+
+    /// Create a new Value var.
+    pub fn new_value(value: pxs_PixelValue) -> Self {
+        pxs_Var {
+            tag: pxs_VarType::pxs_Value,
+            value: pxs_VarValue {
+                value_val: Box::into_raw(Box::new(value)),
+            },
+            deleter: Cell::new(None)
+        }
+    }
+
+    /// Get the pxs_PixelValue of a Value var.
+    pub fn get_value(&self) -> Option<&pxs_PixelValue> {
+        if !self.is_value() {
+            None
+        } else {
+            unsafe { Some(&*self.value.value_val) }
+        }
+    }
+
+    /// Get the pxs_PixelValue of a Value var, to set it's fields.
+    pub fn get_value_mut(&mut self) -> Option<&mut pxs_PixelValue> {
+        if !self.is_value() {
+            None
+        } else {
+            unsafe { Some(&mut *self.value.value_val) }
+        }
+    }
+
     /// Create a new Function var.
     pub fn new_function(ptr: *mut c_void, deleter: Option<DeleterFn>) -> Self {
         pxs_Var {
@@ -400,11 +580,11 @@ impl pxs_Var {
         }
     }
 
-    /// Get the ptr of the object if Host, i32, i64, u32, u64
-    pub fn get_object_ptr(&self) -> i32 {
+    /// Get the handle of the object if Host, i64, u64
+    pub fn get_object_ptr(&self) -> i64 {
         match self.tag {
-            pxs_VarType::pxs_Int64 => self.get_i64().unwrap() as i32,
-            pxs_VarType::pxs_UInt64 => self.get_u64().unwrap() as i32,
+            pxs_VarType::pxs_Int64 => self.get_i64().unwrap(),
+            pxs_VarType::pxs_UInt64 => self.get_u64().unwrap() as i64,
             pxs_VarType::pxs_HostObject => unsafe { self.value.host_object_val },
             _ => -1,
         }
@@ -446,8 +626,10 @@ impl pxs_Var {
                 pxs_VarType::pxs_Object => "Object".to_string(),
                 pxs_VarType::pxs_HostObject => {
                     let idx = self.get_object_ptr();
-                    let object = get_object(idx).unwrap();
-                    object.type_name.to_string()
+                    match get_object(idx) {
+                        Some(object) => object.type_name.to_string(),
+                        None => "Destroyed object".to_string(),
+                    }
                 },
                 pxs_VarType::pxs_List => {
                     let list = self.get_list().unwrap();
@@ -455,6 +637,15 @@ impl pxs_Var {
                     t
                 },
                 pxs_VarType::pxs_Function => "Function".to_string(),
+                pxs_VarType::pxs_Exception => borrow_string!(self.value.string_val).to_string(),
+                pxs_VarType::pxs_ArrayView => {
+                    let view = self.get_array_view().unwrap();
+                    format!("ArrayView({:?}, {})", view.elem_type, view.len)
+                },
+                pxs_VarType::pxs_Value => {
+                    let value = self.get_value().unwrap();
+                    format!("{}{:?}", value.value_type.name, value.fields)
+                },
             }
         }
     }
@@ -490,13 +681,16 @@ impl pxs_Var {
         is_object, pxs_VarType::pxs_Object;
         is_host_object, pxs_VarType::pxs_HostObject;
         is_list, pxs_VarType::pxs_List;
-        is_function, pxs_VarType::pxs_Function
+        is_function, pxs_VarType::pxs_Function;
+        is_exception, pxs_VarType::pxs_Exception;
+        is_array_view, pxs_VarType::pxs_ArrayView;
+        is_value, pxs_VarType::pxs_Value
     }
 }
 
 impl Drop for pxs_Var {
     fn drop(&mut self) {
-        if self.tag == pxs_VarType::pxs_String {
+        if self.tag == pxs_VarType::pxs_String || self.tag == pxs_VarType::pxs_Exception {
             unsafe {
                 // Free the mem
                 if !self.value.string_val.is_null() {
@@ -509,6 +703,13 @@ impl Drop for pxs_Var {
                 // This will automatically drop
                 pxs_VarList::from_raw(self.value.list_val)
             };
+        } else if self.tag == pxs_VarType::pxs_ArrayView {
+            // Only the view, the buffer belongs to the host.
+            let _ = unsafe { pxs_VarArrayView::from_raw(self.value.array_view_val) };
+        } else if self.tag == pxs_VarType::pxs_Value {
+            let _ = unsafe { Box::from_raw(self.value.value_val) };
+        } else if self.tag == pxs_VarType::pxs_HostObject {
+            object_decref(unsafe { self.value.host_object_val });
         } else if self.tag == pxs_VarType::pxs_Object {
             if let Some(deleter) = &self.deleter.get_mut() {
                 deleter(unsafe { self.value.object_val });
@@ -580,6 +781,13 @@ impl Clone for pxs_Var {
                         deleter: Cell::new(self.deleter.take())
                     }
                 },
+                pxs_VarType::pxs_Exception => {
+                    pxs_Var::new_exception(borrow_string!(self.value.string_val).to_string())
+                },
+                pxs_VarType::pxs_ArrayView => {
+                    pxs_Var::new_array_view(*pxs_VarArrayView::from_borrow(self.value.array_view_val))
+                },
+                pxs_VarType::pxs_Value => pxs_Var::new_value((*self.value.value_val).clone()),
             }
         }
     }
@@ -608,6 +816,9 @@ pub trait ObjectMethods {
 
     /// Setter
     fn set(var: &pxs_Var, key: &str, value: &pxs_Var) -> Result<pxs_Var, Error>;
+
+    /// Call a class by name and return the instance as a object the host keeps alive.
+    fn instantiate(class_name: &str, args: &mut pxs_VarList) -> Result<pxs_Var, Error>;
 }
 
 /// Type Helper for a pxs_Var
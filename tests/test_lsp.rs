@@ -0,0 +1,294 @@
+// Copyright 2026 Jordan Castro <jordan@grupojvm.com>
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except in compliance with the License. You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied. See the License for the specific language governing permissions and limitations under the License.
+//
+// cargo test --test test_lsp --no-default-features --features "lua" -- --nocapture
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        ffi::{CStr, CString, c_char, c_void},
+        io::{BufRead, BufReader, Read, Write},
+        process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+        ptr,
+    };
+
+    use pixelscript::{
+        shared::{PXS_ALL_RUNTIMES, PtrMagic, json::Json, pxs_Runtime, signature::pxs_ParamType, var::pxs_Var},
+        *,
+    };
+
+    /// Create a raw string from &str.
+    ///
+    /// Remember to FREE THIS!
+    macro_rules! create_raw_string {
+        ($rstr:expr) => {{ CString::new($rstr).unwrap().into_raw() }};
+    }
+
+    /// Free a raw sring
+    macro_rules! free_raw_string {
+        ($rptr:expr) => {{
+            if !$rptr.is_null() {
+                unsafe {
+                    let _ = std::ffi::CString::from_raw($rptr);
+                }
+            }
+        }};
+    }
+
+    unsafe extern "C" fn file_writer(file_path: *const c_char, contents: *const c_char) {
+        let file_path = unsafe { CStr::from_ptr(file_path) }.to_str().unwrap();
+        let contents = unsafe { CStr::from_ptr(contents) }.to_str().unwrap();
+        std::fs::write(file_path, contents).unwrap();
+    }
+
+    pub extern "C" fn noop(_args: *mut pxs_Var, _opaque: *mut c_void) -> *mut pxs_Var {
+        pxs_Var::new_null().into_raw()
+    }
+
+    /// A `game` module with a `game.math` module, and write it's description.
+    fn write_api(api_path: &str) {
+        pxs_initialize();
+        pxs_set_filewriter(file_writer);
+
+        let game_name = create_raw_string!("game");
+        let game = pxs_newmod(game_name);
+        let doc = create_raw_string!("Spawn a entity.");
+        let sig = pxs_newsig(doc);
+        let kind_name = create_raw_string!("kind");
+        let count_name = create_raw_string!("count");
+        pxs_sigaddparam(sig, kind_name, pxs_ParamType::pxs_ParamString, ptr::null_mut());
+        pxs_sigaddparam(sig, count_name, pxs_ParamType::pxs_ParamInt, pxs_newint(1));
+        pxs_sigsetreturn(sig, pxs_ParamType::pxs_ParamBool);
+        let spawn_name = create_raw_string!("spawn");
+        pxs_addfunc_ex(game, spawn_name, noop, ptr::null_mut(), sig);
+        let py_name = create_raw_string!("py_only");
+        pxs_addfunc_for(game, py_name, noop, ptr::null_mut(), pxs_Runtime::pxs_Python.mask());
+        let max_name = create_raw_string!("MAX_PLAYERS");
+        pxs_addconst(game, max_name, pxs_newint(4));
+
+        let math_name = create_raw_string!("math");
+        let math = pxs_newmod(math_name);
+        let clamp_name = create_raw_string!("clamp");
+        pxs_addfunc_for(math, clamp_name, noop, ptr::null_mut(), PXS_ALL_RUNTIMES);
+        let unit_name = create_raw_string!("unit");
+        pxs_addvar(math, unit_name, pxs_newstring(unit_name));
+        pxs_add_submod(game, math);
+        pxs_addmod(game);
+
+        let api_path_raw = create_raw_string!(api_path);
+        assert_eq!(pxs_genapi(api_path_raw), 1, "API, Expected one module");
+        for name in [game_name, doc, kind_name, count_name, spawn_name, py_name, max_name, math_name, clamp_name, unit_name, api_path_raw] {
+            free_raw_string!(name);
+        }
+        pxs_finalize();
+    }
+
+    /// A running `pxs-lsp`.
+    struct Client {
+        child: Child,
+        stdin: ChildStdin,
+        stdout: BufReader<ChildStdout>,
+        next_id: i64,
+        /// Notifications read while waiting for responses.
+        notifications: Vec<Json>,
+    }
+
+    impl Client {
+        fn send(&mut self, message: Json) {
+            let body = message.to_string();
+            write!(self.stdin, "Content-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+            self.stdin.flush().unwrap();
+        }
+
+        fn read(&mut self) -> Json {
+            let mut length = 0;
+            loop {
+                let mut header = String::new();
+                self.stdout.read_line(&mut header).unwrap();
+                let header = header.trim();
+                if header.is_empty() {
+                    break;
+                }
+                if let Some(value) = header.strip_prefix("Content-Length:") {
+                    length = value.trim().parse().unwrap();
+                }
+            }
+            let mut body = vec![0; length];
+            self.stdout.read_exact(&mut body).unwrap();
+            Json::parse(&String::from_utf8(body).unwrap()).unwrap()
+        }
+
+        fn notify(&mut self, method: &str, params: Json) {
+            self.send(Json::object(vec![("jsonrpc", Json::string("2.0")), ("method", Json::string(method)), ("params", params)]));
+        }
+
+        /// Send a request and wait for it's result.
+        fn request(&mut self, method: &str, params: Json) -> Json {
+            self.next_id += 1;
+            let id = Json::Number(self.next_id as f64);
+            self.send(Json::object(vec![
+                ("jsonrpc", Json::string("2.0")),
+                ("id", id.clone()),
+                ("method", Json::string(method)),
+                ("params", params),
+            ]));
+            loop {
+                let message = self.read();
+                if message.get("id") == Some(&id) {
+                    return message.get("result").cloned().unwrap_or(Json::Null);
+                }
+                self.notifications.push(message);
+            }
+        }
+
+        /// Wait for the diagnostics of a document.
+        fn diagnostics(&mut self, uri: &str) -> Vec<Json> {
+            loop {
+                let message = match self.notifications.pop() {
+                    Some(message) => message,
+                    None => self.read(),
+                };
+                if message.get("method").and_then(|m| m.as_str()) == Some("textDocument/publishDiagnostics")
+                    && message.get("params").and_then(|p| p.get("uri")).and_then(|u| u.as_str()) == Some(uri)
+                {
+                    return message.get("params").unwrap().get("diagnostics").unwrap().items().to_vec();
+                }
+            }
+        }
+
+        fn open(&mut self, uri: &str, language_id: &str, text: &str) -> Vec<Json> {
+            let document = Json::object(vec![
+                ("uri", Json::string(uri)),
+                ("languageId", Json::string(language_id)),
+                ("version", Json::Number(1.0)),
+                ("text", Json::string(text)),
+            ]);
+            self.notify("textDocument/didOpen", Json::object(vec![("textDocument", document)]));
+            self.diagnostics(uri)
+        }
+    }
+
+    fn position(uri: &str, line: usize, character: usize) -> Json {
+        Json::object(vec![
+            ("textDocument", Json::object(vec![("uri", Json::string(uri))])),
+            (
+                "position",
+                Json::object(vec![("line", Json::Number(line as f64)), ("character", Json::Number(character as f64))]),
+            ),
+        ])
+    }
+
+    /// The labels of completion items.
+    fn labels(items: &Json) -> Vec<String> {
+        items.items().iter().map(|i| i.get("label").unwrap().as_str().unwrap().to_string()).collect()
+    }
+
+    /// The messages of diagnostics.
+    fn messages(diagnostics: &[Json]) -> Vec<String> {
+        diagnostics.iter().map(|d| d.get("message").unwrap().as_str().unwrap().to_string()).collect()
+    }
+
+    #[test]
+    fn test_execute() {
+        let api_path = format!("{}/api.json", env!("CARGO_TARGET_TMPDIR"));
+        write_api(&api_path);
+
+        let mut child = Command::new(env!("CARGO_BIN_EXE_pxs-lsp"))
+            .arg(&api_path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("LSP, Could not start pxs-lsp");
+        let mut client = Client {
+            stdin: child.stdin.take().unwrap(),
+            stdout: BufReader::new(child.stdout.take().unwrap()),
+            child,
+            next_id: 0,
+            notifications: vec![],
+        };
+
+        let result = client.request("initialize", Json::object(vec![("capabilities", Json::object(vec![]))]));
+        let capabilities = result.get("capabilities").expect("LSP, Expected capabilities");
+        assert_eq!(capabilities.get("hoverProvider"), Some(&Json::Bool(true)), "LSP, Expected hover");
+        client.notify("initialized", Json::object(vec![]));
+
+        // Lua
+        let lua_uri = "file:///mods/main.lua";
+        let lua = "local game = require(\"game\")\n\
+                   local ok = game.spawn(\"orc\", 2)\n\
+                   game.despawn(\"orc\") -- game.nothing\n\
+                   print(game.math.clamp, game.py_only)\n\
+                   game.";
+        let diagnostics = client.open(lua_uri, "lua", lua);
+        assert_eq!(
+            messages(&diagnostics),
+            ["Unknown symbol 'despawn' in module 'game'", "'py_only' of module 'game' is not added to lua"],
+            "LSP, wrong Lua diagnostics"
+        );
+        let range = diagnostics[0].get("range").unwrap().get("start").unwrap();
+        assert_eq!(range.get("line").and_then(|l| l.as_i64()), Some(2), "LSP, wrong diagnostic line");
+        assert_eq!(range.get("character").and_then(|c| c.as_i64()), Some(5), "LSP, wrong diagnostic character");
+
+        let items = client.request("textDocument/completion", position(lua_uri, 4, 5));
+        let mut items = labels(&items);
+        items.sort();
+        assert_eq!(items, ["MAX_PLAYERS", "math", "spawn"], "LSP, wrong Lua completion");
+        let items = client.request("textDocument/completion", position(lua_uri, 3, 16));
+        assert_eq!(labels(&items), ["unit", "clamp"], "LSP, wrong game.math completion");
+
+        let hover = client.request("textDocument/hover", position(lua_uri, 1, 17));
+        let hover = hover.get("contents").and_then(|c| c.get("value")).and_then(|v| v.as_str()).expect("LSP, Expected hover");
+        assert_eq!(
+            hover,
+            "```lua\nspawn(kind: string, count: integer = 1) -> boolean\n```\n\nSpawn a entity.",
+            "LSP, wrong hover"
+        );
+
+        let help = client.request("textDocument/signatureHelp", position(lua_uri, 1, 29));
+        assert_eq!(help.get("activeParameter").and_then(|a| a.as_i64()), Some(1), "LSP, wrong active parameter");
+        let signature = &help.get("signatures").unwrap().items()[0];
+        let label = signature.get("label").unwrap().as_str().unwrap();
+        assert_eq!(label, "spawn(kind: string, count: integer = 1) -> boolean", "LSP, wrong signature");
+        let count_label = signature.get("parameters").unwrap().items()[1].get("label").unwrap().items();
+        let (start, end) = (count_label[0].as_i64().unwrap() as usize, count_label[1].as_i64().unwrap() as usize);
+        assert_eq!(&label[start..end], "count: integer = 1", "LSP, wrong parameter label");
+
+        // Python
+        let py_uri = "file:///mods/main.py";
+        let python = "import game\n\
+                      from game import math as gmath, missing\n\
+                      game.spawn('orc')\n\
+                      gmath.clamp(1)\n\
+                      gmath.round(1)\n\
+                      game.py_only()\n";
+        let diagnostics = client.open(py_uri, "python", python);
+        assert_eq!(
+            messages(&diagnostics),
+            ["Unknown symbol 'missing' in module 'game'", "Unknown symbol 'round' in module 'game.math'"],
+            "LSP, wrong Python diagnostics"
+        );
+        let hover = client.request("textDocument/hover", position(py_uri, 0, 9));
+        let hover = hover.get("contents").and_then(|c| c.get("value")).and_then(|v| v.as_str()).expect("LSP, Expected hover");
+        assert_eq!(hover, "```python\nmodule game\n```", "LSP, wrong module hover");
+        let hover = client.request("textDocument/hover", position(py_uri, 1, 26));
+        assert!(hover.get("contents").is_some(), "LSP, Expected hover of a imported module");
+        let items = client.request("textDocument/completion", position(py_uri, 1, 17));
+        let mut items = labels(&items);
+        items.sort();
+        assert_eq!(items, ["MAX_PLAYERS", "math", "py_only", "spawn"], "LSP, wrong from import completion");
+
+        // Unknown names are left to the normal language server.
+        let hover = client.request("textDocument/hover", position(py_uri, 1, 2));
+        assert_eq!(hover, Json::Null, "LSP, Expected no hover");
+
+        assert_eq!(client.request("shutdown", Json::Null), Json::Null, "LSP, wrong shutdown");
+        client.notify("exit", Json::Null);
+        let status = client.child.wait().unwrap();
+        assert!(status.success(), "LSP, Expected a clean exit");
+    }
+}
@@ -13,12 +13,12 @@ mod tests {
     use std::{
         ffi::{CStr, CString, c_char, c_void},
         ptr,
-        sync::Arc,
+        sync::{Mutex, atomic::{AtomicI64, AtomicPtr, AtomicUsize, Ordering}},
     };
 
     use pixelscript::{
         lua::LuaScripting,
-        shared::{PixelScript, PtrMagic, object::pxs_PixelObject, var::{pxs_Var, pxs_VarT}},
+        shared::{PixelScript, PtrMagic, pxs_Runtime, args::{FromPxsVar, HostRef, HostType}, func::pxs_Func, module::pxs_Module, object::{pxs_FieldType, pxs_OperatorKind}, signature::pxs_ParamType, types::{pxs_PixelType, pxs_ValueType}, var::{pxs_Var, pxs_VarT}},
         *,
     };
 
@@ -120,6 +120,13 @@ mod tests {
             let get_name_raw = create_raw_string!("get_name");
             pxs_object_addfunc(pixel_object, set_name_raw, set_name, opaque);
             pxs_object_addfunc(pixel_object, get_name_raw, get_name, opaque);
+            // Properties
+            let name_raw = create_raw_string!("name");
+            let display_name_raw = create_raw_string!("display_name");
+            pxs_object_addprop(pixel_object, name_raw, get_name, Some(set_name), opaque);
+            pxs_object_addprop(pixel_object, display_name_raw, get_name, None, opaque);
+            free_raw_string!(name_raw);
+            free_raw_string!(display_name_raw);
             // Save...
             let var = pxs_newhost(pixel_object);
 
@@ -130,6 +137,139 @@ mod tests {
         }
     }
 
+    #[derive(Clone, Copy)]
+    struct Vector {
+        x: f64,
+        y: f64,
+        hits: i32,
+    }
+
+    impl PtrMagic for Vector {}
+
+    impl HostType for Vector {
+        const TYPE_NAME: &'static str = "Vector";
+    }
+
+    static VECTORS_FREED: AtomicUsize = AtomicUsize::new(0);
+
+    pub extern "C" fn free_vector(ptr: *mut c_void) {
+        let _ = Vector::from_raw(ptr as *mut Vector);
+        VECTORS_FREED.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// The Vector type, registered in `test_add_module`.
+    static VECTOR_TYPE: AtomicPtr<pxs_PixelType> = AtomicPtr::new(ptr::null_mut());
+
+    fn register_vector_type() {
+        let type_name = create_raw_string!("Vector");
+        let vector_type = pxs_newtype(type_name);
+        free_raw_string!(type_name);
+        pxs_type_addop(vector_type, pxs_OperatorKind::pxs_OpAdd, vector_add, ptr::null_mut());
+        pxs_type_addop(vector_type, pxs_OperatorKind::pxs_OpMul, vector_mul, ptr::null_mut());
+        pxs_type_addop(vector_type, pxs_OperatorKind::pxs_OpEq, vector_eq, ptr::null_mut());
+        pxs_type_addop(vector_type, pxs_OperatorKind::pxs_OpToString, vector_tostring, ptr::null_mut());
+        pxs_type_addop(vector_type, pxs_OperatorKind::pxs_OpLen, vector_len, ptr::null_mut());
+        pxs_type_addop(vector_type, pxs_OperatorKind::pxs_OpIndex, vector_index, ptr::null_mut());
+        pxs_type_addop(vector_type, pxs_OperatorKind::pxs_OpNext, vector_next, ptr::null_mut());
+        pxs_type_setctor(vector_type, new_vector, ptr::null_mut());
+
+        let x_name = create_raw_string!("x");
+        pxs_object_addfield(vector_type, x_name, std::mem::offset_of!(Vector, x), pxs_FieldType::pxs_FieldF64, false);
+        free_raw_string!(x_name);
+        let y_name = create_raw_string!("y");
+        pxs_object_addfield(vector_type, y_name, std::mem::offset_of!(Vector, y), pxs_FieldType::pxs_FieldF64, true);
+        free_raw_string!(y_name);
+        let hits_name = create_raw_string!("hits");
+        pxs_object_addfield(vector_type, hits_name, std::mem::offset_of!(Vector, hits), pxs_FieldType::pxs_FieldI32, false);
+        free_raw_string!(hits_name);
+
+        let sum_name = create_raw_string!("sum");
+        pxs_type_addmethod(vector_type, sum_name, vector_sum, ptr::null_mut());
+        free_raw_string!(sum_name);
+
+        let zero_name = create_raw_string!("zero");
+        pxs_type_addstatic(vector_type, zero_name, vector_zero, ptr::null_mut());
+        free_raw_string!(zero_name);
+        let dimensions_name = create_raw_string!("DIMENSIONS");
+        pxs_type_addconst(vector_type, dimensions_name, pxs_newint(2));
+        free_raw_string!(dimensions_name);
+
+        VECTOR_TYPE.store(vector_type, Ordering::SeqCst);
+    }
+
+    fn make_vector(v: Vector) -> *mut pxs_Var {
+        pxs_newinstance(
+            VECTOR_TYPE.load(Ordering::SeqCst),
+            Vector::into_raw(v) as *mut c_void,
+            free_vector,
+        )
+    }
+
+    pub extern "C" fn new_vector(args: *mut pxs_Var, _opaque: *mut c_void) -> *mut pxs_Var {
+        let (_rt, x, y): (pxs_Runtime, f64, f64) = pxs_args!(args);
+        make_vector(Vector { x, y, hits: 0 })
+    }
+
+    pub extern "C" fn vector_zero(args: *mut pxs_Var, _opaque: *mut c_void) -> *mut pxs_Var {
+        let (_rt,): (pxs_Runtime,) = pxs_args!(args);
+        make_vector(Vector { x: 0.0, y: 0.0, hits: 0 })
+    }
+
+    pub extern "C" fn vector_sum(args: *mut pxs_Var, _opaque: *mut c_void) -> *mut pxs_Var {
+        let (_rt, this): (pxs_Runtime, HostRef<Vector>) = pxs_args!(args);
+        pxs_newfloat(this.x + this.y)
+    }
+
+    pub extern "C" fn vector_add(args: *mut pxs_Var, _opaque: *mut c_void) -> *mut pxs_Var {
+        let (_rt, this, other, _reflected): (pxs_Runtime, HostRef<Vector>, HostRef<Vector>, bool) =
+            pxs_args!(args);
+        make_vector(Vector { x: this.x + other.x, y: this.y + other.y, hits: 0 })
+    }
+
+    pub extern "C" fn vector_mul(args: *mut pxs_Var, _opaque: *mut c_void) -> *mut pxs_Var {
+        let (_rt, this, scalar, _reflected): (pxs_Runtime, HostRef<Vector>, f64, bool) = pxs_args!(args);
+        make_vector(Vector { x: this.x * scalar, y: this.y * scalar, hits: 0 })
+    }
+
+    pub extern "C" fn vector_eq(args: *mut pxs_Var, _opaque: *mut c_void) -> *mut pxs_Var {
+        let (_rt, this, other, _reflected): (pxs_Runtime, HostRef<Vector>, pxs_Var, bool) = pxs_args!(args);
+        let other = HostRef::<Vector>::from_pxs_var(&other);
+        let eq = other.map(|o| o.x == this.x && o.y == this.y).unwrap_or(false);
+        pxs_Var::new_bool(eq).into_raw()
+    }
+
+    pub extern "C" fn vector_tostring(args: *mut pxs_Var, _opaque: *mut c_void) -> *mut pxs_Var {
+        let (_rt, this): (pxs_Runtime, HostRef<Vector>) = pxs_args!(args);
+        pxs_Var::new_string(format!("Vector({}, {})", this.x, this.y)).into_raw()
+    }
+
+    pub extern "C" fn vector_len(args: *mut pxs_Var, _opaque: *mut c_void) -> *mut pxs_Var {
+        let (_rt, _this): (pxs_Runtime, HostRef<Vector>) = pxs_args!(args);
+        pxs_Var::new_i64(2).into_raw()
+    }
+
+    pub extern "C" fn vector_index(args: *mut pxs_Var, _opaque: *mut c_void) -> *mut pxs_Var {
+        let (_rt, this, key): (pxs_Runtime, HostRef<Vector>, String) = pxs_args!(args);
+        match key.as_str() {
+            "x" => pxs_Var::new_f64(this.x).into_raw(),
+            "y" => pxs_Var::new_f64(this.y).into_raw(),
+            _ => pxs_Var::new_exception(format!("Vector has no component {}", key)).into_raw(),
+        }
+    }
+
+    pub extern "C" fn vector_next(args: *mut pxs_Var, _opaque: *mut c_void) -> *mut pxs_Var {
+        let (_rt, this, state): (pxs_Runtime, HostRef<Vector>, Option<i64>) = pxs_args!(args);
+        let (state, value) = match state {
+            None => (1, this.x),
+            Some(1) => (2, this.y),
+            _ => return pxs_newnull(),
+        };
+        let step = pxs_newlist();
+        pxs_listadd(step, pxs_newint(state));
+        pxs_listadd(step, pxs_newfloat(value));
+        step
+    }
+
     // Testing callbacks
     pub extern "C" fn print_wrapper(
         args: *mut pxs_Var,
@@ -171,6 +311,249 @@ mod tests {
         }
     }
 
+    pub extern "C" fn repeat_wrapper(
+        args: *mut pxs_Var,
+        _opaque: *mut c_void,
+    ) -> *mut pxs_Var {
+        let (_rt, text, count): (pxs_Runtime, String, Option<i64>) = pxs_args!(args);
+
+        pxs_Var::new_string(text.repeat(count.unwrap_or(1) as usize)).into_raw()
+    }
+
+    /// Calls `describe` on a object, which scripts override in a subclass.
+    pub extern "C" fn describe_wrapper(args: *mut pxs_Var, _opaque: *mut c_void) -> *mut pxs_Var {
+        let method = create_raw_string!("describe");
+        let res = pxs_objectcall(pxs_listget(args, 0), pxs_listget(args, 1), method, pxs_newlist());
+        free_raw_string!(method);
+        if res.is_null() { pxs_newnull() } else { res }
+    }
+
+    /// Host buffer exposed to the scripts through a array view.
+    static mut PARTICLES: [f32; 4] = [1.0, 2.0, 3.0, 4.0];
+
+    pub extern "C" fn particles_wrapper(args: *mut pxs_Var, _opaque: *mut c_void) -> *mut pxs_Var {
+        let (_rt, readonly): (pxs_Runtime, bool) = pxs_args!(args);
+        let particles = &raw mut PARTICLES;
+        pxs_newarrayview(pxs_FieldType::pxs_FieldF32, particles as *mut c_void, 4, readonly)
+    }
+
+    static VEC2_TYPE: AtomicPtr<pxs_ValueType> = AtomicPtr::new(ptr::null_mut());
+
+    fn register_vec2_type() {
+        let name = create_raw_string!("Vec2");
+        let x = create_raw_string!("x");
+        let y = create_raw_string!("y");
+        let names = [x as *const c_char, y as *const c_char];
+        let types = [pxs_FieldType::pxs_FieldF64, pxs_FieldType::pxs_FieldF64];
+        VEC2_TYPE.store(pxs_newvaluetype(name, names.as_ptr(), types.as_ptr(), 2), Ordering::SeqCst);
+        free_raw_string!(name);
+        free_raw_string!(x);
+        free_raw_string!(y);
+    }
+
+    /// Scales a Vec2, values are copied in and out.
+    pub extern "C" fn scale_wrapper(args: *mut pxs_Var, _opaque: *mut c_void) -> *mut pxs_Var {
+        let value = pxs_listget(args, 1);
+        let factor = pxs_getfloat(pxs_listget(args, 2));
+        let res = pxs_newvalue(VEC2_TYPE.load(Ordering::SeqCst));
+        for field in ["x", "y"] {
+            let name = create_raw_string!(field);
+            let v = pxs_valueget(value, name);
+            let scaled = pxs_newfloat(pxs_getfloat(v) * factor);
+            pxs_valueset(res, name, scaled);
+            pxs_freevar(v);
+            pxs_freevar(scaled);
+            free_raw_string!(name);
+        }
+        res
+    }
+
+    /// Host state read and written by scripts through a live variable.
+    static SCORE: AtomicI64 = AtomicI64::new(10);
+
+    pub extern "C" fn score_getter(_args: *mut pxs_Var, _opaque: *mut c_void) -> *mut pxs_Var {
+        pxs_newint(SCORE.load(Ordering::SeqCst))
+    }
+
+    pub extern "C" fn score_setter(args: *mut pxs_Var, _opaque: *mut c_void) -> *mut pxs_Var {
+        SCORE.store(pxs_getint(pxs_listget(args, 1)), Ordering::SeqCst);
+        pxs_newnull()
+    }
+
+    pub extern "C" fn dlc_v1(_args: *mut pxs_Var, _opaque: *mut c_void) -> *mut pxs_Var {
+        pxs_newint(1)
+    }
+
+    pub extern "C" fn dlc_v2(_args: *mut pxs_Var, _opaque: *mut c_void) -> *mut pxs_Var {
+        pxs_newint(2)
+    }
+
+    /// Add or replace the `dlc` module, `dlc.version()` calls `version`.
+    fn add_dlc_module(version: pxs_Func, replace: bool) {
+        let module_name = create_raw_string!("dlc");
+        let module = pxs_newmod(module_name);
+        let version_name = create_raw_string!("version");
+        pxs_addfunc(module, version_name, version, ptr::null_mut());
+        if replace {
+            pxs_replacemod(module);
+        } else {
+            pxs_addmod(module);
+        }
+        free_raw_string!(module_name);
+        free_raw_string!(version_name);
+    }
+
+    /// Add or replace `Vector.total`, after scripts already hold vectors.
+    fn set_vector_total(total: pxs_Func) {
+        let total_name = create_raw_string!("total");
+        pxs_type_addmethod(VECTOR_TYPE.load(Ordering::SeqCst), total_name, total, ptr::null_mut());
+        free_raw_string!(total_name);
+    }
+
+    /// Add `name` with `version()` calling `version`.
+    fn add_version_module(name: &str, version: pxs_Func) {
+        let module_name = create_raw_string!(name);
+        let module = pxs_newmod(module_name);
+        let version_name = create_raw_string!("version");
+        pxs_addfunc(module, version_name, version, ptr::null_mut());
+        pxs_addmod(module);
+        free_raw_string!(module_name);
+        free_raw_string!(version_name);
+    }
+
+    /// How many times the resolver was asked for `lazy_items`.
+    static LAZY_RESOLVES: AtomicUsize = AtomicUsize::new(0);
+
+    /// Builds `lazy_items` on demand, `lazy_items.count()` gives 1.
+    pub extern "C" fn lazy_resolver(name: *const c_char) -> *mut pxs_Module {
+        if unsafe { CStr::from_ptr(name) }.to_str().unwrap() != "lazy_items" {
+            return ptr::null_mut();
+        }
+        LAZY_RESOLVES.fetch_add(1, Ordering::SeqCst);
+
+        let module_name = create_raw_string!("lazy_items");
+        let module = pxs_newmod(module_name);
+        let count_name = create_raw_string!("count");
+        pxs_addfunc(module, count_name, dlc_v1, ptr::null_mut());
+        free_raw_string!(module_name);
+        free_raw_string!(count_name);
+        module
+    }
+
+    /// Add `py_only` to Python only, and `masked` where `both` is everywhere, `lua_fn` Lua only and `py_fn` Python only.
+    fn add_masked_modules() {
+        let lua_mask = pxs_Runtime::pxs_Lua.mask();
+        let py_mask = pxs_Runtime::pxs_Python.mask();
+
+        let py_only_name = create_raw_string!("py_only");
+        let py_only = pxs_newmod(py_only_name);
+        let version_name = create_raw_string!("version");
+        pxs_addfunc(py_only, version_name, dlc_v1, ptr::null_mut());
+        pxs_addmod_for(py_only, py_mask);
+
+        let masked_name = create_raw_string!("masked");
+        let masked = pxs_newmod(masked_name);
+        let both_name = create_raw_string!("both");
+        let lua_fn_name = create_raw_string!("lua_fn");
+        let py_fn_name = create_raw_string!("py_fn");
+        pxs_addfunc(masked, both_name, dlc_v1, ptr::null_mut());
+        pxs_addfunc_for(masked, lua_fn_name, dlc_v1, ptr::null_mut(), lua_mask);
+        pxs_addfunc_for(masked, py_fn_name, dlc_v1, ptr::null_mut(), py_mask);
+        pxs_addmod_for(masked, lua_mask | py_mask);
+
+        for name in [py_only_name, version_name, masked_name, both_name, lua_fn_name, py_fn_name] {
+            free_raw_string!(name);
+        }
+    }
+
+    /// Add `settings`, a module that is not frozen with the constant `MAX_PLAYERS` and the variable `motd`.
+    fn add_settings_module() {
+        let settings_name = create_raw_string!("settings");
+        let settings = pxs_newmod(settings_name);
+        let max_name = create_raw_string!("MAX_PLAYERS");
+        pxs_addconst(settings, max_name, pxs_newint(4));
+        let motd_name = create_raw_string!("motd");
+        let motd_value = create_raw_string!("hi");
+        pxs_addvar(settings, motd_name, pxs_newstring(motd_value));
+        pxs_setmodfrozen(settings, false);
+        pxs_addmod(settings);
+
+        for name in [settings_name, max_name, motd_name, motd_value] {
+            free_raw_string!(name);
+        }
+    }
+
+    /// `greet(name, times)`, trusts it's signature to check the arguments.
+    pub extern "C" fn greet_wrapper(args: *mut pxs_Var, _opaque: *mut c_void) -> *mut pxs_Var {
+        let name = pxs_getstring(pxs_listget(args, 1));
+        let greeting = format!("hi {}", unsafe { CStr::from_ptr(name) }.to_str().unwrap()).repeat(pxs_getint(pxs_listget(args, 2)) as usize);
+        pxs_freestr(name);
+        let greeting = create_raw_string!(greeting);
+        let res = pxs_newstring(greeting);
+        free_raw_string!(greeting);
+        res
+    }
+
+    /// `count(items)`, the number of list items. Tables without list items count as empty.
+    pub extern "C" fn count_wrapper(args: *mut pxs_Var, _opaque: *mut c_void) -> *mut pxs_Var {
+        pxs_newint(pxs_listlen(pxs_listget(args, 1)).max(0) as i64)
+    }
+
+    /// Add `docs` with `greet(name, times = 1)` and `count(items)`, described by signatures.
+    fn add_docs_module() {
+        let docs_name = create_raw_string!("docs");
+        let docs = pxs_newmod(docs_name);
+        let doc = create_raw_string!("Greet someone.");
+        let sig = pxs_newsig(doc);
+        let name_name = create_raw_string!("name");
+        let times_name = create_raw_string!("times");
+        pxs_sigaddparam(sig, name_name, pxs_ParamType::pxs_ParamString, ptr::null_mut());
+        assert!(pxs_sigaddparam(sig, times_name, pxs_ParamType::pxs_ParamInt, pxs_newint(1)), "Signatures, Expected a int default");
+        let items_name = create_raw_string!("items");
+        assert!(!pxs_sigaddparam(sig, items_name, pxs_ParamType::pxs_ParamList, pxs_newlist()), "Signatures, Expected a list default to be rejected");
+        free_raw_string!(items_name);
+        pxs_sigsetreturn(sig, pxs_ParamType::pxs_ParamString);
+        let greet_name = create_raw_string!("greet");
+        pxs_addfunc_ex(docs, greet_name, greet_wrapper, ptr::null_mut(), sig);
+        let count_sig = pxs_newsig(ptr::null());
+        let items_name = create_raw_string!("items");
+        let count_name = create_raw_string!("count");
+        pxs_sigaddparam(count_sig, items_name, pxs_ParamType::pxs_ParamList, ptr::null_mut());
+        pxs_sigsetreturn(count_sig, pxs_ParamType::pxs_ParamInt);
+        pxs_addfunc_ex(docs, count_name, count_wrapper, ptr::null_mut(), count_sig);
+        pxs_addmod(docs);
+
+        for name in [docs_name, doc, name_name, times_name, greet_name, items_name, count_name] {
+            free_raw_string!(name);
+        }
+    }
+
+    /// Stub files written by `pxs_genstubs`, by path.
+    static STUBS: Mutex<Vec<(String, String)>> = Mutex::new(vec![]);
+
+    unsafe extern "C" fn stub_writer(file_path: *const c_char, contents: *const c_char) {
+        let file_path = unsafe { CStr::from_ptr(file_path) }.to_str().unwrap().to_string();
+        let contents = unsafe { CStr::from_ptr(contents) }.to_str().unwrap().to_string();
+        STUBS.lock().unwrap().push((file_path, contents));
+    }
+
+    /// Reports every write as failed.
+    unsafe extern "C" fn failing_writer(_file_path: *const c_char, _contents: *const c_char) -> bool {
+        false
+    }
+
+    /// Get a written stub file.
+    fn get_stub(file_path: &str) -> String {
+        let stubs = STUBS.lock().unwrap();
+        let stub = stubs.iter().find(|(path, _)| path == file_path);
+        stub.map(|(_, contents)| contents.clone()).unwrap_or_else(|| panic!("Stubs, Expected {file_path}"))
+    }
+
+    pub extern "C" fn destroy_wrapper(args: *mut pxs_Var, _opaque: *mut c_void) -> *mut pxs_Var {
+        let destroyed = pxs_destroyobject(pxs_listget(args, 1));
+        pxs_Var::new_bool(destroyed).into_raw()
+    }
+
     unsafe extern "C" fn file_loader(file_path: *const c_char) -> *mut c_char {
         let file_path = unsafe { CStr::from_ptr(file_path).to_str().unwrap() };
 
@@ -234,11 +617,54 @@ mod tests {
         let object_name = create_raw_string!("Person");
         pxs_addobject(module, object_name, new_person, ptr::null_mut());
 
+        register_vector_type();
+        pxs_addtype(module, VECTOR_TYPE.load(Ordering::SeqCst));
+
         // Add call 
         let call_name = create_raw_string!("call_function");
         pxs_addfunc(module, call_name, call_function, ptr::null_mut());
         free_raw_string!(call_name);
 
+        // Add typed args
+        let repeat_name = create_raw_string!("repeat");
+        pxs_addfunc(module, repeat_name, repeat_wrapper, ptr::null_mut());
+        free_raw_string!(repeat_name);
+
+        let describe_name = create_raw_string!("describe");
+        pxs_addfunc(module, describe_name, describe_wrapper, ptr::null_mut());
+        free_raw_string!(describe_name);
+
+        let particles_name = create_raw_string!("particles");
+        pxs_addfunc(module, particles_name, particles_wrapper, ptr::null_mut());
+        free_raw_string!(particles_name);
+
+        register_vec2_type();
+        pxs_addvaluetype(module, VEC2_TYPE.load(Ordering::SeqCst));
+
+        let scale_name = create_raw_string!("scale");
+        pxs_addfunc(module, scale_name, scale_wrapper, ptr::null_mut());
+        free_raw_string!(scale_name);
+
+        let damage_name = create_raw_string!("Damage");
+        let members = ["Fire", "Ice", "Poison"].map(|m| create_raw_string!(m));
+        let names = members.map(|m| m as *const c_char);
+        pxs_addenum(module, damage_name, names.as_ptr(), [1, 2, 4].as_ptr(), 3);
+        free_raw_string!(damage_name);
+        for m in members {
+            free_raw_string!(m);
+        }
+
+        let score_name = create_raw_string!("score");
+        pxs_addlivevar(module, score_name, score_getter, Some(score_setter), ptr::null_mut());
+        free_raw_string!(score_name);
+        let level_name = create_raw_string!("level");
+        pxs_addlivevar(module, level_name, score_getter, None, ptr::null_mut());
+        free_raw_string!(level_name);
+
+        let destroy_name = create_raw_string!("destroy");
+        pxs_addfunc(module, destroy_name, destroy_wrapper, ptr::null_mut());
+        free_raw_string!(destroy_name);
+
         // Add a inner module
         let math_module_name = create_raw_string!("math");
         let math_module = pxs_newmod(math_module_name);
@@ -247,6 +673,13 @@ mod tests {
         let sub_name = create_raw_string!("sub");
         pxs_addfunc(math_module, sub_name, sub_wrapper, ptr::null_mut());
 
+        // And a variable, owned by the inner module
+        let unit_name = create_raw_string!("unit");
+        let unit_value = create_raw_string!("radians");
+        pxs_addvar(math_module, unit_name, pxs_newstring(unit_value));
+        free_raw_string!(unit_name);
+        free_raw_string!(unit_value);
+
         pxs_add_submod(module, math_module);
         pxs_addmod(module);
 
@@ -265,6 +698,14 @@ mod tests {
     fn test_execute() {
         pxs_initialize();
 
+        // Exceptions
+        let exception = pxs_Var::new_exception("bad\0message".to_string());
+        assert_eq!(exception.get_exception().unwrap(), "badmessage", "Exception, Expected the NUL removed");
+        assert!(exception.get_string().is_err(), "Exception, Expected it not to be a string");
+        let empty_exception = pxs_newexception(ptr::null());
+        assert_eq!(unsafe { pxs_Var::from_borrow(empty_exception) }.get_exception().unwrap(), "", "Exception, Expected a empty message");
+        pxs_freevar(empty_exception);
+
         test_add_module();
 
         pxs_set_filereader(file_loader);
@@ -290,6 +731,9 @@ mod tests {
             end
 
             local res = pxs_math.sub(1, 2)
+            assert(pxs.math == pxs_math, "Submodules, Expected pxs.math to be require('pxs.math')")
+            assert(pxs.math.sub(1, 2) == 1, "Submodules, Expected pxs.math.sub to work")
+            assert(pxs_math.unit == "radians", "Submodules, Expected pxs.math.unit")
 
             if res ~= 1 then
                 error("Math, Expected 1, got " .. tostring(res))
@@ -300,6 +744,16 @@ mod tests {
             person:set_name("Jordan Castro")
             pxs.print(person:get_name())
 
+            -- Properties
+            person.name = "Jordan C"
+            if person.name ~= "Jordan C" or person.display_name ~= "Jordan C" then
+                error("Properties, Expected Jordan C, got " .. tostring(person.name))
+            end
+            local ok = pcall(function() person.display_name = "Nope" end)
+            if ok then
+                error("Properties, Expected display_name to be read-only")
+            end
+
             -- Test calling function.
             function hadd(n1, n2)
                 return n1 + n2
@@ -310,10 +764,422 @@ mod tests {
                 return 3.145
             end 
             pxs.print(tostring(pxs.call_function(get_pi)))
+
+            -- Operators
+            local v = pxs.Vector(1, 2) + pxs.Vector(3, 4)
+            if tostring(v) ~= "Vector(4, 6)" or #v ~= 2 or v.x ~= 4 then
+                error("Operators, Expected Vector(4, 6), got " .. tostring(v))
+            end
+            if v ~= pxs.Vector(4, 6) or (2 * v).y ~= 12 then
+                error("Operators, Expected v == Vector(4, 6)")
+            end
+
+            -- Iteration
+            local components = {}
+            for i, value in pairs(v) do
+                components[i] = value
+            end
+            if #components ~= 2 or components[1] ~= 4 or components[2] ~= 6 then
+                error("Iteration, Expected {4, 6}")
+            end
+
+            -- Fields
+            local f = pxs.Vector(1, 2)
+            f.x = 5
+            if f.x ~= 5 or f.y ~= 2 or f:sum() ~= 7 then
+                error("Fields, Expected x = 5 in host memory")
+            end
+            local ok, err = pcall(function() f.y = 3 end)
+            if ok or not string.find(tostring(err), "read-only", 1, true) then
+                error("Fields, Expected y to be read-only")
+            end
+            f.hits = 3
+            for _, big in ipairs({1 << 40, 2.0 ^ 40}) do
+                local ok, err = pcall(function() f.hits = big end)
+                if ok or not string.find(tostring(err), "does not fit in a i32", 1, true) or f.hits ~= 3 then
+                    error("Fields, Expected 2^40 to be out of range for hits: " .. tostring(err))
+                end
+            end
+
+            -- Array views
+            local particles = pxs.particles(false)
+            if #particles ~= 4 or particles[1] ~= 1 or particles[5] ~= nil then
+                error("Array views, Expected 4 particles")
+            end
+            for i = 1, #particles do
+                particles[i] = particles[i] * 2
+            end
+            local total = 0
+            for _, p in ipairs(particles) do
+                total = total + p
+            end
+            for i, p in pairs(particles) do
+                total = total + i
+            end
+            if total ~= 30 then
+                error("Array views, Expected 30 got " .. total)
+            end
+            ok, err = pcall(function() particles[5] = 1 end)
+            if ok or not string.find(tostring(err), "out of range", 1, true) then
+                error("Array views, Expected a bounds error")
+            end
+            ok, err = pcall(function() pxs.particles(true)[1] = 0 end)
+            if ok or not string.find(tostring(err), "read-only", 1, true) then
+                error("Array views, Expected a read-only error")
+            end
+
+            -- Value types
+            local v = pxs.Vec2(1, 2)
+            if getmetatable(v).__index ~= nil or v.x ~= 1 or v.y ~= 2 or tostring(v) ~= "Vec2(x=1, y=2)" then
+                error("Values, Expected a plain Vec2 table got " .. tostring(v))
+            end
+            v.x = 3
+            local scaled = pxs.scale(v, 2)
+            if scaled.x ~= 6 or scaled.y ~= 4 or v.x ~= 3 or pxs.Vec2().y ~= 0 then
+                error("Values, Expected a scaled copy")
+            end
+            v.y = "a"
+            ok, err = pcall(pxs.scale, v, 2)
+            if ok or not string.find(tostring(err), "Vec2.y", 1, true) then
+                error("Values, Expected a validation error")
+            end
+
+            -- Enums
+            local fire = pxs.Damage.Fire
+            if tostring(fire) ~= "Damage.Fire" or fire.name ~= "Fire" or fire.value ~= 1 then
+                error("Enums, Expected Damage.Fire got " .. tostring(fire))
+            end
+            if pxs.Damage[4] ~= pxs.Damage.Poison or pxs.Damage[3] ~= nil or pxs.Damage[fire] ~= fire or #pxs.Damage ~= 3 then
+                error("Enums, Expected reverse lookup and membership")
+            end
+            if pxs.add(fire, pxs.Damage.Ice) ~= 3 then
+                error("Enums, Expected members to cross as their value")
+            end
+            local members = {}
+            for name, m in pairs(pxs.Damage) do
+                members[#members + 1] = name .. "=" .. m.value
+            end
+            if table.concat(members, ",") ~= "Fire=1,Ice=2,Poison=4" then
+                error("Enums, Expected members in order")
+            end
+            ok, err = pcall(function() pxs.Damage.Fire = 3 end)
+            if ok or not string.find(tostring(err), "read-only", 1, true) then
+                error("Enums, Expected a frozen enum")
+            end
+
+            -- Live variables
+            if pxs.score ~= 10 then
+                error("Live variables, Expected 10")
+            end
+            pxs.score = pxs.score + 5
+            if pxs.score ~= 15 or pxs.level ~= 15 then
+                error("Live variables, Expected the setter to write the host value")
+            end
+            ok, err = pcall(function() pxs.level = 1 end)
+            if ok or not string.find(tostring(err), "read-only", 1, true) then
+                error("Live variables, Expected level to be read-only")
+            end
+
+            -- Types
+            local zero = pxs.Vector.zero()
+            if tostring(zero) ~= "Vector(0, 0)" or pxs.Vector.DIMENSIONS ~= 2 then
+                error("Types, Expected Vector(0, 0) and 2 dimensions")
+            end
+            if not pxs.Vector.isinstance(zero) or pxs.Vector.isinstance(pxs.Person("Jordan")) then
+                error("Types, Expected isinstance to check the type")
+            end
+
+            -- Subclasses
+            FireVector = pxs.Vector:extend()
+            function FireVector:describe()
+                return "fire " .. tostring(self)
+            end
+            local fv = FireVector(1, 2)
+            if fv:describe() ~= "fire Vector(1, 2)" or fv.x ~= 1 or fv:sum() ~= 3 then
+                error("Subclass, Expected overrides and host methods to work")
+            end
+            if not FireVector.isinstance(fv) or not pxs.Vector.isinstance(fv) or FireVector.isinstance(zero) then
+                error("Subclass, Expected isinstance to follow the subclass")
+            end
+            if pxs.describe(fv) ~= "fire Vector(1, 2)" then
+                error("Subclass, Expected the host to call the override")
+            end
+
+            -- Temporaries are freed by the GC
+            for i = 1, 10 do
+                local _ = pxs.Vector(i, i) + pxs.Vector(i, i)
+            end
+            collectgarbage()
+
+            -- Destroyed objects raise
+            local doomed = pxs.Vector(1, 1)
+            if not pxs.destroy(doomed) or pxs.destroy(doomed) then
+                error("Destroy, Expected the first destroy only to succeed")
+            end
+            local ok, err = pcall(function() return doomed + doomed end)
+            if ok or not string.find(tostring(err), "object was destroyed", 1, true) then
+                error("Destroy, Expected a destroyed error, got " .. tostring(err))
+            end
+
+            -- Typed args
+            if pxs["repeat"]("ab", 2) ~= "abab" or pxs["repeat"]("ab") ~= "ab" then
+                error("Typed args, Expected abab and ab")
+            end
+            local ok, err = pcall(pxs["repeat"], 5)
+            if ok or not string.find(tostring(err), "Argument 1: expected string", 1, true) then
+                error("Typed args, Expected a type error, got " .. tostring(err))
+            end
+            ok, err = pcall(pxs["repeat"])
+            if ok or not string.find(tostring(err), "Missing argument 1", 1, true) then
+                error("Typed args, Expected a arity error, got " .. tostring(err))
+            end
+            local vector_mt = getmetatable(pxs.Vector(1, 2))
+            ok, err = pcall(vector_mt.sum, pxs.Person("Jordan"))
+            if ok or not string.find(tostring(err), "expected a Vector object, found Person", 1, true) then
+                error("Typed args, Expected the wrong type to be rejected, got " .. tostring(err))
+            end
+            ok, err = pcall(vector_mt.sum, setmetatable({_id = 123456789}, vector_mt))
+            if ok or not string.find(tostring(err), "object was destroyed", 1, true) then
+                error("Typed args, Expected a forged handle to be rejected, got " .. tostring(err))
+            end
         "#;
         let err = LuaScripting::execute(lua_code, "<test>");
 
         assert!(err.is_empty(), "Lua Error is not empty: {}", err);
+        assert!(VECTORS_FREED.load(Ordering::SeqCst) >= 30, "Vectors were not freed by the GC");
+        assert_eq!(SCORE.load(Ordering::SeqCst), 15, "Live variables, Expected the script to write the score");
+        assert_eq!(unsafe { *(&raw const PARTICLES) }, [2.0, 4.0, 6.0, 8.0], "Array views, Expected writes in the host buffer");
+
+        // Typed args, host side
+        let vector = make_vector(Vector { x: 1.0, y: 2.0, hits: 0 });
+        let person_args = pxs_newlist();
+        let person_name = create_raw_string!("Jordan");
+        pxs_listadd(person_args, pxs_newint(pxs_Runtime::pxs_Lua as i64));
+        pxs_listadd(person_args, pxs_newstring(person_name));
+        free_raw_string!(person_name);
+        let person = new_person(person_args, ptr::null_mut());
+        let (vector_var, person_var) = unsafe { (pxs_Var::from_borrow(vector), pxs_Var::from_borrow(person)) };
+        assert!(HostRef::<Vector>::from_pxs_var(vector_var).is_ok(), "Typed args, Expected a Vector");
+        let arg_err = HostRef::<Vector>::from_pxs_var(person_var).err().map(|e| e.to_string()).unwrap_or_default();
+        assert_eq!(arg_err, "expected Vector, found Person", "Typed args, Expected the wrong type to be rejected");
+        let forged = pxs_Var::new_i64(vector_var.get_object_ptr());
+        assert!(HostRef::<Vector>::from_pxs_var(&forged).is_err(), "Typed args, Expected a int handle to be rejected");
+        let arg_err = i32::from_pxs_var(&pxs_Var::new_i64(1 << 40)).err().map(|e| e.to_string()).unwrap_or_default();
+        assert_eq!(arg_err, "int 1099511627776 does not fit in a i32", "Typed args, Expected a range error");
+        pxs_freevar(vector);
+        pxs_freevar(person);
+        pxs_freevar(person_args);
+
+        // Instantiate a script subclass from the host
+        let runtime = pxs_newint(pxs_Runtime::pxs_Lua as i64);
+        let class_name = create_raw_string!("FireVector");
+        let ctor_args = pxs_newlist();
+        pxs_listadd(ctor_args, pxs_newfloat(3.0));
+        pxs_listadd(ctor_args, pxs_newfloat(4.0));
+        let fire = pxs_instantiate(runtime, class_name, ctor_args);
+        free_raw_string!(class_name);
+
+        let method = create_raw_string!("describe");
+        let described = pxs_objectcall(runtime, fire, method, pxs_newlist());
+        free_raw_string!(method);
+        let method = create_raw_string!("sum");
+        let sum = pxs_objectcall(runtime, fire, method, pxs_newlist());
+        free_raw_string!(method);
+
+        assert!(!described.is_null() && !sum.is_null(), "Subclass, Expected the host calls to succeed");
+        let described_str = pxs_getstring(described);
+        assert_eq!(unsafe { CStr::from_ptr(described_str) }.to_str().unwrap(), "fire Vector(3, 4)");
+        assert_eq!(pxs_getfloat(sum), 7.0);
+        pxs_freestr(described_str);
+        pxs_freevar(described);
+        pxs_freevar(sum);
+        pxs_freevar(fire);
+        pxs_freevar(runtime);
+
+        // Hot-swap a module
+        add_dlc_module(dlc_v1, false);
+        let err = LuaScripting::execute(r#"
+            old_version = require("dlc").version
+            assert(old_version() == 1, "Modules, Expected version 1")
+        "#, "<dlc>");
+        assert!(err.is_empty(), "Lua Error is not empty: {}", err);
+        add_dlc_module(dlc_v2, true);
+        let err = LuaScripting::execute(r#"
+            assert(require("dlc").version() == 2, "Modules, Expected version 2")
+            local ok, err = pcall(old_version)
+            assert(not ok and string.find(tostring(err), "was removed", 1, true), "Modules, Expected the old version to be removed: " .. tostring(err))
+        "#, "<dlc>");
+        assert!(err.is_empty(), "Lua Error is not empty: {}", err);
+        let dlc_name = create_raw_string!("dlc");
+        assert!(pxs_removemod(dlc_name), "Modules, Expected dlc to be removed");
+        assert!(!pxs_removemod(dlc_name), "Modules, Expected dlc to be removed once");
+        free_raw_string!(dlc_name);
+        let err = LuaScripting::execute(r#"assert(not pcall(require, "dlc"), "Modules, Expected require to fail")"#, "<dlc>");
+        assert!(err.is_empty(), "Lua Error is not empty: {}", err);
+
+        // Members added to a type in use
+        let err = LuaScripting::execute(r#"
+            local pxs = require("pxs")
+            kept = pxs.Vector(1, 2)
+            kept_fire = pxs.Vector:extend()(1, 2)
+        "#, "<members>");
+        assert!(err.is_empty(), "Lua Error is not empty: {}", err);
+        set_vector_total(vector_len);
+        let err = LuaScripting::execute(r#"
+            local _ = require("pxs").Vector(0, 0)
+            assert(kept:total() == 2 and kept_fire:total() == 2, "Types, Expected the added method on live objects")
+        "#, "<members>");
+        assert!(err.is_empty(), "Lua Error is not empty: {}", err);
+        set_vector_total(vector_sum);
+        let err = LuaScripting::execute(r#"
+            local _ = require("pxs").Vector(0, 0)
+            assert(kept:total() == 3 and kept_fire:total() == 3, "Types, Expected the replaced method on live objects")
+        "#, "<members>");
+        assert!(err.is_empty(), "Lua Error is not empty: {}", err);
+
+        // Source modules
+        let helpers_name = create_raw_string!("helpers.text");
+        let helpers_source = create_raw_string!("local M = {}\nfunction M.shout(s) return string.upper(s) .. '!' end\nfunction M.fail() error('boom') end\nreturn M");
+        let res = pxs_addsourcemod(pxs_Runtime::pxs_Lua, helpers_name, helpers_source);
+        assert!(unsafe { CStr::from_ptr(res) }.is_empty(), "Source modules, Expected helpers.text to compile");
+        pxs_freestr(res);
+        free_raw_string!(helpers_name);
+        free_raw_string!(helpers_source);
+        let err = LuaScripting::execute(r#"
+            local text = require("helpers.text")
+            assert(text.shout("hi") == "HI!", "Source modules, Expected HI!")
+            local ok, err = pcall(text.fail)
+            assert(not ok and string.find(tostring(err), "helpers/text.lua", 1, true), "Source modules, Expected the file name in errors")
+        "#, "<helpers>");
+        assert!(err.is_empty(), "Lua Error is not empty: {}", err);
+
+        let broken_name = create_raw_string!("broken");
+        let broken_source = create_raw_string!("return {");
+        let res = pxs_addsourcemod(pxs_Runtime::pxs_Lua, broken_name, broken_source);
+        assert!(unsafe { CStr::from_ptr(res) }.to_str().unwrap().contains("broken.lua"), "Source modules, Expected a syntax error");
+        pxs_freestr(res);
+        free_raw_string!(broken_name);
+        free_raw_string!(broken_source);
+
+        // Resolved modules
+        pxs_setmodresolver(lazy_resolver);
+        let err = LuaScripting::execute(r#"
+            assert(require("lazy_items").count() == 1, "Resolved modules, Expected count 1")
+            assert(require("lazy_items").count() == 1, "Resolved modules, Expected count 1 again")
+            assert(not pcall(require, "nowhere"), "Resolved modules, Expected require to fail")
+        "#, "<resolver>");
+        assert!(err.is_empty(), "Lua Error is not empty: {}", err);
+        assert_eq!(LAZY_RESOLVES.load(Ordering::SeqCst), 1, "Resolved modules, Expected one resolve");
+
+        // Runtime masks
+        add_masked_modules();
+        let err = LuaScripting::execute(r#"
+            assert(not pcall(require, "py_only"), "Runtime masks, Expected py_only to be missing")
+            local masked = require("masked")
+            assert(masked.both() == 1 and masked.lua_fn() == 1, "Runtime masks, Expected both and lua_fn")
+            assert(masked.py_fn == nil, "Runtime masks, Expected py_fn to be missing")
+        "#, "<masks>");
+        assert!(err.is_empty(), "Lua Error is not empty: {}", err);
+
+        // Constants and frozen modules
+        add_settings_module();
+        let err = LuaScripting::execute(r#"
+            local ok, err = pcall(function() require("pxs").name = "x" end)
+            assert(not ok and string.find(tostring(err), "module 'pxs' is read-only", 1, true), "Frozen, Expected pxs to be read-only")
+            local settings = require("settings")
+            settings.motd = "bye"
+            assert(settings.motd == "bye", "Frozen, Expected motd to change")
+            ok, err = pcall(function() settings.MAX_PLAYERS = 1 end)
+            assert(not ok and string.find(tostring(err), "constant 'MAX_PLAYERS' is read-only", 1, true), "Frozen, Expected a constant error")
+            assert(settings.MAX_PLAYERS == 4, "Frozen, Expected MAX_PLAYERS to stay 4")
+            local count = 0
+            for _ in pairs(settings) do count = count + 1 end
+            assert(count == 2, "Frozen, Expected pairs to see 2 values")
+        "#, "<frozen>");
+        assert!(err.is_empty(), "Lua Error is not empty: {}", err);
+
+        // Signatures
+        add_docs_module();
+        let err = LuaScripting::execute(r#"
+            local docs = require("docs")
+            local doc = pxs.doc(docs.greet)
+            assert(doc == "greet(name: string, times: integer = 1) -> string\n\nGreet someone.", "Signatures, wrong doc: " .. tostring(doc))
+            assert(pxs.doc(print) == nil, "Signatures, Expected no doc for print")
+            assert(docs.greet("a") == "hi a", "Signatures, Expected the default times")
+            assert(docs.greet("a", 2.0) == "hi ahi a", "Signatures, Expected 2.0 to be a int")
+            assert(docs.greet("a", nil) == "hi a", "Signatures, Expected nil to take the default")
+            local ok, err = pcall(docs.greet, "a", "b")
+            assert(not ok and string.find(tostring(err), "docs.greet() argument 2 'times': expected integer, found string", 1, true), "Signatures, wrong error: " .. tostring(err))
+            ok, err = pcall(docs.greet)
+            assert(not ok and string.find(tostring(err), "docs.greet() missing argument 1 'name' (string)", 1, true), "Signatures, wrong error: " .. tostring(err))
+            ok, err = pcall(docs.greet, "a", 1, 2)
+            assert(not ok and string.find(tostring(err), "at most 2 arguments (3 given)", 1, true), "Signatures, wrong error: " .. tostring(err))
+            ok, err = pcall(docs.greet, "a", 1.5)
+            assert(not ok, "Signatures, Expected 1.5 to not be a int")
+            ok, err = pcall(docs.greet, "a", 2^63)
+            assert(not ok, "Signatures, Expected 2^63 to not be a int")
+            assert(docs.count({}) == 0 and docs.count({1, 2}) == 2, "Signatures, Expected {} to be a table")
+            ok, err = pcall(docs.count, 1)
+            assert(not ok and string.find(tostring(err), "docs.count() argument 1 'items': expected table, found integer", 1, true), "Signatures, wrong error: " .. tostring(err))
+            ok, err = pcall(docs.greet, require("pxs").Vec2(1, 2))
+            assert(not ok and string.find(tostring(err), "expected string, found Vec2", 1, true), "Signatures, wrong error: " .. tostring(err))
+        "#, "<docs>");
+        assert!(err.is_empty(), "Lua Error is not empty: {}", err);
+
+        // Stubs
+        let stubs_dir = create_raw_string!("stubs/");
+        assert_eq!(pxs_genstubs(pxs_Runtime::pxs_Lua, stubs_dir), -1, "Stubs, Expected -1 without a file writer");
+        pxs_set_filewriter(stub_writer);
+        assert_eq!(pxs_genstubs(pxs_Runtime::pxs_Lua, stubs_dir), 0, "Stubs, Expected every Lua stub to be written");
+        let docs_stub = get_stub("stubs/docs.lua");
+        assert!(docs_stub.starts_with("---@meta docs"), "Stubs, Expected a meta file: {docs_stub}");
+        assert!(docs_stub.contains("--- Greet someone.\n---@param name string\n---@param times? integer\n---@return string\nfunction M.greet(name, times) end"), "Stubs, wrong greet: {docs_stub}");
+        let pxs_stub = get_stub("stubs/pxs.lua");
+        assert!(pxs_stub.contains("M.math = require(\"pxs.math\")"), "Stubs, Expected pxs.math: {pxs_stub}");
+        assert!(pxs_stub.contains("---@enum pxs.Damage"), "Stubs, Expected the Damage enum: {pxs_stub}");
+        assert!(pxs_stub.contains("M[\"repeat\"] = function("), "Stubs, Expected a quoted keyword: {pxs_stub}");
+        get_stub("stubs/pxs.math.lua");
+        assert_eq!(pxs_genstubs(pxs_Runtime::pxs_JavaScript, stubs_dir), 0, "Stubs, Expected every JavaScript stub to be written");
+        let docs_stub = get_stub("stubs/docs.d.ts");
+        assert!(docs_stub.contains("declare module \"docs\" {\n    /** Greet someone. */\n    export function greet(name: string, times?: number): string;"), "Stubs, wrong greet: {docs_stub}");
+        free_raw_string!(stubs_dir);
+
+        // API description
+        let api_path = create_raw_string!("api.json");
+        assert!(pxs_genapi(api_path) > 0, "API, Expected modules");
+        free_raw_string!(api_path);
+        let modules = pixelscript::shared::api::parse_api(&get_stub("api.json")).expect("API, Expected valid JSON");
+        let greet = modules.iter().find(|m| m.name == "docs").and_then(|m| m.member("greet")).expect("API, Expected docs.greet");
+        let signature = greet.signature.as_ref().expect("API, Expected the greet signature");
+        assert_eq!(signature.signature_line("greet", pxs_Runtime::pxs_Lua), "greet(name: string, times: integer = 1) -> string", "API, wrong greet");
+        assert_eq!(signature.doc, "Greet someone.", "API, wrong greet doc");
+        let pxs = modules.iter().find(|m| m.name == "pxs").expect("API, Expected pxs");
+        assert!(pxs.member("math").is_some_and(|m| m.kind == pixelscript::shared::api::ApiKind::Module), "API, Expected pxs.math");
+
+        // Only a writer set with pxs_set_filewriter_ex reports failed writes
+        let stubs_dir = create_raw_string!("stubs/");
+        let stub_count = STUBS.lock().unwrap().len();
+        pxs_set_filewriter_ex(failing_writer);
+        assert!(pxs_genstubs(pxs_Runtime::pxs_Lua, stubs_dir) > 0, "Stubs, Expected failed writes to be counted");
+        free_raw_string!(stubs_dir);
+        let api_path = create_raw_string!("api.json");
+        assert_eq!(pxs_genapi(api_path), -1, "API, Expected -1 for a failed write");
+        free_raw_string!(api_path);
+        assert_eq!(STUBS.lock().unwrap().len(), stub_count, "Stubs, Expected the ex writer to be used over the plain one");
+
+        // Clearing the state forgets the modules, removing one afterwards leaves new callbacks alone.
+        pxs_clearstate(false);
+        add_version_module("before_clear", dlc_v1);
+        pxs_clearstate(false);
+        add_version_module("after_clear", dlc_v2);
+        let before_name = create_raw_string!("before_clear");
+        assert!(!pxs_removemod(before_name), "Clear state, Expected before_clear to be cleared");
+        free_raw_string!(before_name);
+        let clear_err = LuaScripting::execute(r#"
+            local after = require('after_clear')
+            assert(after.version() == 2, "Clear state, Expected after_clear.version to work")
+        "#, "<clear_state>");
+        assert!(clear_err.is_empty(), "Lua Error is not empty: {}", clear_err);
 
         pxs_finalize();
     }
@@ -12,11 +12,12 @@ mod tests {
     use std::{
         ffi::{CStr, CString, c_char, c_void},
         ptr,
+        sync::{Mutex, atomic::{AtomicI64, AtomicPtr, AtomicUsize, Ordering}},
     };
 
     use pixelscript::{
         python::PythonScripting,
-        shared::{PixelScript, PtrMagic, pxs_DirHandle, var::{pxs_Var, pxs_VarT}},
+        shared::{PixelScript, PtrMagic, pxs_Runtime, args::{FromPxsVar, HostRef, HostType}, func::pxs_Func, module::pxs_Module, object::{pxs_FieldType, pxs_OperatorKind}, signature::pxs_ParamType, pxs_DirHandle, types::{pxs_PixelType, pxs_ValueType}, var::{pxs_Var, pxs_VarT}},
         *,
     };
     /// Create a raw string from &str.
@@ -117,6 +118,13 @@ mod tests {
             let get_name_raw = create_raw_string!("get_name");
             pxs_object_addfunc(pixel_object, set_name_raw, set_name, opaque);
             pxs_object_addfunc(pixel_object, get_name_raw, get_name, opaque);
+            // Properties
+            let name_raw = create_raw_string!("name");
+            let display_name_raw = create_raw_string!("display_name");
+            pxs_object_addprop(pixel_object, name_raw, get_name, Some(set_name), opaque);
+            pxs_object_addprop(pixel_object, display_name_raw, get_name, None, opaque);
+            free_raw_string!(name_raw);
+            free_raw_string!(display_name_raw);
             // Save...
             let var = pxs_newhost(pixel_object);
 
@@ -127,6 +135,139 @@ mod tests {
         }
     }
 
+    #[derive(Clone, Copy)]
+    struct Vector {
+        x: f64,
+        y: f64,
+        hits: i32,
+    }
+
+    impl PtrMagic for Vector {}
+
+    impl HostType for Vector {
+        const TYPE_NAME: &'static str = "Vector";
+    }
+
+    static VECTORS_FREED: AtomicUsize = AtomicUsize::new(0);
+
+    pub extern "C" fn free_vector(ptr: *mut c_void) {
+        let _ = Vector::from_raw(ptr as *mut Vector);
+        VECTORS_FREED.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// The Vector type, registered in `test_add_module`.
+    static VECTOR_TYPE: AtomicPtr<pxs_PixelType> = AtomicPtr::new(ptr::null_mut());
+
+    fn register_vector_type() {
+        let type_name = create_raw_string!("Vector");
+        let vector_type = pxs_newtype(type_name);
+        free_raw_string!(type_name);
+        pxs_type_addop(vector_type, pxs_OperatorKind::pxs_OpAdd, vector_add, ptr::null_mut());
+        pxs_type_addop(vector_type, pxs_OperatorKind::pxs_OpMul, vector_mul, ptr::null_mut());
+        pxs_type_addop(vector_type, pxs_OperatorKind::pxs_OpEq, vector_eq, ptr::null_mut());
+        pxs_type_addop(vector_type, pxs_OperatorKind::pxs_OpToString, vector_tostring, ptr::null_mut());
+        pxs_type_addop(vector_type, pxs_OperatorKind::pxs_OpLen, vector_len, ptr::null_mut());
+        pxs_type_addop(vector_type, pxs_OperatorKind::pxs_OpIndex, vector_index, ptr::null_mut());
+        pxs_type_addop(vector_type, pxs_OperatorKind::pxs_OpNext, vector_next, ptr::null_mut());
+        pxs_type_setctor(vector_type, new_vector, ptr::null_mut());
+
+        let x_name = create_raw_string!("x");
+        pxs_object_addfield(vector_type, x_name, std::mem::offset_of!(Vector, x), pxs_FieldType::pxs_FieldF64, false);
+        free_raw_string!(x_name);
+        let y_name = create_raw_string!("y");
+        pxs_object_addfield(vector_type, y_name, std::mem::offset_of!(Vector, y), pxs_FieldType::pxs_FieldF64, true);
+        free_raw_string!(y_name);
+        let hits_name = create_raw_string!("hits");
+        pxs_object_addfield(vector_type, hits_name, std::mem::offset_of!(Vector, hits), pxs_FieldType::pxs_FieldI32, false);
+        free_raw_string!(hits_name);
+
+        let sum_name = create_raw_string!("sum");
+        pxs_type_addmethod(vector_type, sum_name, vector_sum, ptr::null_mut());
+        free_raw_string!(sum_name);
+
+        let zero_name = create_raw_string!("zero");
+        pxs_type_addstatic(vector_type, zero_name, vector_zero, ptr::null_mut());
+        free_raw_string!(zero_name);
+        let dimensions_name = create_raw_string!("DIMENSIONS");
+        pxs_type_addconst(vector_type, dimensions_name, pxs_newint(2));
+        free_raw_string!(dimensions_name);
+
+        VECTOR_TYPE.store(vector_type, Ordering::SeqCst);
+    }
+
+    fn make_vector(v: Vector) -> *mut pxs_Var {
+        pxs_newinstance(
+            VECTOR_TYPE.load(Ordering::SeqCst),
+            Vector::into_raw(v) as *mut c_void,
+            free_vector,
+        )
+    }
+
+    pub extern "C" fn new_vector(args: *mut pxs_Var, _opaque: *mut c_void) -> *mut pxs_Var {
+        let (_rt, x, y): (pxs_Runtime, f64, f64) = pxs_args!(args);
+        make_vector(Vector { x, y, hits: 0 })
+    }
+
+    pub extern "C" fn vector_zero(args: *mut pxs_Var, _opaque: *mut c_void) -> *mut pxs_Var {
+        let (_rt,): (pxs_Runtime,) = pxs_args!(args);
+        make_vector(Vector { x: 0.0, y: 0.0, hits: 0 })
+    }
+
+    pub extern "C" fn vector_sum(args: *mut pxs_Var, _opaque: *mut c_void) -> *mut pxs_Var {
+        let (_rt, this): (pxs_Runtime, HostRef<Vector>) = pxs_args!(args);
+        pxs_newfloat(this.x + this.y)
+    }
+
+    pub extern "C" fn vector_add(args: *mut pxs_Var, _opaque: *mut c_void) -> *mut pxs_Var {
+        let (_rt, this, other, _reflected): (pxs_Runtime, HostRef<Vector>, HostRef<Vector>, bool) =
+            pxs_args!(args);
+        make_vector(Vector { x: this.x + other.x, y: this.y + other.y, hits: 0 })
+    }
+
+    pub extern "C" fn vector_mul(args: *mut pxs_Var, _opaque: *mut c_void) -> *mut pxs_Var {
+        let (_rt, this, scalar, _reflected): (pxs_Runtime, HostRef<Vector>, f64, bool) = pxs_args!(args);
+        make_vector(Vector { x: this.x * scalar, y: this.y * scalar, hits: 0 })
+    }
+
+    pub extern "C" fn vector_eq(args: *mut pxs_Var, _opaque: *mut c_void) -> *mut pxs_Var {
+        let (_rt, this, other, _reflected): (pxs_Runtime, HostRef<Vector>, pxs_Var, bool) = pxs_args!(args);
+        let other = HostRef::<Vector>::from_pxs_var(&other);
+        let eq = other.map(|o| o.x == this.x && o.y == this.y).unwrap_or(false);
+        pxs_Var::new_bool(eq).into_raw()
+    }
+
+    pub extern "C" fn vector_tostring(args: *mut pxs_Var, _opaque: *mut c_void) -> *mut pxs_Var {
+        let (_rt, this): (pxs_Runtime, HostRef<Vector>) = pxs_args!(args);
+        pxs_Var::new_string(format!("Vector({}, {})", this.x, this.y)).into_raw()
+    }
+
+    pub extern "C" fn vector_len(args: *mut pxs_Var, _opaque: *mut c_void) -> *mut pxs_Var {
+        let (_rt, _this): (pxs_Runtime, HostRef<Vector>) = pxs_args!(args);
+        pxs_Var::new_i64(2).into_raw()
+    }
+
+    pub extern "C" fn vector_index(args: *mut pxs_Var, _opaque: *mut c_void) -> *mut pxs_Var {
+        let (_rt, this, key): (pxs_Runtime, HostRef<Vector>, String) = pxs_args!(args);
+        match key.as_str() {
+            "x" => pxs_Var::new_f64(this.x).into_raw(),
+            "y" => pxs_Var::new_f64(this.y).into_raw(),
+            _ => pxs_Var::new_exception(format!("Vector has no component {}", key)).into_raw(),
+        }
+    }
+
+    pub extern "C" fn vector_next(args: *mut pxs_Var, _opaque: *mut c_void) -> *mut pxs_Var {
+        let (_rt, this, state): (pxs_Runtime, HostRef<Vector>, Option<i64>) = pxs_args!(args);
+        let (state, value) = match state {
+            None => (1, this.x),
+            Some(1) => (2, this.y),
+            _ => return pxs_newnull(),
+        };
+        let step = pxs_newlist();
+        pxs_listadd(step, pxs_newint(state));
+        pxs_listadd(step, pxs_newfloat(value));
+        step
+    }
+
     // Testing callbacks
     pub extern "C" fn print_wrapper(
         args: *mut pxs_Var,
@@ -176,6 +317,300 @@ mod tests {
         }
     }
 
+    pub extern "C" fn repeat_wrapper(
+        args: *mut pxs_Var,
+        _opaque: *mut c_void,
+    ) -> *mut pxs_Var {
+        let (_rt, text, count): (pxs_Runtime, String, Option<i64>) = pxs_args!(args);
+
+        pxs_Var::new_string(text.repeat(count.unwrap_or(1) as usize)).into_raw()
+    }
+
+    /// Calls `describe` on a object, which scripts override in a subclass.
+    pub extern "C" fn describe_wrapper(args: *mut pxs_Var, _opaque: *mut c_void) -> *mut pxs_Var {
+        let method = create_raw_string!("describe");
+        let res = pxs_objectcall(pxs_listget(args, 0), pxs_listget(args, 1), method, pxs_newlist());
+        free_raw_string!(method);
+        if res.is_null() { pxs_newnull() } else { res }
+    }
+
+    /// The host object shared by the Lua and Python scripts.
+    static SHARED_VECTOR: AtomicPtr<pxs_Var> = AtomicPtr::new(ptr::null_mut());
+
+    pub extern "C" fn shared_wrapper(_args: *mut pxs_Var, _opaque: *mut c_void) -> *mut pxs_Var {
+        pxs_newcopy(SHARED_VECTOR.load(Ordering::SeqCst))
+    }
+
+    /// Host buffer exposed to the scripts through a array view.
+    static mut PARTICLES: [f32; 4] = [1.0, 2.0, 3.0, 4.0];
+
+    pub extern "C" fn particles_wrapper(args: *mut pxs_Var, _opaque: *mut c_void) -> *mut pxs_Var {
+        let (_rt, readonly): (pxs_Runtime, bool) = pxs_args!(args);
+        let particles = &raw mut PARTICLES;
+        pxs_newarrayview(pxs_FieldType::pxs_FieldF32, particles as *mut c_void, 4, readonly)
+    }
+
+    static VEC2_TYPE: AtomicPtr<pxs_ValueType> = AtomicPtr::new(ptr::null_mut());
+
+    fn register_vec2_type() {
+        let name = create_raw_string!("Vec2");
+        let x = create_raw_string!("x");
+        let y = create_raw_string!("y");
+        let names = [x as *const c_char, y as *const c_char];
+        let types = [pxs_FieldType::pxs_FieldF64, pxs_FieldType::pxs_FieldF64];
+        VEC2_TYPE.store(pxs_newvaluetype(name, names.as_ptr(), types.as_ptr(), 2), Ordering::SeqCst);
+        free_raw_string!(name);
+        free_raw_string!(x);
+        free_raw_string!(y);
+    }
+
+    /// Scales a Vec2, values are copied in and out.
+    pub extern "C" fn scale_wrapper(args: *mut pxs_Var, _opaque: *mut c_void) -> *mut pxs_Var {
+        let value = pxs_listget(args, 1);
+        let factor = pxs_getfloat(pxs_listget(args, 2));
+        let res = pxs_newvalue(VEC2_TYPE.load(Ordering::SeqCst));
+        for field in ["x", "y"] {
+            let name = create_raw_string!(field);
+            let v = pxs_valueget(value, name);
+            let scaled = pxs_newfloat(pxs_getfloat(v) * factor);
+            pxs_valueset(res, name, scaled);
+            pxs_freevar(v);
+            pxs_freevar(scaled);
+            free_raw_string!(name);
+        }
+        res
+    }
+
+    /// Host state read and written by scripts through a live variable.
+    static SCORE: AtomicI64 = AtomicI64::new(10);
+
+    pub extern "C" fn score_getter(_args: *mut pxs_Var, _opaque: *mut c_void) -> *mut pxs_Var {
+        pxs_newint(SCORE.load(Ordering::SeqCst))
+    }
+
+    pub extern "C" fn score_setter(args: *mut pxs_Var, _opaque: *mut c_void) -> *mut pxs_Var {
+        SCORE.store(pxs_getint(pxs_listget(args, 1)), Ordering::SeqCst);
+        pxs_newnull()
+    }
+
+    pub extern "C" fn dlc_v1(_args: *mut pxs_Var, _opaque: *mut c_void) -> *mut pxs_Var {
+        pxs_newint(1)
+    }
+
+    pub extern "C" fn dlc_v2(_args: *mut pxs_Var, _opaque: *mut c_void) -> *mut pxs_Var {
+        pxs_newint(2)
+    }
+
+    /// Add or replace the `dlc` module, `dlc.version()` calls `version`.
+    fn add_dlc_module(version: pxs_Func, replace: bool) {
+        let module_name = create_raw_string!("dlc");
+        let module = pxs_newmod(module_name);
+        let version_name = create_raw_string!("version");
+        pxs_addfunc(module, version_name, version, ptr::null_mut());
+        if replace {
+            pxs_replacemod(module);
+        } else {
+            pxs_addmod(module);
+        }
+        free_raw_string!(module_name);
+        free_raw_string!(version_name);
+    }
+
+    /// Add or replace `Vector.total`, after scripts already hold vectors.
+    fn set_vector_total(total: pxs_Func) {
+        let total_name = create_raw_string!("total");
+        pxs_type_addmethod(VECTOR_TYPE.load(Ordering::SeqCst), total_name, total, ptr::null_mut());
+        free_raw_string!(total_name);
+    }
+
+    /// How many times the resolver was asked for `lazy_items`.
+    static LAZY_RESOLVES: AtomicUsize = AtomicUsize::new(0);
+
+    /// Builds `lazy_items` on demand, `lazy_items.count()` gives 1.
+    pub extern "C" fn lazy_resolver(name: *const c_char) -> *mut pxs_Module {
+        if unsafe { CStr::from_ptr(name) }.to_str().unwrap() != "lazy_items" {
+            return ptr::null_mut();
+        }
+        LAZY_RESOLVES.fetch_add(1, Ordering::SeqCst);
+
+        let module_name = create_raw_string!("lazy_items");
+        let module = pxs_newmod(module_name);
+        let count_name = create_raw_string!("count");
+        pxs_addfunc(module, count_name, dlc_v1, ptr::null_mut());
+        free_raw_string!(module_name);
+        free_raw_string!(count_name);
+        module
+    }
+
+    /// Add `py_only` to Python only, and `masked` where `both` is everywhere, `lua_fn` Lua only and `py_fn` Python only.
+    fn add_masked_modules() {
+        let lua_mask = pxs_Runtime::pxs_Lua.mask();
+        let py_mask = pxs_Runtime::pxs_Python.mask();
+
+        let py_only_name = create_raw_string!("py_only");
+        let py_only = pxs_newmod(py_only_name);
+        let version_name = create_raw_string!("version");
+        pxs_addfunc(py_only, version_name, dlc_v1, ptr::null_mut());
+        pxs_addmod_for(py_only, py_mask);
+
+        let masked_name = create_raw_string!("masked");
+        let masked = pxs_newmod(masked_name);
+        let both_name = create_raw_string!("both");
+        let lua_fn_name = create_raw_string!("lua_fn");
+        let py_fn_name = create_raw_string!("py_fn");
+        pxs_addfunc(masked, both_name, dlc_v1, ptr::null_mut());
+        pxs_addfunc_for(masked, lua_fn_name, dlc_v1, ptr::null_mut(), lua_mask);
+        pxs_addfunc_for(masked, py_fn_name, dlc_v1, ptr::null_mut(), py_mask);
+        pxs_addmod_for(masked, lua_mask | py_mask);
+
+        for name in [py_only_name, version_name, masked_name, both_name, lua_fn_name, py_fn_name] {
+            free_raw_string!(name);
+        }
+    }
+
+    /// Add `settings`, a module that is not frozen with the constant `MAX_PLAYERS` and the variable `motd`.
+    fn add_settings_module() {
+        let settings_name = create_raw_string!("settings");
+        let settings = pxs_newmod(settings_name);
+        let max_name = create_raw_string!("MAX_PLAYERS");
+        pxs_addconst(settings, max_name, pxs_newint(4));
+        let motd_name = create_raw_string!("motd");
+        let motd_value = create_raw_string!("hi");
+        pxs_addvar(settings, motd_name, pxs_newstring(motd_value));
+        pxs_setmodfrozen(settings, false);
+        pxs_addmod(settings);
+
+        for name in [settings_name, max_name, motd_name, motd_value] {
+            free_raw_string!(name);
+        }
+    }
+
+    /// `greet(name, times)`, trusts it's signature to check the arguments.
+    pub extern "C" fn greet_wrapper(args: *mut pxs_Var, _opaque: *mut c_void) -> *mut pxs_Var {
+        let name = pxs_getstring(pxs_listget(args, 1));
+        let greeting = format!("hi {}", unsafe { CStr::from_ptr(name) }.to_str().unwrap()).repeat(pxs_getint(pxs_listget(args, 2)) as usize);
+        pxs_freestr(name);
+        let greeting = create_raw_string!(greeting);
+        let res = pxs_newstring(greeting);
+        free_raw_string!(greeting);
+        res
+    }
+
+    /// `count(items)`, the number of list items. Tables without list items count as empty.
+    pub extern "C" fn count_wrapper(args: *mut pxs_Var, _opaque: *mut c_void) -> *mut pxs_Var {
+        pxs_newint(pxs_listlen(pxs_listget(args, 1)).max(0) as i64)
+    }
+
+    /// Add `docs` with `greet(name, times = 1)` and `count(items)`, described by signatures.
+    fn add_docs_module() {
+        let docs_name = create_raw_string!("docs");
+        let docs = pxs_newmod(docs_name);
+        let doc = create_raw_string!("Greet someone.");
+        let sig = pxs_newsig(doc);
+        let name_name = create_raw_string!("name");
+        let times_name = create_raw_string!("times");
+        pxs_sigaddparam(sig, name_name, pxs_ParamType::pxs_ParamString, ptr::null_mut());
+        assert!(pxs_sigaddparam(sig, times_name, pxs_ParamType::pxs_ParamInt, pxs_newint(1)), "Signatures, Expected a int default");
+        let items_name = create_raw_string!("items");
+        assert!(!pxs_sigaddparam(sig, items_name, pxs_ParamType::pxs_ParamList, pxs_newlist()), "Signatures, Expected a list default to be rejected");
+        free_raw_string!(items_name);
+        pxs_sigsetreturn(sig, pxs_ParamType::pxs_ParamString);
+        let greet_name = create_raw_string!("greet");
+        pxs_addfunc_ex(docs, greet_name, greet_wrapper, ptr::null_mut(), sig);
+        let count_sig = pxs_newsig(ptr::null());
+        let items_name = create_raw_string!("items");
+        let count_name = create_raw_string!("count");
+        pxs_sigaddparam(count_sig, items_name, pxs_ParamType::pxs_ParamList, ptr::null_mut());
+        pxs_sigsetreturn(count_sig, pxs_ParamType::pxs_ParamInt);
+        pxs_addfunc_ex(docs, count_name, count_wrapper, ptr::null_mut(), count_sig);
+        pxs_addmod(docs);
+
+        for name in [docs_name, doc, name_name, times_name, greet_name, items_name, count_name] {
+            free_raw_string!(name);
+        }
+    }
+
+    pub extern "C" fn free_byte(ptr: *mut c_void) {
+        let _ = unsafe { Box::from_raw(ptr as *mut u8) };
+    }
+
+    /// Constructor of the `collide` types, the type is the opaque.
+    pub extern "C" fn new_collide(_args: *mut pxs_Var, opaque: *mut c_void) -> *mut pxs_Var {
+        pxs_newinstance(opaque as *mut pxs_PixelType, Box::into_raw(Box::new(0u8)) as *mut c_void, free_byte)
+    }
+
+    pub extern "C" fn a_bfoo(_args: *mut pxs_Var, _opaque: *mut c_void) -> *mut pxs_Var {
+        pxs_Var::new_string("A.Bfoo".to_string()).into_raw()
+    }
+
+    pub extern "C" fn ab_foo(_args: *mut pxs_Var, _opaque: *mut c_void) -> *mut pxs_Var {
+        pxs_Var::new_string("AB.foo".to_string()).into_raw()
+    }
+
+    /// Add `collide` with the types `A` (method `Bfoo`) and `AB` (method `foo`), which used to share a full name.
+    ///
+    /// Returns the idx of the first `AB.foo`, replaced by the second.
+    fn add_collide_module() -> i32 {
+        let collide_name = create_raw_string!("collide");
+        let collide = pxs_newmod(collide_name);
+        let a_name = create_raw_string!("A");
+        let ab_name = create_raw_string!("AB");
+        let bfoo_name = create_raw_string!("Bfoo");
+        let foo_name = create_raw_string!("foo");
+
+        let a = pxs_newtype(a_name);
+        pxs_type_setctor(a, new_collide, a as *mut c_void);
+        pxs_type_addmethod(a, bfoo_name, a_bfoo, ptr::null_mut());
+        let ab = pxs_newtype(ab_name);
+        pxs_type_setctor(ab, new_collide, ab as *mut c_void);
+        pxs_type_addmethod(ab, foo_name, a_bfoo, ptr::null_mut());
+        let replaced_idx = unsafe { (*ab).members().methods[0].idx };
+        pxs_type_addmethod(ab, foo_name, ab_foo, ptr::null_mut());
+        pxs_addtype(collide, a);
+        pxs_addtype(collide, ab);
+        pxs_addmod(collide);
+
+        for name in [collide_name, a_name, ab_name, bfoo_name, foo_name] {
+            free_raw_string!(name);
+        }
+        replaced_idx
+    }
+
+    /// Stub files written by `pxs_genstubs`, by path.
+    static STUBS: Mutex<Vec<(String, String)>> = Mutex::new(vec![]);
+
+    unsafe extern "C" fn stub_writer(file_path: *const c_char, contents: *const c_char) {
+        let file_path = unsafe { CStr::from_ptr(file_path) }.to_str().unwrap().to_string();
+        let contents = unsafe { CStr::from_ptr(contents) }.to_str().unwrap().to_string();
+        STUBS.lock().unwrap().push((file_path, contents));
+    }
+
+    /// Writes to disk without creating directories, like a careless host.
+    unsafe extern "C" fn flat_disk_writer(file_path: *const c_char, contents: *const c_char) -> bool {
+        let file_path = unsafe { CStr::from_ptr(file_path) }.to_str().unwrap();
+        let contents = unsafe { CStr::from_ptr(contents) }.to_str().unwrap();
+        std::fs::write(file_path, contents).is_ok()
+    }
+
+    /// Writes to disk, creating the directories.
+    unsafe extern "C" fn disk_writer(file_path: *const c_char, contents: *const c_char) -> bool {
+        let file_path = std::path::Path::new(unsafe { CStr::from_ptr(file_path) }.to_str().unwrap());
+        let contents = unsafe { CStr::from_ptr(contents) }.to_str().unwrap();
+        file_path.parent().map_or(Ok(()), std::fs::create_dir_all).and_then(|_| std::fs::write(file_path, contents)).is_ok()
+    }
+
+    /// Get a written stub file.
+    fn get_stub(file_path: &str) -> String {
+        let stubs = STUBS.lock().unwrap();
+        let stub = stubs.iter().find(|(path, _)| path == file_path);
+        stub.map(|(_, contents)| contents.clone()).unwrap_or_else(|| panic!("Stubs, Expected {file_path}"))
+    }
+
+    pub extern "C" fn destroy_wrapper(args: *mut pxs_Var, _opaque: *mut c_void) -> *mut pxs_Var {
+        let destroyed = pxs_destroyobject(pxs_listget(args, 1));
+        pxs_Var::new_bool(destroyed).into_raw()
+    }
+
     unsafe extern "C" fn file_loader(file_path: *const c_char) -> *mut c_char {
         let file_path = unsafe { CStr::from_ptr(file_path).to_str().unwrap() };
 
@@ -279,11 +714,58 @@ mod tests {
         let object_name = create_raw_string!("Person");
         pxs_addobject(module, object_name, new_person, ptr::null_mut());
 
+        register_vector_type();
+        pxs_addtype(module, VECTOR_TYPE.load(Ordering::SeqCst));
+
         // Add call 
         let call_name = create_raw_string!("call_function");
         pxs_addfunc(module, call_name, call_function, ptr::null_mut());
         free_raw_string!(call_name);
 
+        // Add typed args
+        let repeat_name = create_raw_string!("repeat");
+        pxs_addfunc(module, repeat_name, repeat_wrapper, ptr::null_mut());
+        free_raw_string!(repeat_name);
+
+        let describe_name = create_raw_string!("describe");
+        pxs_addfunc(module, describe_name, describe_wrapper, ptr::null_mut());
+        free_raw_string!(describe_name);
+
+        let particles_name = create_raw_string!("particles");
+        pxs_addfunc(module, particles_name, particles_wrapper, ptr::null_mut());
+        free_raw_string!(particles_name);
+
+        register_vec2_type();
+        pxs_addvaluetype(module, VEC2_TYPE.load(Ordering::SeqCst));
+
+        let scale_name = create_raw_string!("scale");
+        pxs_addfunc(module, scale_name, scale_wrapper, ptr::null_mut());
+        free_raw_string!(scale_name);
+
+        let damage_name = create_raw_string!("Damage");
+        let members = ["Fire", "Ice", "Poison"].map(|m| create_raw_string!(m));
+        let names = members.map(|m| m as *const c_char);
+        pxs_addenum(module, damage_name, names.as_ptr(), [1, 2, 4].as_ptr(), 3);
+        free_raw_string!(damage_name);
+        for m in members {
+            free_raw_string!(m);
+        }
+
+        let score_name = create_raw_string!("score");
+        pxs_addlivevar(module, score_name, score_getter, Some(score_setter), ptr::null_mut());
+        free_raw_string!(score_name);
+        let level_name = create_raw_string!("level");
+        pxs_addlivevar(module, level_name, score_getter, None, ptr::null_mut());
+        free_raw_string!(level_name);
+
+        let destroy_name = create_raw_string!("destroy");
+        pxs_addfunc(module, destroy_name, destroy_wrapper, ptr::null_mut());
+        free_raw_string!(destroy_name);
+
+        let shared_name = create_raw_string!("shared");
+        pxs_addfunc(module, shared_name, shared_wrapper, ptr::null_mut());
+        free_raw_string!(shared_name);
+
         // Add a inner module
         let math_module_name = create_raw_string!("math");
         let math_module = pxs_newmod(math_module_name);
@@ -292,6 +774,13 @@ mod tests {
         let sub_name = create_raw_string!("sub");
         pxs_addfunc(math_module, sub_name, sub_wrapper, ptr::null_mut());
 
+        // And a variable, owned by the inner module
+        let unit_name = create_raw_string!("unit");
+        let unit_value = create_raw_string!("radians");
+        pxs_addvar(math_module, unit_name, pxs_newstring(unit_value));
+        free_raw_string!(unit_name);
+        free_raw_string!(unit_value);
+
         pxs_add_submod(module, math_module);
         pxs_addmod(module);
 
@@ -334,6 +823,11 @@ if result != 3:
 
 res = pxs.math.sub(2, 1)
 pxs.print(res)
+from pxs import math as pxs_math
+assert pxs_math is pxs.math, "Submodules, Expected from pxs import math to be pxs.math"
+assert 'math' in dir(pxs), "Submodules, Expected math in dir(pxs)"
+assert pxs_math.unit == "radians", "Submodules, Expected pxs.math.unit"
+
 if res != 1:
     raise Exception("Math, Expected 1, got " + str(res))
 
@@ -343,6 +837,17 @@ print(person.get_name())
 person.set_name("Jordan Castro")
 print(person.get_name())
 
+
+# Properties
+person.name = "Jordan C"
+if person.name != "Jordan C" or person.display_name != "Jordan C":
+    raise Exception("Properties, Expected Jordan C, got " + str(person.name))
+try:
+    person.display_name = "Nope"
+    raise Exception("Properties, Expected display_name to be read-only")
+except TypeError:
+    pass
+
 print(type(person).__name__)
 print(type(pxs.Person).__name__)
 
@@ -355,14 +860,508 @@ def get_pi():
     return 3.1459
 
 print(pxs.call_function(get_pi))
+
+# Operators
+v = pxs.Vector(1, 2) + pxs.Vector(3, 4)
+if str(v) != "Vector(4, 6)" or len(v) != 2 or v["x"] != 4:
+    raise Exception("Operators, Expected Vector(4, 6), got " + str(v))
+if v != pxs.Vector(4, 6) or (2 * v)["y"] != 12:
+    raise Exception("Operators, Expected v == Vector(4, 6)")
+
+# Iteration
+if [c for c in v] != [4, 6]:
+    raise Exception("Iteration, Expected [4, 6]")
+it = iter(v)
+if next(it) != 4 or next(it) != 6:
+    raise Exception("Iteration, Expected next to step")
+
+# Fields
+f = pxs.Vector(1, 2)
+f.x = 5
+if f.x != 5 or f.y != 2 or f.sum() != 7:
+    raise Exception("Fields, Expected x = 5 in host memory")
+readonly = False
+try:
+    f.y = 3
+except Exception:
+    readonly = True
+if not readonly or f.y != 2:
+    raise Exception("Fields, Expected y to be read-only")
+f.hits = 3
+for big in [2 ** 40, 2.0 ** 40]:
+    try:
+        f.hits = big
+        raise Exception("Fields, Expected 2^40 to be out of range for hits")
+    except RuntimeError as e:
+        if "does not fit in a i32" not in str(e) or f.hits != 3:
+            raise Exception("Fields, wrong error: " + str(e))
+# Field builtins only take fields of the object's type, never a offset
+for call in [lambda: _pxs_getfield(f.ptr, 'x', 100000, 1), lambda: _pxs_setfield(f.ptr, 'x', 100000, 1, 1)]:
+    try:
+        call()
+        raise Exception("Fields, Expected a offset to be rejected")
+    except RuntimeError as e:
+        if "expects a object id" not in str(e):
+            raise Exception("Fields, wrong error: " + str(e))
+try:
+    _pxs_getfield(f.ptr, 'nope')
+    raise Exception("Fields, Expected a unknown field error")
+except RuntimeError as e:
+    if "'Vector' has no field 'nope'" not in str(e):
+        raise Exception("Fields, wrong error: " + str(e))
+try:
+    _pxs_setfield(f.ptr, 'y', 3)
+    raise Exception("Fields, Expected y to be read-only through _pxs_setfield")
+except RuntimeError as e:
+    if "read-only" not in str(e) or f.y != 2:
+        raise Exception("Fields, wrong error: " + str(e))
+if _pxs_getfield(f.ptr, 'x') != 5:
+    raise Exception("Fields, Expected _pxs_getfield by name")
+# Handles are only created by the host
+if "_pxs_handle" in dir(__import__("builtins")):
+    raise Exception("Handles, Expected _pxs_handle to not be a builtin")
+try:
+    _pxs_rewrap(f, int)
+    raise Exception("Handles, Expected _pxs_rewrap to reject a class that is not a Vector")
+except RuntimeError as e:
+    if "subclass of 'Vector'" not in str(e):
+        raise Exception("Handles, wrong error: " + str(e))
+try:
+    _pxs_rewrap(f.ptr, type(f))
+    raise Exception("Handles, Expected _pxs_rewrap to reject a plain id")
+except RuntimeError as e:
+    if "expects a host object" not in str(e):
+        raise Exception("Handles, wrong error: " + str(e))
+
+# Array views
+particles = pxs.particles(False)
+if len(particles) != 4 or particles[0] != 1 or particles[-1] != 4:
+    raise Exception("Array views, Expected 4 particles")
+for i in range(len(particles)):
+    particles[i] = particles[i] * 2
+if [p for p in particles] != [2, 4, 6, 8]:
+    raise Exception("Array views, Expected [2, 4, 6, 8]")
+try:
+    particles[4] = 1
+    raise Exception("Array views, Expected a bounds error")
+except RuntimeError as e:
+    if "out of range" not in str(e):
+        raise Exception("Array views, wrong error: " + str(e))
+try:
+    pxs.particles(True)[0] = 0
+    raise Exception("Array views, Expected a read-only error")
+except RuntimeError as e:
+    if "read-only" not in str(e):
+        raise Exception("Array views, wrong error: " + str(e))
+
+# Value types
+v = pxs.Vec2(1, 2)
+if v.x != 1 or v.y != 2 or repr(v) != "Vec2(x=1, y=2)":
+    raise Exception("Values, Expected a Vec2 got " + repr(v))
+v.x = 3
+scaled = pxs.scale(v, 2)
+if scaled != pxs.Vec2(6.0, 4.0) or not isinstance(scaled, pxs.Vec2) or v.x != 3 or pxs.Vec2().y != 0:
+    raise Exception("Values, Expected a scaled copy got " + repr(scaled))
+v.y = "a"
+try:
+    pxs.scale(v, 2)
+    raise Exception("Values, Expected a validation error")
+except RuntimeError as e:
+    if "Vec2.y" not in str(e):
+        raise Exception("Values, wrong error: " + str(e))
+
+# Enums
+fire = pxs.Damage.Fire
+if str(fire) != "Damage.Fire" or fire.name != "Fire" or fire.value != 1 or fire != 1:
+    raise Exception("Enums, Expected Damage.Fire got " + str(fire))
+if pxs.Damage(4) is not pxs.Damage.Poison or pxs.Damage["Ice"].value != 2 or 3 in pxs.Damage or 4 not in pxs.Damage or len(pxs.Damage) != 3:
+    raise Exception("Enums, Expected reverse lookup and membership")
+if pxs.add(fire, pxs.Damage.Ice) != 3:
+    raise Exception("Enums, Expected members to cross as their value")
+if [m.name for m in pxs.Damage] != ["Fire", "Ice", "Poison"]:
+    raise Exception("Enums, Expected members in order")
+try:
+    pxs.Damage.Fire = 3
+    raise Exception("Enums, Expected a frozen enum")
+except TypeError:
+    pass
+
+# Live variables
+if pxs.score != 10:
+    raise Exception("Live variables, Expected 10")
+pxs.score = pxs.score + 5
+if pxs.score != 15 or pxs.level != 15:
+    raise Exception("Live variables, Expected the setter to write the host value")
+try:
+    pxs.level = 1
+    raise Exception("Live variables, Expected level to be read-only")
+except RuntimeError as e:
+    if "read-only" not in str(e):
+        raise Exception("Live variables, wrong error: " + str(e))
+
+# Types
+zero = pxs.Vector.zero()
+if str(zero) != "Vector(0, 0)" or pxs.Vector.DIMENSIONS != 2:
+    raise Exception("Types, Expected Vector(0, 0) and 2 dimensions")
+if not isinstance(zero, pxs.Vector) or isinstance(pxs.Person("Jordan"), pxs.Vector):
+    raise Exception("Types, Expected isinstance to check the type")
+
+# Subclasses
+class FireVector(pxs.Vector):
+    def describe(self):
+        return "fire " + str(self)
+fv = FireVector(1, 2)
+if fv.describe() != "fire Vector(1, 2)" or fv["x"] != 1 or fv.sum() != 3:
+    raise Exception("Subclass, Expected overrides and host methods to work")
+if not isinstance(fv, FireVector) or not isinstance(fv, pxs.Vector) or isinstance(zero, FireVector):
+    raise Exception("Subclass, Expected isinstance to follow the subclass")
+if pxs.describe(fv) != "fire Vector(1, 2)":
+    raise Exception("Subclass, Expected the host to call the override")
+
+# Temporaries are freed by the GC
+for i in range(10):
+    _ = pxs.Vector(i, i) + pxs.Vector(i, i)
+_ = None
+import gc
+gc.collect()
+
+# Destroyed objects raise
+doomed = pxs.Vector(1, 1)
+if not pxs.destroy(doomed) or pxs.destroy(doomed):
+    raise Exception("Destroy, Expected the first destroy only to succeed")
+try:
+    doomed + doomed
+    raise Exception("Destroy, Expected a destroyed error")
+except RuntimeError as e:
+    if "object was destroyed" not in str(e):
+        raise Exception("Destroy, wrong error: " + str(e))
+
+# Typed args
+if pxs.repeat("ab", 2) != "abab" or pxs.repeat("ab") != "ab":
+    raise Exception("Typed args, Expected abab and ab")
+try:
+    pxs.repeat(5)
+    raise Exception("Typed args, Expected a type error")
+except RuntimeError as e:
+    if "Argument 1: expected string" not in str(e):
+        raise Exception("Typed args, wrong error: " + str(e))
+try:
+    pxs.repeat("ab", 1, 2)
+    raise Exception("Typed args, Expected a arity error")
+except RuntimeError as e:
+    if "at most 3 arguments" not in str(e):
+        raise Exception("Typed args, wrong error: " + str(e))
+try:
+    pxs.repeat("ab", 2.0 ** 63)
+    raise Exception("Typed args, Expected a range error")
+except RuntimeError as e:
+    if "does not fit in a i64" not in str(e):
+        raise Exception("Typed args, wrong error: " + str(e))
+try:
+    pxs.Vector.sum(pxs.Person("Jordan"))
+    raise Exception("Typed args, Expected the wrong type to be rejected")
+except RuntimeError as e:
+    if "expected a Vector object, found Person" not in str(e):
+        raise Exception("Typed args, wrong error: " + str(e))
+class Forged:
+    ptr = 123456789
+try:
+    pxs.Vector.sum(Forged())
+    raise Exception("Typed args, Expected a forged handle to be rejected")
+except RuntimeError as e:
+    if "object was destroyed" not in str(e):
+        raise Exception("Typed args, wrong error: " + str(e))
         "#;
         let err = PythonScripting::execute(py_code, "<test>");
+        assert!(VECTORS_FREED.load(Ordering::SeqCst) >= 30, "Vectors were not freed by the GC");
+        assert_eq!(SCORE.load(Ordering::SeqCst), 15, "Live variables, Expected the script to write the score");
+        assert_eq!(unsafe { *(&raw const PARTICLES) }, [2.0, 4.0, 6.0, 8.0], "Array views, Expected writes in the host buffer");
+
+        // Typed args, host side
+        let vector = make_vector(Vector { x: 1.0, y: 2.0, hits: 0 });
+        let person_args = pxs_newlist();
+        let person_name = create_raw_string!("Jordan");
+        pxs_listadd(person_args, pxs_newint(pxs_Runtime::pxs_Python as i64));
+        pxs_listadd(person_args, pxs_newstring(person_name));
+        free_raw_string!(person_name);
+        let person = new_person(person_args, ptr::null_mut());
+        let (vector_var, person_var) = unsafe { (pxs_Var::from_borrow(vector), pxs_Var::from_borrow(person)) };
+        assert!(HostRef::<Vector>::from_pxs_var(vector_var).is_ok(), "Typed args, Expected a Vector");
+        let arg_err = HostRef::<Vector>::from_pxs_var(person_var).err().map(|e| e.to_string()).unwrap_or_default();
+        assert_eq!(arg_err, "expected Vector, found Person", "Typed args, Expected the wrong type to be rejected");
+        let forged = pxs_Var::new_i64(vector_var.get_object_ptr());
+        assert!(HostRef::<Vector>::from_pxs_var(&forged).is_err(), "Typed args, Expected a int handle to be rejected");
+        let arg_err = i32::from_pxs_var(&pxs_Var::new_i64(1 << 40)).err().map(|e| e.to_string()).unwrap_or_default();
+        assert_eq!(arg_err, "int 1099511627776 does not fit in a i32", "Typed args, Expected a range error");
+        pxs_freevar(vector);
+        pxs_freevar(person);
+        pxs_freevar(person_args);
+
+        // Instantiate a script subclass from the host
+        let runtime = pxs_newint(pxs_Runtime::pxs_Python as i64);
+        let class_name = create_raw_string!("FireVector");
+        let ctor_args = pxs_newlist();
+        pxs_listadd(ctor_args, pxs_newfloat(3.0));
+        pxs_listadd(ctor_args, pxs_newfloat(4.0));
+        let fire = pxs_instantiate(runtime, class_name, ctor_args);
+        free_raw_string!(class_name);
+
+        let method = create_raw_string!("describe");
+        let described = pxs_objectcall(runtime, fire, method, pxs_newlist());
+        free_raw_string!(method);
+        let method = create_raw_string!("sum");
+        let sum = pxs_objectcall(runtime, fire, method, pxs_newlist());
+        free_raw_string!(method);
+
+        assert!(!described.is_null() && !sum.is_null(), "Subclass, Expected the host calls to succeed");
+        let described_str = pxs_getstring(described);
+        assert_eq!(unsafe { CStr::from_ptr(described_str) }.to_str().unwrap(), "fire Vector(3, 4)");
+        assert_eq!(pxs_getfloat(sum), 7.0);
+        pxs_freestr(described_str);
+        pxs_freevar(described);
+        pxs_freevar(sum);
+        pxs_freevar(fire);
+        pxs_freevar(runtime);
+
+        // One host object wrapped by a Lua and a Python script at the same time.
+        #[cfg(feature = "lua")]
+        {
+            SHARED_VECTOR.store(make_vector(Vector { x: 5.0, y: 6.0, hits: 0 }), Ordering::SeqCst);
+            let lua_err = pixelscript::lua::LuaScripting::execute(
+                r#"
+                local pxs = require('pxs')
+                player = pxs.shared()
+                assert(rawequal(player, pxs.shared()), "Lua, Expected the same table")
+                "#,
+                "<shared>",
+            );
+            assert!(lua_err.is_empty(), "Lua Error is not empty: {}", lua_err);
+
+            let py_err = PythonScripting::execute(
+                r#"
+import gc
+import pxs
+player = pxs.shared()
+if player is not pxs.shared():
+    raise Exception("Python, Expected the same wrapper")
+# A kept handle keeps the wrapper alive
+player.tag = "kept"
+handle = player._pxs_handle
+del player
+gc.collect()
+junk = [[i] for i in range(1000)]
+if pxs.shared()._pxs_handle is not handle or getattr(pxs.shared(), "tag", None) != "kept":
+    raise Exception("Python, Expected the kept handle to keep the wrapper")
+del handle, junk
+gc.collect()
+"#,
+                "<shared>",
+            );
+            assert!(py_err.is_empty(), "Python Error is not empty: {}", py_err);
+
+            // Python released it's wrapper, Lua's is untouched.
+            let lua_err = pixelscript::lua::LuaScripting::execute(
+                r#"
+                local pxs = require('pxs')
+                collectgarbage()
+                assert(rawequal(player, pxs.shared()), "Lua, Expected the same table after Python released it")
+                assert(player:sum() == 11, "Lua, Expected 11")
+                player = nil
+                "#,
+                "<shared>",
+            );
+            assert!(lua_err.is_empty(), "Lua Error is not empty: {}", lua_err);
+
+            let py_err = PythonScripting::execute(
+                r#"
+import pxs
+if pxs.shared().sum() != 11:
+    raise Exception("Python, Expected 11")
+"#,
+                "<shared>",
+            );
+            assert!(py_err.is_empty(), "Python Error is not empty: {}", py_err);
+            pxs_freevar(SHARED_VECTOR.swap(ptr::null_mut(), Ordering::SeqCst));
+        }
 
         pxs_startthread();
         pxs_startthread();
         pxs_stopthread();
         pxs_stopthread();
 
+        // Hot-swap a module, existing imports see the new functions.
+        add_dlc_module(dlc_v1, false);
+        let dlc_err = PythonScripting::execute("import dlc\nif dlc.version() != 1:\n    raise Exception('Modules, Expected version 1')", "<dlc>");
+        assert!(dlc_err.is_empty(), "Python Error is not empty: {}", dlc_err);
+        add_dlc_module(dlc_v2, true);
+        let dlc_err = PythonScripting::execute("if dlc.version() != 2:\n    raise Exception('Modules, Expected version 2')", "<dlc>");
+        assert!(dlc_err.is_empty(), "Python Error is not empty: {}", dlc_err);
+        let dlc_name = create_raw_string!("dlc");
+        assert!(pxs_removemod(dlc_name), "Modules, Expected dlc to be removed");
+        assert!(!pxs_removemod(dlc_name), "Modules, Expected dlc to be removed once");
+        free_raw_string!(dlc_name);
+        let dlc_err = PythonScripting::execute(r#"
+try:
+    dlc.version()
+    raise Exception("Modules, Expected dlc to be removed")
+except AttributeError:
+    pass
+"#, "<dlc>");
+        assert!(dlc_err.is_empty(), "Python Error is not empty: {}", dlc_err);
+
+        // Members added to a type in use
+        let members_err = PythonScripting::execute(r#"
+import pxs
+class KeptVector(pxs.Vector):
+    pass
+kept = pxs.Vector(1, 2)
+kept_sub = KeptVector(1, 2)
+"#, "<members>");
+        assert!(members_err.is_empty(), "Python Error is not empty: {}", members_err);
+        set_vector_total(vector_len);
+        let members_err = PythonScripting::execute(r#"
+_ = pxs.Vector(0, 0)
+assert kept.total() == 2 and kept_sub.total() == 2, "Types, Expected the added method on live objects"
+assert isinstance(kept, pxs.Vector), "Types, Expected the class to stay the same"
+"#, "<members>");
+        assert!(members_err.is_empty(), "Python Error is not empty: {}", members_err);
+        set_vector_total(vector_sum);
+        let members_err = PythonScripting::execute(r#"
+_ = pxs.Vector(0, 0)
+assert kept.total() == 3 and kept_sub.total() == 3, "Types, Expected the replaced method on live objects"
+"#, "<members>");
+        assert!(members_err.is_empty(), "Python Error is not empty: {}", members_err);
+
+        // Source modules
+        let helpers_name = create_raw_string!("text_helpers");
+        let helpers_source = create_raw_string!("def shout(s):\n    return s.upper() + '!'\n\ndef fail():\n    raise ValueError('boom')\n");
+        let res = pxs_addsourcemod(pxs_Runtime::pxs_Python, helpers_name, helpers_source);
+        assert!(unsafe { CStr::from_ptr(res) }.is_empty(), "Source modules, Expected text_helpers to run");
+        pxs_freestr(res);
+        free_raw_string!(helpers_name);
+        free_raw_string!(helpers_source);
+        let helpers_err = PythonScripting::execute("import text_helpers\nif text_helpers.shout('hi') != 'HI!':\n    raise Exception('Source modules, Expected HI!')", "<helpers>");
+        assert!(helpers_err.is_empty(), "Python Error is not empty: {}", helpers_err);
+        let helpers_err = PythonScripting::execute("text_helpers.fail()", "<helpers>");
+        assert!(helpers_err.contains("text_helpers.py"), "Source modules, Expected the file name in the traceback: {}", helpers_err);
+
+        let broken_name = create_raw_string!("broken");
+        let broken_source = create_raw_string!("def broken(:\n");
+        let res = pxs_addsourcemod(pxs_Runtime::pxs_Python, broken_name, broken_source);
+        assert!(unsafe { CStr::from_ptr(res) }.to_str().unwrap().contains("broken.py"), "Source modules, Expected a syntax error");
+        pxs_freestr(res);
+        free_raw_string!(broken_name);
+        free_raw_string!(broken_source);
+
+        // Resolved modules
+        pxs_setmodresolver(lazy_resolver);
+        let resolver_err = PythonScripting::execute(r#"
+import lazy_items
+assert lazy_items.count() == 1, "Resolved modules, Expected count 1"
+import lazy_items
+assert lazy_items.count() == 1, "Resolved modules, Expected count 1 again"
+try:
+    import nowhere
+    assert False, "Resolved modules, Expected import to fail"
+except ImportError:
+    pass
+"#, "<resolver>");
+        assert!(resolver_err.is_empty(), "Python Error is not empty: {}", resolver_err);
+        assert_eq!(LAZY_RESOLVES.load(Ordering::SeqCst), 1, "Resolved modules, Expected one resolve");
+
+        // Runtime masks
+        add_masked_modules();
+        let masks_err = PythonScripting::execute(r#"
+import py_only
+import masked
+assert py_only.version() == 1, "Runtime masks, Expected py_only"
+assert masked.both() == 1 and masked.py_fn() == 1, "Runtime masks, Expected both and py_fn"
+assert not hasattr(masked, 'lua_fn'), "Runtime masks, Expected lua_fn to be missing"
+"#, "<masks>");
+        assert!(masks_err.is_empty(), "Python Error is not empty: {}", masks_err);
+
+        // Constants and frozen modules
+        add_settings_module();
+        let frozen_err = PythonScripting::execute(r#"
+import pxs
+import settings
+try:
+    pxs.name = "x"
+    assert False, "Frozen, Expected pxs to be read-only"
+except AttributeError as e:
+    assert "module 'pxs' is read-only" in str(e), "Frozen, wrong error: " + str(e)
+try:
+    del pxs.name
+    assert False, "Frozen, Expected del to fail"
+except AttributeError:
+    pass
+settings.motd = "bye"
+assert settings.motd == "bye", "Frozen, Expected motd to change"
+try:
+    settings.MAX_PLAYERS = 1
+    assert False, "Frozen, Expected a constant error"
+except AttributeError as e:
+    assert "constant 'MAX_PLAYERS' is read-only" in str(e), "Frozen, wrong error: " + str(e)
+assert settings.MAX_PLAYERS == 4, "Frozen, Expected MAX_PLAYERS to stay 4"
+"#, "<frozen>");
+        assert!(frozen_err.is_empty(), "Python Error is not empty: {}", frozen_err);
+
+        // Signatures
+        add_docs_module();
+        let docs_err = PythonScripting::execute(r#"
+import docs
+import pxs
+doc = docs.greet.__doc__
+assert doc == "greet(name: str, times: int = 1) -> str\n\nGreet someone.", "Signatures, wrong doc: " + str(doc)
+assert docs.greet("a") == "hi a", "Signatures, Expected the default times"
+assert docs.greet("a", 2.0) == "hi ahi a", "Signatures, Expected 2.0 to be a int"
+assert docs.count({}) == 0 and docs.count([1, 2]) == 2, "Signatures, Expected {} to be a list"
+for args, msg in [(("a", "b"), "docs.greet() argument 2 'times': expected int, found str"), ((), "docs.greet() missing argument 1 'name' (str)"), (("a", 1, 2), "at most 2 arguments (3 given)"), (("a", 2.0 ** 63), "expected int, found float"), ((pxs.Vec2(1, 2),), "expected str, found Vec2")]:
+    try:
+        docs.greet(*args)
+        assert False, "Signatures, Expected a error for " + str(args)
+    except RuntimeError as e:
+        assert msg in str(e), "Signatures, wrong error: " + str(e)
+"#, "<docs>");
+        assert!(docs_err.is_empty(), "Python Error is not empty: {}", docs_err);
+
+        // Types with names that run together
+        let replaced_idx = add_collide_module();
+        assert!(unsafe { pixelscript::shared::func::call_function(replaced_idx, vec![]) }.is_exception(), "Types, Expected the replaced method to be released");
+        let collide_err = PythonScripting::execute(r#"
+import collide
+assert collide.A().Bfoo() == "A.Bfoo", "Types, wrong A.Bfoo"
+assert collide.AB().foo() == "AB.foo", "Types, wrong AB.foo"
+"#, "<collide>");
+        assert!(collide_err.is_empty(), "Python Error is not empty: {}", collide_err);
+
+        // Stubs
+        let stubs_dir = create_raw_string!("stubs");
+        pxs_set_filewriter(stub_writer);
+        assert_eq!(pxs_genstubs(pxs_Runtime::pxs_Python, stubs_dir), 0, "Stubs, Expected every Python stub to be written");
+        let docs_stub = get_stub("stubs/docs.pyi");
+        assert!(docs_stub.contains("def greet(name: str, times: int = 1) -> str:\n    \"\"\"Greet someone.\"\"\""), "Stubs, wrong greet: {docs_stub}");
+        let pxs_stub = get_stub("stubs/pxs/__init__.pyi");
+        assert!(pxs_stub.contains("from . import math as math"), "Stubs, Expected pxs.math: {pxs_stub}");
+        assert!(pxs_stub.contains("class Damage:"), "Stubs, Expected the Damage enum: {pxs_stub}");
+        get_stub("stubs/pxs/math.pyi");
+        let settings_stub = get_stub("stubs/settings.pyi");
+        assert!(settings_stub.contains("MAX_PLAYERS: Final[int]\nmotd: str"), "Stubs, wrong settings: {settings_stub}");
+        free_raw_string!(stubs_dir);
+
+        // Nested modules need directories, writers that can not create them report errors
+        let disk_dir = std::env::temp_dir().join("pxs_test_stubs");
+        let _ = std::fs::remove_dir_all(&disk_dir);
+        std::fs::create_dir_all(&disk_dir).unwrap();
+        let disk_dir_raw = create_raw_string!(disk_dir.to_str().unwrap());
+        pxs_set_filewriter_ex(flat_disk_writer);
+        assert!(pxs_genstubs(pxs_Runtime::pxs_Python, disk_dir_raw) > 0, "Stubs, Expected pxs/__init__.pyi to fail without its directory");
+        pxs_set_filewriter_ex(disk_writer);
+        assert_eq!(pxs_genstubs(pxs_Runtime::pxs_Python, disk_dir_raw), 0, "Stubs, Expected every Python stub on disk");
+        assert!(disk_dir.join("pxs").join("__init__.pyi").is_file(), "Stubs, Expected pxs/__init__.pyi on disk");
+        free_raw_string!(disk_dir_raw);
+        let _ = std::fs::remove_dir_all(&disk_dir);
+
         pxs_finalize();
         assert!(err.is_empty(), "Python Error is not empty: {}", err);
     }